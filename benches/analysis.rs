@@ -0,0 +1,48 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use alpha3d::analysis::decimate::{decimate, PREVIEW_MAX_TRIANGLES};
+use alpha3d::analysis::intersections::find_self_intersections;
+use alpha3d::analysis::thumbnail::{render_thumbnail, THUMBNAIL_SIZE};
+use alpha3d::analysis::{analyze_mesh, Mesh};
+
+/// UV sphere with outward-facing triangles: `2 * segments * (rings - 1)` faces.
+fn sphere(radius: f64, rings: usize, segments: usize) -> Mesh {
+    let mut mesh = Mesh::default();
+    mesh.vertices.push([0.0, 0.0, radius]);
+    for ring in 1..rings {
+        let theta = std::f64::consts::PI * ring as f64 / rings as f64;
+        for segment in 0..segments {
+            let phi = std::f64::consts::TAU * segment as f64 / segments as f64;
+            mesh.vertices.push([radius * theta.sin() * phi.cos(), radius * theta.sin() * phi.sin(), radius * theta.cos()]);
+        }
+    }
+    mesh.vertices.push([0.0, 0.0, -radius]);
+    let bottom = mesh.vertices.len() - 1;
+    let at = |ring: usize, segment: usize| 1 + (ring - 1) * segments + segment % segments;
+    for s in 0..segments {
+        mesh.faces.push([0, at(1, s), at(1, s + 1)]);
+        mesh.faces.push([bottom, at(rings - 1, s + 1), at(rings - 1, s)]);
+        for ring in 1..rings - 1 {
+            mesh.faces.push([at(ring, s), at(ring + 1, s), at(ring + 1, s + 1)]);
+            mesh.faces.push([at(ring, s), at(ring + 1, s + 1), at(ring, s + 1)]);
+        }
+    }
+    mesh
+}
+
+fn large_meshes(c: &mut Criterion) {
+    // About 200k and 800k faces
+    for (rings, segments) in [(201, 500), (401, 1000)] {
+        let mesh = sphere(50.0, rings, segments);
+        let mut group = c.benchmark_group(format!("sphere_{}k_faces", mesh.faces.len() / 1000));
+        group.sample_size(10);
+        group.throughput(Throughput::Elements(mesh.faces.len() as u64));
+        group.bench_function("analyze_mesh", |b| b.iter(|| analyze_mesh(&mesh)));
+        group.bench_function("self_intersections", |b| b.iter(|| find_self_intersections(&mesh)));
+        group.bench_function("thumbnail", |b| b.iter(|| render_thumbnail(&mesh, THUMBNAIL_SIZE)));
+        group.bench_function("decimate_preview", |b| b.iter(|| decimate(&mesh, PREVIEW_MAX_TRIANGLES)));
+        group.finish();
+    }
+}
+
+criterion_group!(benches, large_meshes);
+criterion_main!(benches);
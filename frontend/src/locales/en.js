@@ -9,33 +9,82 @@ export default {
   },
   upload: {
     title: 'Analyze Geometry',
-    subtitle: 'Upload your STL file to get instant geometric analysis',
-    dropTitle: 'Drop your STL file here',
+    subtitle: 'Upload your STL, OBJ or 3MF file to get instant geometric analysis',
+    dropTitle: 'Drop your STL, OBJ or 3MF file here',
     dropSubtitle: 'or click to browse',
-    hint: 'Supports .stl files',
+    hint: 'Supports .stl, .obj and .3mf files',
     analyzing: 'Analyzing geometry...',
+    analysisFailed: 'This file could not be analyzed: {reason}',
     results: 'Analysis Results',
     fileInfo: 'File Info',
     filename: 'Filename',
     id: 'ID',
     geometryData: 'Geometry Data',
     volume: 'Volume (cm³)',
+    volumeEstimated: 'The mesh has holes, so the volume is estimated (± {error} cm³).',
     surfaceArea: 'Surface Area (cm²)',
+    dimensions: 'Dimensions (mm)',
+    parts: '{count} separate parts',
+    voids: '{count} internal voids excluded from the volume',
+    tipsOver: 'The centre of mass lies outside the base, so the part falls over as uploaded.',
+    units: 'Model units',
+    unitsAuto: 'Detect automatically',
+    unitNames: { mm: 'millimetres', cm: 'centimetres', m: 'metres', in: 'inches' },
+    unitsSuggestion: 'This model looks unusually small. It may have been drawn in {units}.',
+    rescale: 'Rescale from {units}',
+    rescaling: 'Rescaling...',
+    tooLarge: 'This model is larger than the build volume of all our printers.',
+    needsRepair: 'This mesh has holes or flipped faces and may need repair before printing.',
+    selfIntersecting: '{count} pairs of faces cut through each other; merge overlapping bodies in your CAD tool before exporting.',
+    notPrintable: 'This mesh does not enclose a printable volume.',
+    repair: 'Repair automatically',
+    repairing: 'Repairing...',
+    repairApplied: 'The following fixes were applied to a copy of your model:',
+    useRepaired: 'Use repaired model',
+    nothingToRepair: 'No automatic fixes were needed.',
+    fixes: {
+      welded_vertices: '{count} vertices merged',
+      removed_degenerate_faces: '{count} zero-area faces removed',
+      removed_duplicate_faces: '{count} duplicate faces removed',
+      flipped_faces: '{count} faces re-oriented',
+      flipped_shells: '{count} inside-out shells flipped',
+      filled_holes: '{count} holes filled',
+      added_faces: '{count} faces added',
+    },
     analyzeAnother: 'Analyze Another File',
     instantQuote: 'Instant Quote',
     material: 'Material',
     color: 'Color',
     infill: 'Infill (%)',
+    part: 'Part',
+    allParts: 'All parts',
+    partName: 'Part {n}',
+    layerHeight: 'Layer Height',
+    optimizeOrientation: 'Choose the best print orientation',
+    hollow: 'Hollow the part (2 mm walls) to save resin',
     calculatePrice: 'Calculate Price',
     calculating: 'Calculating...',
     placeOrder: 'Place Order',
     processing: 'Processing...',
     materialCost: 'Material Cost',
     machineCost: 'Machine Cost',
+    hollowSaved: 'Resin Saved by Hollowing (cm³)',
+    supportMaterial: 'Support Material (g)',
+    printTime: 'Print Time (h)',
+    thinWalls: 'Some walls are only {min} mm thick; this material needs at least {required} mm.',
+    trappedResin: '{count} sealed cavities would trap {volume} cm³ of uncured resin; add drain holes before printing.',
     orderPlaced: 'Order placed successfully! Order ID: {id}',
     failedQuote: 'Failed to calculate quote',
     failedOrder: 'Failed to place order',
-    invalidFile: 'Please upload a valid STL file (.stl)'
+    invalidFile: 'Please upload a valid STL, OBJ or 3MF file (.stl, .obj, .3mf)'
+  },
+  analysisErrors: {
+    UNSUPPORTED_FORMAT: 'This file type is not supported. Export your model as STL, OBJ or 3MF.',
+    TRUNCATED_BINARY: 'The STL file is incomplete ({actual_bytes} of {expected_bytes} bytes). Upload it again or re-export it.',
+    INVALID_SYNTAX: 'The {format} file could not be read ({detail}). Re-export it from your modelling software.',
+    NO_FACES: 'The file contains no surfaces. Check that you exported the model itself, not an empty scene.',
+    NON_FINITE_COORDINATES: '{vertices} vertices have invalid coordinates. Repair the model in your modelling software and export it again.',
+    TOO_MANY_TRIANGLES: 'The model has {triangles} triangles, more than the {limit} we can analyze. Reduce its resolution and try again.'
   },
   viewer: {
     loading: 'Loading 3D Model...',
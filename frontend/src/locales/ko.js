@@ -9,33 +9,82 @@ export default {
   },
   upload: {
     title: '형상 분석',
-    subtitle: 'STL 파일을 업로드하여 즉시 형상을 분석하세요',
-    dropTitle: 'STL 파일을 여기에 놓으세요',
+    subtitle: 'STL, OBJ 또는 3MF 파일을 업로드하여 즉시 형상을 분석하세요',
+    dropTitle: 'STL, OBJ 또는 3MF 파일을 여기에 놓으세요',
     dropSubtitle: '또는 클릭하여 찾아보기',
-    hint: '.stl 파일 지원',
+    hint: '.stl, .obj, .3mf 파일 지원',
     analyzing: '형상 분석 중...',
+    analysisFailed: '파일을 분석할 수 없습니다: {reason}',
     results: '분석 결과',
     fileInfo: '파일 정보',
     filename: '파일명',
     id: 'ID',
     geometryData: '형상 데이터',
     volume: '부피 (cm³)',
+    volumeEstimated: '메시에 구멍이 있어 부피는 추정값입니다 (± {error} cm³).',
     surfaceArea: '표면적 (cm²)',
+    dimensions: '크기 (mm)',
+    parts: '분리된 파트 {count}개',
+    voids: '내부 빈 공간 {count}개 (부피에서 제외)',
+    tipsOver: '무게 중심이 바닥면 밖에 있어 업로드한 자세로는 넘어집니다.',
+    units: '모델 단위',
+    unitsAuto: '자동 감지',
+    unitNames: { mm: '밀리미터', cm: '센티미터', m: '미터', in: '인치' },
+    unitsSuggestion: '모델이 비정상적으로 작습니다. {units} 단위로 작성되었을 수 있습니다.',
+    rescale: '{units} 기준으로 크기 변환',
+    rescaling: '변환 중...',
+    tooLarge: '이 모델은 모든 프린터의 출력 가능 크기를 초과합니다.',
+    needsRepair: '이 메시에는 구멍이나 뒤집힌 면이 있어 출력 전에 수리가 필요할 수 있습니다.',
+    selfIntersecting: '서로 관통하는 면 {count}쌍이 있습니다. 내보내기 전에 CAD에서 겹친 바디를 합쳐 주세요.',
+    notPrintable: '이 메시는 출력 가능한 부피를 형성하지 않습니다.',
+    repair: '자동 수리',
+    repairing: '수리 중...',
+    repairApplied: '모델 사본에 다음 수정이 적용되었습니다:',
+    useRepaired: '수리된 모델 사용',
+    nothingToRepair: '자동 수리가 필요하지 않습니다.',
+    fixes: {
+      welded_vertices: '정점 {count}개 병합',
+      removed_degenerate_faces: '면적이 0인 면 {count}개 제거',
+      removed_duplicate_faces: '중복 면 {count}개 제거',
+      flipped_faces: '면 {count}개 방향 수정',
+      flipped_shells: '뒤집힌 셸 {count}개 반전',
+      filled_holes: '구멍 {count}개 메움',
+      added_faces: '면 {count}개 추가',
+    },
     analyzeAnother: '다른 파일 분석하기',
     instantQuote: '즉시 견적',
     material: '재질',
     color: '색상',
     infill: '채움 (%)',
+    part: '파트',
+    allParts: '전체 파트',
+    partName: '파트 {n}',
+    layerHeight: '레이어 높이',
+    optimizeOrientation: '최적 출력 방향 자동 선택',
+    hollow: '파트를 속이 빈 형태로 출력 (벽 두께 2 mm)',
     calculatePrice: '가격 계산',
     calculating: '계산 중...',
     placeOrder: '주문하기',
     processing: '처리 중...',
     materialCost: '재료비',
     machineCost: '가공비',
+    hollowSaved: '속 비우기로 절약한 레진 (cm³)',
+    supportMaterial: '서포트 재료 (g)',
+    printTime: '출력 시간 (시간)',
+    thinWalls: '일부 벽 두께가 {min} mm로, 이 재료는 최소 {required} mm가 필요합니다.',
+    trappedResin: '밀폐된 내부 공간 {count}개에 미경화 레진 {volume} cm³가 갇힙니다. 출력 전에 배출 구멍을 추가해 주세요.',
     orderPlaced: '주문이 성공적으로 접수되었습니다! 주문 ID: {id}',
     failedQuote: '견적 계산 실패',
     failedOrder: '주문 실패',
-    invalidFile: '유효한 STL 파일을 업로드해주세요 (.stl)'
+    invalidFile: '유효한 STL, OBJ 또는 3MF 파일을 업로드해주세요 (.stl, .obj, .3mf)'
+  },
+  analysisErrors: {
+    UNSUPPORTED_FORMAT: '지원하지 않는 파일 형식입니다. STL, OBJ 또는 3MF로 내보내 주세요.',
+    TRUNCATED_BINARY: 'STL 파일이 손상되었습니다 ({expected_bytes}바이트 중 {actual_bytes}바이트). 다시 업로드하거나 다시 내보내 주세요.',
+    INVALID_SYNTAX: '{format} 파일을 읽을 수 없습니다 ({detail}). 모델링 프로그램에서 다시 내보내 주세요.',
+    NO_FACES: '파일에 면이 없습니다. 빈 장면이 아닌 모델을 내보냈는지 확인해 주세요.',
+    NON_FINITE_COORDINATES: '{vertices}개 꼭짓점의 좌표가 올바르지 않습니다. 모델링 프로그램에서 모델을 수정한 뒤 다시 내보내 주세요.',
+    TOO_MANY_TRIANGLES: '모델의 삼각형이 {triangles}개로, 분석 가능한 {limit}개를 초과합니다. 해상도를 낮춰 다시 시도해 주세요.'
   },
   viewer: {
     loading: '3D 모델 로딩 중...',
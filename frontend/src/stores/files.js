@@ -2,19 +2,32 @@ import { defineStore } from 'pinia';
 import axios from 'axios';
 import { useAuthStore } from './auth';
 
+const ANALYSIS_POLL_MS = 1000;
+
+// Requests on an unreadable model fail with its analysis error as JSON
+// ({ code, ...details, message }); other failures are plain text
+const failureOf = (err) => (err.response?.data?.code ? err.response.data : null);
+const messageOf = (err, fallback) => err.response?.data?.message || err.response?.data || fallback;
+
 export const useFileStore = defineStore('files', {
   state: () => ({
     currentFile: null,
     uploading: false,
+    repairing: false,
+    rescaling: false,
+    repair: null,
     error: null,
+    errorFailure: null,
   }),
   actions: {
-    async uploadFile(file) {
+    async uploadFile(file, units = null) {
       this.uploading = true;
       this.error = null;
+      this.errorFailure = null;
       const authStore = useAuthStore();
       
       const formData = new FormData();
+      if (units) formData.append('units', units);
       formData.append('file', file);
 
       try {
@@ -24,18 +37,80 @@ export const useFileStore = defineStore('files', {
             Authorization: `Bearer ${authStore.token}`,
           },
         });
-        this.currentFile = response.data;
+        // Analysis runs in the background; poll until the file leaves PENDING
+        let file = response.data;
+        while (file.status === 'PENDING') {
+          await new Promise((resolve) => setTimeout(resolve, ANALYSIS_POLL_MS));
+          const analysis = await axios.get(`/api/files/${file.id}/analysis`, {
+            headers: { Authorization: `Bearer ${authStore.token}` },
+          });
+          file = analysis.data;
+        }
+        this.currentFile = { ...file, file_id: file.id };
+        this.repair = null;
         return true;
       } catch (err) {
-        this.error = err.response?.data || 'Upload failed';
+        this.error = messageOf(err, 'Upload failed');
+        this.errorFailure = failureOf(err);
         return false;
       } finally {
         this.uploading = false;
       }
     },
+    async repairFile() {
+      if (!this.currentFile) return false;
+      this.repairing = true;
+      this.error = null;
+      this.errorFailure = null;
+      const authStore = useAuthStore();
+
+      try {
+        const response = await axios.post(`/api/files/${this.currentFile.file_id}/repair`, null, {
+          headers: { Authorization: `Bearer ${authStore.token}` },
+        });
+        this.repair = response.data;
+        return true;
+      } catch (err) {
+        this.error = messageOf(err, 'Repair failed');
+        this.errorFailure = failureOf(err);
+        return false;
+      } finally {
+        this.repairing = false;
+      }
+    },
+    async rescaleFile(units) {
+      if (!this.currentFile) return false;
+      this.rescaling = true;
+      this.error = null;
+      this.errorFailure = null;
+      const authStore = useAuthStore();
+
+      try {
+        const response = await axios.post(`/api/files/${this.currentFile.file_id}/rescale`, { units }, {
+          headers: { Authorization: `Bearer ${authStore.token}` },
+        });
+        this.currentFile = response.data;
+        this.repair = null;
+        return true;
+      } catch (err) {
+        this.error = messageOf(err, 'Rescale failed');
+        this.errorFailure = failureOf(err);
+        return false;
+      } finally {
+        this.rescaling = false;
+      }
+    },
+    acceptRepair() {
+      if (this.repair?.repaired_file) {
+        this.currentFile = this.repair.repaired_file;
+      }
+      this.repair = null;
+    },
     clearFile() {
       this.currentFile = null;
+      this.repair = null;
       this.error = null;
+      this.errorFailure = null;
     }
   },
 });
@@ -0,0 +1,2 @@
+-- Track the uploaded model format (STL, OBJ, ...)
+ALTER TABLE files ADD COLUMN file_format VARCHAR(16) NOT NULL DEFAULT 'STL';
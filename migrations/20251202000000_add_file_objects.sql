@@ -0,0 +1,2 @@
+-- Per-object analysis for multi-object uploads (3MF build plates)
+ALTER TABLE files ADD COLUMN objects JSONB;
@@ -0,0 +1,28 @@
+-- Bounding box of the analyzed model (mm)
+ALTER TABLE files
+    ADD COLUMN bbox_min_x DOUBLE PRECISION,
+    ADD COLUMN bbox_min_y DOUBLE PRECISION,
+    ADD COLUMN bbox_min_z DOUBLE PRECISION,
+    ADD COLUMN bbox_max_x DOUBLE PRECISION,
+    ADD COLUMN bbox_max_y DOUBLE PRECISION,
+    ADD COLUMN bbox_max_z DOUBLE PRECISION,
+    ADD COLUMN fits_build_volume BOOLEAN;
+
+-- Printers available for production, used for build volume checks
+CREATE TABLE printers (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    name VARCHAR(255) UNIQUE NOT NULL,
+    technology VARCHAR(50) NOT NULL, -- 'FDM' or 'RESIN'
+    build_x_mm DOUBLE PRECISION NOT NULL,
+    build_y_mm DOUBLE PRECISION NOT NULL,
+    build_z_mm DOUBLE PRECISION NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+INSERT INTO printers (name, technology, build_x_mm, build_y_mm, build_z_mm)
+VALUES
+    ('Prusa MK4', 'FDM', 250, 210, 220),
+    ('Bambu Lab X1 Carbon', 'FDM', 256, 256, 256),
+    ('Elegoo Saturn 3', 'RESIN', 218.88, 122.88, 250)
+ON CONFLICT (name) DO NOTHING;
@@ -0,0 +1,2 @@
+-- Mesh topology report; files.status may now also be 'NEEDS_REPAIR'
+ALTER TABLE files ADD COLUMN mesh_validation JSONB;
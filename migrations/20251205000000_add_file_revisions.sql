@@ -0,0 +1,5 @@
+-- Derived files (repairs, transforms, ...) point back to the file they came from
+ALTER TABLE files
+    ADD COLUMN parent_file_id UUID REFERENCES files(id),
+    ADD COLUMN revision_kind VARCHAR(50),
+    ADD COLUMN repair_report JSONB;
@@ -0,0 +1,18 @@
+-- Motion settings used to estimate FDM print time from toolpaths
+ALTER TABLE printers
+    ADD COLUMN nozzle_diameter_mm DOUBLE PRECISION NOT NULL DEFAULT 0.4,
+    ADD COLUMN filament_diameter_mm DOUBLE PRECISION NOT NULL DEFAULT 1.75,
+    ADD COLUMN perimeter_speed_mm_s DOUBLE PRECISION NOT NULL DEFAULT 45,
+    ADD COLUMN infill_speed_mm_s DOUBLE PRECISION NOT NULL DEFAULT 80,
+    ADD COLUMN travel_speed_mm_s DOUBLE PRECISION NOT NULL DEFAULT 150,
+    ADD COLUMN acceleration_mm_s2 DOUBLE PRECISION NOT NULL DEFAULT 1000,
+    ADD COLUMN perimeters INTEGER NOT NULL DEFAULT 2,
+    ADD COLUMN solid_layers INTEGER NOT NULL DEFAULT 4;
+
+UPDATE printers
+SET perimeter_speed_mm_s = 120, infill_speed_mm_s = 200, travel_speed_mm_s = 300, acceleration_mm_s2 = 2500
+WHERE name = 'Prusa MK4';
+
+UPDATE printers
+SET perimeter_speed_mm_s = 200, infill_speed_mm_s = 270, travel_speed_mm_s = 500, acceleration_mm_s2 = 10000
+WHERE name = 'Bambu Lab X1 Carbon';
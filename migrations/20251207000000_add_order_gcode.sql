@@ -0,0 +1,7 @@
+-- Firmware flavor for generated G-code, and the G-code stored per order
+ALTER TABLE printers
+    ADD COLUMN gcode_flavor VARCHAR(50) NOT NULL DEFAULT 'MARLIN';
+
+ALTER TABLE orders
+    ADD COLUMN gcode_path VARCHAR(255),
+    ADD COLUMN gcode_printer_id UUID REFERENCES printers(id);
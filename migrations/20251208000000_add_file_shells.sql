@@ -0,0 +1,4 @@
+-- Connected shells (parts and internal voids) per file, and the part a quote covers
+ALTER TABLE files ADD COLUMN shells JSONB;
+
+ALTER TABLE quotes ADD COLUMN shell_index INTEGER;
@@ -0,0 +1,3 @@
+-- Units the stored model's coordinates are in, and the units suggested when they look wrong
+ALTER TABLE files ADD COLUMN units VARCHAR(10) NOT NULL DEFAULT 'mm';
+ALTER TABLE files ADD COLUMN suggested_units VARCHAR(10);
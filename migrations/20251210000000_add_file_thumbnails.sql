@@ -0,0 +1,2 @@
+-- Rendered PNG preview of each file
+ALTER TABLE files ADD COLUMN thumbnail_path VARCHAR(255);
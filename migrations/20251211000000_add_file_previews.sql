@@ -0,0 +1,2 @@
+-- Decimated binary STL served to the web viewer
+ALTER TABLE files ADD COLUMN preview_path VARCHAR(255);
@@ -0,0 +1,15 @@
+-- Uploads are analyzed in the background; one job per file waiting for (or done with) analysis
+CREATE TABLE analysis_jobs (
+    id UUID PRIMARY KEY DEFAULT gen_random_uuid(),
+    file_id UUID NOT NULL REFERENCES files(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'QUEUED', -- 'QUEUED', 'DONE' or 'FAILED'
+    detect_units BOOLEAN NOT NULL DEFAULT FALSE,
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMPTZ
+);
+
+CREATE INDEX analysis_jobs_queued_idx ON analysis_jobs (created_at) WHERE status = 'QUEUED';
+
+-- Why analysis of a FAILED file could not complete
+ALTER TABLE files ADD COLUMN analysis_error TEXT;
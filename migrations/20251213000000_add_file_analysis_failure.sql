@@ -0,0 +1,2 @@
+-- Structured reason (stable code plus details) a FAILED file could not be read
+ALTER TABLE files ADD COLUMN analysis_failure JSONB;
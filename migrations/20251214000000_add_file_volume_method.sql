@@ -0,0 +1,4 @@
+-- How volume_cm3 was measured (SIGNED_VOLUME or VOXEL) and its estimated uncertainty
+ALTER TABLE files
+    ADD COLUMN volume_method VARCHAR(20),
+    ADD COLUMN volume_error_cm3 DOUBLE PRECISION;
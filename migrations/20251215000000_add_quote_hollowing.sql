@@ -0,0 +1,2 @@
+-- Hollowing settings a resin quote was priced with (NULL for solid parts)
+ALTER TABLE quotes ADD COLUMN hollow JSONB;
@@ -0,0 +1,2 @@
+-- Centre of mass, inertia tensor and tip-over stability of the analyzed model
+ALTER TABLE files ADD COLUMN mass_properties JSONB;
@@ -0,0 +1,8 @@
+-- Workers claim a job by marking it RUNNING until `locked_until`, instead of holding a
+-- transaction open; an expired RUNNING job is claimed again. Storage failures put the
+-- job back in the queue until it has been tried `attempts` times.
+ALTER TABLE analysis_jobs ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE analysis_jobs ADD COLUMN locked_until TIMESTAMPTZ;
+
+DROP INDEX analysis_jobs_queued_idx;
+CREATE INDEX analysis_jobs_pending_idx ON analysis_jobs (created_at) WHERE status IN ('QUEUED', 'RUNNING');
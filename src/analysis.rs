@@ -1,45 +1,457 @@
+pub mod bvh;
+pub mod decimate;
+pub mod drainage;
+pub mod error;
+pub mod gcode;
+pub mod hollow;
+pub mod intersections;
+pub mod mass;
+pub mod measure;
+pub mod obj;
+pub mod orientation;
+pub mod repair;
+pub mod section;
+pub mod shells;
+pub mod slicer;
+pub mod supports;
+pub mod thickness;
+pub mod threemf;
+pub mod thumbnail;
+pub mod toolpath;
+pub mod transform;
+pub mod units;
+pub mod validation;
+pub mod vec3;
+pub mod voxel;
+
 use std::io::Cursor;
-use stl_io::read_stl;
+use std::path::Path;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use error::{AnalysisError, MAX_TRIANGLES};
+use mass::{mass_properties, MassProperties};
+use shells::{analyze_shells, total_volume_cm3, Shell};
+use units::LengthUnit;
+use validation::{validate_mesh, MeshValidation, STATUS_FAILED};
+use voxel::{voxel_volume, VolumeMethod, VoxelSettings};
 
 #[derive(Debug, Clone)]
 pub struct GeometryAnalysis {
+    pub volume_cm3: f64,
+    /// How `volume_cm3` was measured: voxelized when the mesh isn't watertight.
+    pub volume_method: VolumeMethod,
+    /// Estimated uncertainty of `volume_cm3`; zero for closed meshes.
+    pub volume_error_cm3: f64,
+    pub surface_area_cm2: f64,
+    pub bounding_box: BoundingBox,
+    pub validation: MeshValidation,
+    /// Per-object breakdown for multi-object containers (3MF build plates).
+    pub objects: Vec<ObjectAnalysis>,
+    /// Connected bodies, including internal voids.
+    pub shells: Vec<Shell>,
+    /// Centre of mass, inertia and whether the part stands as uploaded; `None` without a volume.
+    pub mass_properties: Option<MassProperties>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectAnalysis {
+    pub name: String,
     pub volume_cm3: f64,
     pub surface_area_cm2: f64,
 }
 
-pub fn analyze_stl(data: &[u8]) -> Result<GeometryAnalysis, String> {
+/// Axis-aligned bounding box in millimetres.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub min: [f64; 3],
+    pub max: [f64; 3],
+}
+
+impl BoundingBox {
+    /// X/Y/Z extents in millimetres.
+    pub fn size(&self) -> [f64; 3] {
+        [
+            self.max[0] - self.min[0],
+            self.max[1] - self.min[1],
+            self.max[2] - self.min[2],
+        ]
+    }
+
+    /// Whether the box fits inside a build volume, allowing the part to be
+    /// turned onto any of its axis-aligned sides.
+    pub fn fits_within(&self, build_volume: [f64; 3]) -> bool {
+        let [x, y, z] = self.size();
+        let orientations = [[x, y, z], [y, x, z], [x, z, y], [z, x, y], [y, z, x], [z, y, x]];
+        orientations
+            .iter()
+            .any(|dims| dims.iter().zip(build_volume.iter()).all(|(d, limit)| d <= limit))
+    }
+}
+
+/// File formats accepted by the analysis engine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ModelFormat {
+    Stl,
+    Obj,
+    #[serde(rename = "3MF")]
+    ThreeMf,
+}
+
+impl ModelFormat {
+    /// Detects the format from the file extension, falling back to sniffing the content.
+    pub fn detect(filename: &str, data: &[u8]) -> Option<Self> {
+        let extension = Path::new(filename)
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("stl") => return Some(ModelFormat::Stl),
+            Some("obj") => return Some(ModelFormat::Obj),
+            Some("3mf") => return Some(ModelFormat::ThreeMf),
+            _ => {}
+        }
+
+        // 3MF is a ZIP package
+        if data.starts_with(b"PK\x03\x04") {
+            return Some(ModelFormat::ThreeMf);
+        }
+
+        // Binary STL: 80 byte header + u32 count + 50 bytes per triangle
+        if data.len() >= 84 {
+            let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+            if 84 + count * 50 == data.len() {
+                return Some(ModelFormat::Stl);
+            }
+        }
+
+        let head = String::from_utf8_lossy(&data[..data.len().min(1024)]);
+        if head.trim_start().starts_with("solid") {
+            return Some(ModelFormat::Stl);
+        }
+        if head.lines().any(|line| line.trim_start().starts_with("v ")) {
+            return Some(ModelFormat::Obj);
+        }
+
+        None
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelFormat::Stl => "STL",
+            ModelFormat::Obj => "OBJ",
+            ModelFormat::ThreeMf => "3MF",
+        }
+    }
+
+    /// Inverse of `as_str`, for formats read back from the database.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "STL" => Some(ModelFormat::Stl),
+            "OBJ" => Some(ModelFormat::Obj),
+            "3MF" => Some(ModelFormat::ThreeMf),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ModelFormat::Stl => "model/stl",
+            ModelFormat::Obj => "model/obj",
+            ModelFormat::ThreeMf => "model/3mf",
+        }
+    }
+}
+
+/// Triangle mesh in millimetres, shared by all importers.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub vertices: Vec<[f64; 3]>,
+    pub faces: Vec<[usize; 3]>,
+}
+
+/// A named part of a multi-object model, as a range of faces in the combined mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelObject {
+    pub name: String,
+    pub first_face: usize,
+    pub face_count: usize,
+}
+
+/// A parsed upload: the combined mesh plus the objects it was assembled from.
+#[derive(Debug, Clone, Default)]
+pub struct Model {
+    pub mesh: Mesh,
+    pub objects: Vec<ModelObject>,
+}
+
+impl Mesh {
+    /// Bounding box of the vertices referenced by faces (all zeros for an empty mesh).
+    pub fn bounding_box(&self) -> BoundingBox {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for &vertex in self.faces.iter().flatten() {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(self.vertices[vertex][axis]);
+                max[axis] = max[axis].max(self.vertices[vertex][axis]);
+            }
+        }
+        if self.faces.is_empty() {
+            return BoundingBox { min: [0.0; 3], max: [0.0; 3] };
+        }
+        BoundingBox { min, max }
+    }
+
+    pub fn triangle(&self, face: usize) -> [[f64; 3]; 3] {
+        let [a, b, c] = self.faces[face];
+        [self.vertices[a], self.vertices[b], self.vertices[c]]
+    }
+
+    /// Encodes the mesh as binary STL.
+    pub fn to_stl_bytes(&self) -> Vec<u8> {
+        let triangles: Vec<stl_io::Triangle> = (0..self.faces.len())
+            .map(|face| {
+                let [a, b, c] = self.triangle(face);
+                let normal = vec3::normalize(vec3::face_normal(a, b, c));
+                let to_f32 = |v: [f64; 3]| stl_io::Vertex::new([v[0] as f32, v[1] as f32, v[2] as f32]);
+                stl_io::Triangle {
+                    normal: to_f32(normal),
+                    vertices: [to_f32(a), to_f32(b), to_f32(c)],
+                }
+            })
+            .collect();
+        let mut bytes = Vec::with_capacity(84 + triangles.len() * 50);
+        stl_io::write_stl(&mut bytes, triangles.iter()).expect("writing to a Vec cannot fail");
+        bytes
+    }
+
+    /// Copy of the mesh with every vertex multiplied by a 3x3 rotation matrix.
+    pub fn rotated(&self, rotation: &[[f64; 3]; 3]) -> Mesh {
+        let vertices = self
+            .vertices
+            .iter()
+            .map(|&v| [vec3::dot(rotation[0], v), vec3::dot(rotation[1], v), vec3::dot(rotation[2], v)])
+            .collect();
+        Mesh { vertices, faces: self.faces.clone() }
+    }
+
+    /// Copy of the mesh with every coordinate multiplied by `factor`.
+    pub fn scaled(&self, factor: f64) -> Mesh {
+        let vertices = self.vertices.iter().map(|v| v.map(|c| c * factor)).collect();
+        Mesh { vertices, faces: self.faces.clone() }
+    }
+
+    /// Copy of the mesh keeping only the faces `keep` marks (all of them for
+    /// `None`) and the vertices they use, renumbered in order of first use.
+    pub(crate) fn compact(&self, keep: Option<&[bool]>) -> Mesh {
+        let mut remap = vec![usize::MAX; self.vertices.len()];
+        let mut mesh = Mesh::default();
+        for (index, f) in self.faces.iter().enumerate() {
+            if keep.is_some_and(|keep| !keep[index]) {
+                continue;
+            }
+            mesh.faces.push(f.map(|v| {
+                if remap[v] == usize::MAX {
+                    remap[v] = mesh.vertices.len();
+                    mesh.vertices.push(self.vertices[v]);
+                }
+                remap[v]
+            }));
+        }
+        mesh
+    }
+}
+
+impl From<stl_io::IndexedMesh> for Mesh {
+    fn from(mesh: stl_io::IndexedMesh) -> Self {
+        let vertices = mesh
+            .vertices
+            .iter()
+            .map(|v| [v[0] as f64, v[1] as f64, v[2] as f64])
+            .collect();
+        let faces = mesh.faces.iter().map(|f| f.vertices).collect();
+        Mesh { vertices, faces }
+    }
+}
+
+/// Parses `data` in the given format into a model in millimetres.
+pub fn load_model(data: &[u8], format: ModelFormat) -> Result<Model, AnalysisError> {
+    let model = match format {
+        ModelFormat::Stl => Model { mesh: read_stl_mesh(data)?, objects: Vec::new() },
+        ModelFormat::Obj => {
+            let parsed = obj::parse_obj(data).map_err(|e| AnalysisError::syntax(format, e))?;
+            let objects = parsed
+                .groups
+                .into_iter()
+                .map(|group| ModelObject { name: group.name, first_face: group.first_face, face_count: group.face_count })
+                .collect();
+            Model { mesh: parsed.mesh, objects }
+        }
+        ModelFormat::ThreeMf => {
+            let (mesh, objects) = threemf::parse_3mf(data)?;
+            Model { mesh, objects }
+        }
+    };
+    check_mesh(&model.mesh)?;
+    Ok(model)
+}
+
+/// Reads ASCII or binary STL, telling a truncated binary file apart from bad ASCII.
+fn read_stl_mesh(data: &[u8]) -> Result<Mesh, AnalysisError> {
+    let binary_triangles = (data.len() >= 84).then(|| u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as u64);
+    let exact_binary = binary_triangles.is_some_and(|count| 84 + count * 50 == data.len() as u64);
+    // Some exporters start binary headers with "solid" too, so the size decides
+    // first, then NUL bytes (padding, zero normals) that never appear in text
+    let looks_like_text = !data[..data.len().min(512)].contains(&0);
+    if !exact_binary && data.starts_with(b"solid") && looks_like_text {
+        let mut cursor = Cursor::new(data);
+        return stl_io::AsciiStlReader::create_triangle_iterator(&mut cursor)
+            .and_then(|mut triangles| triangles.as_indexed_triangles())
+            .map(Mesh::from)
+            .map_err(|e| AnalysisError::syntax(ModelFormat::Stl, e.to_string()));
+    }
+
+    let Some(triangles) = binary_triangles else {
+        return Err(AnalysisError::TruncatedBinary { expected_bytes: 84, actual_bytes: data.len() as u64 });
+    };
+    if triangles > MAX_TRIANGLES {
+        return Err(AnalysisError::TooManyTriangles { triangles, limit: MAX_TRIANGLES });
+    }
+    let expected_bytes = 84 + triangles * 50;
+    if (data.len() as u64) < expected_bytes {
+        return Err(AnalysisError::TruncatedBinary { expected_bytes, actual_bytes: data.len() as u64 });
+    }
     let mut cursor = Cursor::new(data);
-    let mesh = read_stl(&mut cursor).map_err(|e| e.to_string())?;
+    stl_io::BinaryStlReader::create_triangle_iterator(&mut cursor)
+        .and_then(|mut triangles| triangles.as_indexed_triangles())
+        .map(Mesh::from)
+        .map_err(|e| AnalysisError::syntax(ModelFormat::Stl, e.to_string()))
+}
 
-    let mut total_volume = 0.0;
-    let mut total_area = 0.0;
+/// Rejects meshes too large to analyze or with unusable coordinates.
+fn check_mesh(mesh: &Mesh) -> Result<(), AnalysisError> {
+    let triangles = mesh.faces.len() as u64;
+    if triangles > MAX_TRIANGLES {
+        return Err(AnalysisError::TooManyTriangles { triangles, limit: MAX_TRIANGLES });
+    }
+    let mut used = vec![false; mesh.vertices.len()];
+    for &vertex in mesh.faces.iter().flatten() {
+        used[vertex] = true;
+    }
+    let vertices = mesh
+        .vertices
+        .iter()
+        .zip(used)
+        .filter(|(v, used)| *used && v.iter().any(|c| !c.is_finite()))
+        .count();
+    if vertices > 0 {
+        return Err(AnalysisError::NonFiniteCoordinates { vertices });
+    }
+    Ok(())
+}
 
-    // stl_io returns an IndexedMesh. We need to iterate over faces.
-    for face in &mesh.faces {
-        let v1 = mesh.vertices[face.vertices[0]];
-        let v2 = mesh.vertices[face.vertices[1]];
-        let v3 = mesh.vertices[face.vertices[2]];
+pub fn load_mesh(data: &[u8], format: ModelFormat) -> Result<Mesh, AnalysisError> {
+    load_model(data, format).map(|model| model.mesh)
+}
 
-        // Calculate Signed Volume of Tetrahedron formed by triangle and origin
-        // V = (v1 . (v2 x v3)) / 6
-        let v1_vec = [v1[0] as f64, v1[1] as f64, v1[2] as f64];
-        let v2_vec = [v2[0] as f64, v2[1] as f64, v2[2] as f64];
-        let v3_vec = [v3[0] as f64, v3[1] as f64, v3[2] as f64];
+pub fn analyze(data: &[u8], format: ModelFormat) -> Result<GeometryAnalysis, AnalysisError> {
+    load_model_in_units(data, format, LengthUnit::Millimeter).map(|model| analyze_model(&model))
+}
 
-        total_volume += signed_volume(v1_vec, v2_vec, v3_vec);
-        total_area += triangle_area(v1_vec, v2_vec, v3_vec);
+/// Parses a model whose coordinates are in `units`, converting it to millimetres.
+/// Fails when the model has no faces.
+pub fn load_model_in_units(data: &[u8], format: ModelFormat, units: LengthUnit) -> Result<Model, AnalysisError> {
+    let mut model = load_model(data, format)?;
+    if model.mesh.faces.is_empty() {
+        return Err(AnalysisError::NoFaces);
     }
+    if units != LengthUnit::Millimeter {
+        model.mesh = model.mesh.scaled(units.mm_per_unit());
+    }
+    Ok(model)
+}
+
+pub fn analyze_model(model: &Model) -> GeometryAnalysis {
+    let mut analysis = analyze_mesh(&model.mesh);
+    analysis.objects = model
+        .objects
+        .iter()
+        .map(|object| {
+            let (volume_mm3, area_mm2) = face_range_totals(&model.mesh, object.first_face..object.first_face + object.face_count);
+            ObjectAnalysis {
+                name: object.name.clone(),
+                volume_cm3: volume_mm3.abs() / 1000.0,
+                surface_area_cm2: area_mm2 / 100.0,
+            }
+        })
+        .collect();
+    analysis
+}
+
+pub fn analyze_stl(data: &[u8]) -> Result<GeometryAnalysis, AnalysisError> {
+    analyze(data, ModelFormat::Stl)
+}
+
+pub fn analyze_mesh(mesh: &Mesh) -> GeometryAnalysis {
+    let (_, total_area) = face_range_totals(mesh, 0..mesh.faces.len());
+
+    // Solid shells minus the voids inside them, whichever way each is wound
+    let shells = analyze_shells(mesh);
+    let validation = validate_mesh(mesh);
+
+    // Signed volumes of an open surface can be off by any amount. Meshes that
+    // enclose nothing at all (FAILED) can't be quoted, so aren't worth voxelizing.
+    let (volume_method, volume_cm3, volume_error_cm3) = if validation.is_watertight || validation.status == STATUS_FAILED {
+        (VolumeMethod::SignedVolume, total_volume_cm3(&shells), 0.0)
+    } else {
+        let estimate = voxel_volume(mesh, &VoxelSettings::from_env());
+        (VolumeMethod::Voxel, estimate.volume_cm3, estimate.error_cm3)
+    };
 
-    // Convert mm3 to cm3 (1 cm3 = 1000 mm3)
-    let volume_cm3 = (total_volume.abs() / 1000.0).max(0.0);
-    
     // Convert mm2 to cm2 (1 cm2 = 100 mm2)
     let surface_area_cm2 = (total_area / 100.0).max(0.0);
 
-    Ok(GeometryAnalysis {
+    GeometryAnalysis {
         volume_cm3,
+        volume_method,
+        volume_error_cm3,
         surface_area_cm2,
-    })
+        bounding_box: mesh.bounding_box(),
+        validation,
+        objects: Vec::new(),
+        mass_properties: mass_properties(mesh, &shells),
+        shells,
+    }
+}
+
+/// Faces summed per parallel task; chunk sums are added in order so totals don't
+/// depend on thread scheduling.
+pub(crate) const FACE_CHUNK: usize = 16_384;
+
+/// Signed volume (mm3) and area (mm2) of a range of faces.
+fn face_range_totals(mesh: &Mesh, faces: std::ops::Range<usize>) -> (f64, f64) {
+    let end = faces.end;
+    let chunks: Vec<(f64, f64)> = faces
+        .step_by(FACE_CHUNK)
+        .collect::<Vec<_>>()
+        .into_par_iter()
+        .map(|start| {
+            let mut volume = 0.0;
+            let mut area = 0.0;
+            for face in start..(start + FACE_CHUNK).min(end) {
+                let [v1, v2, v3] = mesh.triangle(face);
+
+                // Calculate Signed Volume of Tetrahedron formed by triangle and origin
+                // V = (v1 . (v2 x v3)) / 6
+                volume += signed_volume(v1, v2, v3);
+                area += triangle_area(v1, v2, v3);
+            }
+            (volume, area)
+        })
+        .collect();
+
+    chunks.into_iter().fold((0.0, 0.0), |(volume, area), (v, a)| (volume + v, area + a))
 }
 
 fn signed_volume(p1: [f64; 3], p2: [f64; 3], p3: [f64; 3]) -> f64 {
@@ -49,24 +461,63 @@ fn signed_volume(p1: [f64; 3], p2: [f64; 3], p3: [f64; 3]) -> f64 {
     let v132 = p1[0] * p3[1] * p2[2];
     let v213 = p2[0] * p1[1] * p3[2];
     let v123 = p1[0] * p2[1] * p3[2];
-    
+
     (1.0 / 6.0) * (-v321 + v231 + v312 - v132 - v213 + v123)
 }
 
 fn triangle_area(p1: [f64; 3], p2: [f64; 3], p3: [f64; 3]) -> f64 {
     let ab = [p2[0] - p1[0], p2[1] - p1[1], p2[2] - p1[2]];
     let ac = [p3[0] - p1[0], p3[1] - p1[1], p3[2] - p1[2]];
-    
+
     let cross_product = [
         ab[1] * ac[2] - ab[2] * ac[1],
         ab[2] * ac[0] - ab[0] * ac[2],
         ab[0] * ac[1] - ab[1] * ac[0],
     ];
-    
+
     let magnitude = (cross_product[0].powi(2) + cross_product[1].powi(2) + cross_product[2].powi(2)).sqrt();
     0.5 * magnitude
 }
 
+/// Mesh fixtures shared by the analysis tests.
+#[cfg(test)]
+pub(crate) mod test_util {
+    use super::Mesh;
+
+    /// Outward-wound sides of a box whose corner `i` takes the max X, Y and Z
+    /// for bits 0, 1 and 2: bottom, top, front, back, left, right.
+    pub(crate) const BOX_FACES: [[usize; 3]; 12] = [
+        [0, 2, 3], [0, 3, 1], [4, 5, 7], [4, 7, 6],
+        [0, 1, 5], [0, 5, 4], [2, 6, 7], [2, 7, 3],
+        [0, 4, 6], [0, 6, 2], [1, 3, 7], [1, 7, 5],
+    ];
+
+    /// Appends an axis-aligned box, wound inward when it models a cavity.
+    pub(crate) fn add_box(mesh: &mut Mesh, min: [f64; 3], max: [f64; 3], inward: bool) {
+        let offset = mesh.vertices.len();
+        mesh.vertices.extend((0..8).map(|i| [
+            if i & 1 == 0 { min[0] } else { max[0] },
+            if i & 2 == 0 { min[1] } else { max[1] },
+            if i & 4 == 0 { min[2] } else { max[2] },
+        ]));
+        for f in BOX_FACES {
+            let f = f.map(|v| v + offset);
+            mesh.faces.push(if inward { [f[0], f[2], f[1]] } else { f });
+        }
+    }
+
+    pub(crate) fn cuboid(min: [f64; 3], max: [f64; 3]) -> Mesh {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, min, max, false);
+        mesh
+    }
+
+    /// Cube from the origin to `size` on every axis.
+    pub(crate) fn cube(size: f64) -> Mesh {
+        cuboid([0.0; 3], [size; 3])
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,15 +528,109 @@ mod tests {
         // 12 triangles.
         // Volume should be 1000 mm3 = 1 cm3.
         // Area should be 600 mm2 = 6 cm2.
-        
+
         // Constructing a minimal valid binary STL for a cube is complex in code.
         // Let's mock the logic test or use a known simple shape if possible.
         // Alternatively, we trust the math functions if unit tested.
-        
+
         let p1 = [0.0, 0.0, 0.0];
         let p2 = [10.0, 0.0, 0.0];
         let p3 = [0.0, 10.0, 0.0];
         // Triangle in XY plane. Area = 50.
         assert_eq!(triangle_area(p1, p2, p3), 50.0);
     }
+
+    #[test]
+    fn test_bounding_box_fit() {
+        let mesh = Mesh {
+            vertices: vec![[-5.0, 0.0, 0.0], [295.0, 10.0, 0.0], [0.0, 0.0, 20.0], [1000.0, 1000.0, 1000.0]],
+            faces: vec![[0, 1, 2]],
+        };
+        let bbox = mesh.bounding_box();
+        // The unreferenced vertex is ignored
+        assert_eq!(bbox.min, [-5.0, 0.0, 0.0]);
+        assert_eq!(bbox.size(), [300.0, 10.0, 20.0]);
+
+        // 300mm long part only fits standing up in a 250 x 210 x 320 volume
+        assert!(bbox.fits_within([250.0, 210.0, 320.0]));
+        assert!(!bbox.fits_within([250.0, 210.0, 220.0]));
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(ModelFormat::detect("part.STL", b""), Some(ModelFormat::Stl));
+        assert_eq!(ModelFormat::detect("part.obj", b""), Some(ModelFormat::Obj));
+        assert_eq!(ModelFormat::detect("upload", b"solid part\nendsolid part"), Some(ModelFormat::Stl));
+        assert_eq!(ModelFormat::detect("upload", b"# Blender\nv 0 0 0\n"), Some(ModelFormat::Obj));
+        assert_eq!(ModelFormat::detect("plate.3mf", b""), Some(ModelFormat::ThreeMf));
+        assert_eq!(ModelFormat::detect("upload", b"PK\x03\x04rest"), Some(ModelFormat::ThreeMf));
+        assert_eq!(ModelFormat::detect("upload", b"hello"), None);
+    }
+
+    fn binary_stl(triangles: &[[[f32; 3]; 3]]) -> Vec<u8> {
+        let mut data = b"solid exported by a tool that labels binary files".to_vec();
+        data.resize(80, 0);
+        data.extend_from_slice(&(triangles.len() as u32).to_le_bytes());
+        for triangle in triangles {
+            data.extend_from_slice(&[0u8; 12]);
+            for coord in triangle.iter().flatten() {
+                data.extend_from_slice(&coord.to_le_bytes());
+            }
+            data.extend_from_slice(&[0u8; 2]);
+        }
+        data
+    }
+
+    #[test]
+    fn test_stl_errors_are_classified() {
+        let triangle = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]];
+        // Binary despite the "solid" header, because the size matches
+        assert_eq!(load_mesh(&binary_stl(&[triangle]), ModelFormat::Stl).unwrap().faces.len(), 1);
+
+        let full = binary_stl(&[triangle, triangle]);
+        assert_eq!(
+            load_mesh(&full[..full.len() - 10], ModelFormat::Stl).unwrap_err(),
+            AnalysisError::TruncatedBinary { expected_bytes: 184, actual_bytes: 174 },
+        );
+
+        let mut huge = full.clone();
+        huge[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(load_mesh(&huge, ModelFormat::Stl).unwrap_err().code(), "TOO_MANY_TRIANGLES");
+
+        let nan = binary_stl(&[[[f32::NAN, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]]);
+        assert_eq!(load_mesh(&nan, ModelFormat::Stl).unwrap_err(), AnalysisError::NonFiniteCoordinates { vertices: 1 });
+
+        let ascii = b"solid part\nfacet normal 0 0 1\nouter loop\nvertex 0 0 zero\n";
+        assert_eq!(load_mesh(ascii, ModelFormat::Stl).unwrap_err().code(), "INVALID_SYNTAX");
+        assert_eq!(analyze(b"solid part\nendsolid part\n", ModelFormat::Stl).unwrap_err(), AnalysisError::NoFaces);
+    }
+
+    #[test]
+    fn test_obj_groups_become_objects() {
+        let obj = b"v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\n\
+            o base\nf 1 3 2\n\
+            g sides\nf 1 2 4\nf 2 3 4\nf 3 1 4\n";
+        let model = load_model(obj, ModelFormat::Obj).unwrap();
+        let objects: Vec<(&str, usize, usize)> = model.objects.iter().map(|o| (o.name.as_str(), o.first_face, o.face_count)).collect();
+        assert_eq!(objects, vec![("base", 0, 1), ("sides", 1, 3)]);
+
+        let analysis = analyze_model(&model);
+        assert_eq!(analysis.objects.len(), 2);
+        assert!((analysis.objects[0].surface_area_cm2 - 0.005).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_compact_drops_unused_vertices() {
+        let mesh = Mesh {
+            vertices: vec![[9.0; 3], [0.0; 3], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            faces: vec![[1, 2, 3], [1, 3, 4], [2, 4, 3]],
+        };
+        let all = mesh.compact(None);
+        assert_eq!(all.vertices.len(), 4);
+        assert_eq!(all.faces, vec![[0, 1, 2], [0, 2, 3], [1, 3, 2]]);
+
+        let kept = mesh.compact(Some(&[false, true, false]));
+        assert_eq!(kept.vertices, vec![[0.0; 3], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]]);
+        assert_eq!(kept.faces, vec![[0, 1, 2]]);
+    }
 }
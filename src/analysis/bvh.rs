@@ -0,0 +1,243 @@
+use super::vec3::{cross, dot, sub, Vec3};
+use super::Mesh;
+
+const LEAF_SIZE: usize = 4;
+
+/// Bounding volume hierarchy over the faces of a mesh.
+pub struct Bvh {
+    nodes: Vec<Node>,
+    /// Face indices, ordered so every node covers a contiguous range.
+    faces: Vec<usize>,
+}
+
+struct Node {
+    min: Vec3,
+    max: Vec3,
+    /// Leaf: first entry in `faces`. Interior: index of the left child (right is `left + 1`).
+    start: usize,
+    /// Number of faces for leaves, 0 for interior nodes.
+    count: usize,
+}
+
+/// Closest ray/face intersection.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RayHit {
+    pub face: usize,
+    pub distance: f64,
+}
+
+/// `min_distance` (mm) for [`Bvh::ray_hit`] that keeps a ray from hitting the
+/// face it starts on.
+pub(crate) const RAY_EPSILON_MM: f64 = 1e-6;
+
+impl Bvh {
+    pub fn new(mesh: &Mesh) -> Self {
+        let centroids: Vec<Vec3> = (0..mesh.faces.len())
+            .map(|face| {
+                let [a, b, c] = mesh.triangle(face);
+                [(a[0] + b[0] + c[0]) / 3.0, (a[1] + b[1] + c[1]) / 3.0, (a[2] + b[2] + c[2]) / 3.0]
+            })
+            .collect();
+        let mut bvh = Bvh { nodes: Vec::with_capacity(2 * mesh.faces.len() / LEAF_SIZE + 1), faces: (0..mesh.faces.len()).collect() };
+        bvh.nodes.push(Node { min: [0.0; 3], max: [0.0; 3], start: 0, count: 0 });
+        bvh.build(mesh, &centroids, 0, 0, mesh.faces.len());
+        bvh
+    }
+
+    fn build(&mut self, mesh: &Mesh, centroids: &[Vec3], node: usize, start: usize, end: usize) {
+        let (min, max) = self.bounds(mesh, start, end);
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+
+        if end - start <= LEAF_SIZE {
+            self.nodes[node].start = start;
+            self.nodes[node].count = end - start;
+            return;
+        }
+
+        // Median split along the longest axis of the centroid spread
+        let mut cmin = [f64::INFINITY; 3];
+        let mut cmax = [f64::NEG_INFINITY; 3];
+        for &face in &self.faces[start..end] {
+            for axis in 0..3 {
+                cmin[axis] = cmin[axis].min(centroids[face][axis]);
+                cmax[axis] = cmax[axis].max(centroids[face][axis]);
+            }
+        }
+        let axis = (0..3).max_by(|&a, &b| (cmax[a] - cmin[a]).total_cmp(&(cmax[b] - cmin[b]))).unwrap_or(0);
+        let mid = (start + end) / 2;
+        self.faces[start..end].select_nth_unstable_by(mid - start, |&a, &b| centroids[a][axis].total_cmp(&centroids[b][axis]));
+
+        let left = self.nodes.len();
+        self.nodes.push(Node { min: [0.0; 3], max: [0.0; 3], start: 0, count: 0 });
+        self.nodes.push(Node { min: [0.0; 3], max: [0.0; 3], start: 0, count: 0 });
+        self.nodes[node].start = left;
+        self.build(mesh, centroids, left, start, mid);
+        self.build(mesh, centroids, left + 1, mid, end);
+    }
+
+    fn bounds(&self, mesh: &Mesh, start: usize, end: usize) -> (Vec3, Vec3) {
+        let mut min = [f64::INFINITY; 3];
+        let mut max = [f64::NEG_INFINITY; 3];
+        for &face in &self.faces[start..end] {
+            for v in mesh.triangle(face) {
+                for axis in 0..3 {
+                    min[axis] = min[axis].min(v[axis]);
+                    max[axis] = max[axis].max(v[axis]);
+                }
+            }
+        }
+        (min, max)
+    }
+
+    /// Nearest face hit by the ray `origin + t * direction` with `min_distance < t`,
+    /// ignoring `skip`. Distances are in units of `direction`'s length.
+    pub fn ray_hit(&self, mesh: &Mesh, origin: Vec3, direction: Vec3, min_distance: f64, skip: Option<usize>) -> Option<RayHit> {
+        if self.faces.is_empty() {
+            return None;
+        }
+        let inverse = direction.map(|d| 1.0 / d);
+        let mut best: Option<RayHit> = None;
+        let mut stack = vec![0];
+
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            let limit = best.map_or(f64::INFINITY, |hit| hit.distance);
+            if !ray_hits_box(origin, inverse, node.min, node.max, limit) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.start);
+                stack.push(node.start + 1);
+                continue;
+            }
+            for &face in &self.faces[node.start..node.start + node.count] {
+                if Some(face) == skip {
+                    continue;
+                }
+                let [a, b, c] = mesh.triangle(face);
+                if let Some(t) = ray_triangle(origin, direction, a, b, c)
+                    && t > min_distance
+                    && best.is_none_or(|hit| t < hit.distance)
+                {
+                    best = Some(RayHit { face, distance: t });
+                }
+            }
+        }
+        best
+    }
+
+    /// Faces that may overlap the box `min`..`max` (leaves are returned whole).
+    pub fn faces_in_box(&self, min: Vec3, max: Vec3) -> Vec<usize> {
+        let mut found = Vec::new();
+        if self.faces.is_empty() {
+            return found;
+        }
+        let overlaps = |node: &Node| (0..3).all(|axis| node.min[axis] <= max[axis] && min[axis] <= node.max[axis]);
+        let mut stack = vec![0];
+        while let Some(index) = stack.pop() {
+            let node = &self.nodes[index];
+            if !overlaps(node) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.start);
+                stack.push(node.start + 1);
+            } else {
+                found.extend_from_slice(&self.faces[node.start..node.start + node.count]);
+            }
+        }
+        found
+    }
+}
+
+/// Slab test; true if the ray enters the box before `limit`.
+fn ray_hits_box(origin: Vec3, inverse: Vec3, min: Vec3, max: Vec3, limit: f64) -> bool {
+    let mut near = f64::NEG_INFINITY;
+    let mut far = limit;
+    for axis in 0..3 {
+        let t1 = (min[axis] - origin[axis]) * inverse[axis];
+        let t2 = (max[axis] - origin[axis]) * inverse[axis];
+        // NaN (0 * inf) means the ray lies in the slab's plane; treat it as inside
+        let (lo, hi) = if t1 <= t2 { (t1, t2) } else { (t2, t1) };
+        if !lo.is_nan() {
+            near = near.max(lo);
+        }
+        if !hi.is_nan() {
+            far = far.min(hi);
+        }
+    }
+    near <= far && far >= 0.0
+}
+
+/// Möller–Trumbore ray/triangle intersection; returns the ray parameter of the hit.
+pub fn ray_triangle(origin: Vec3, direction: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Option<f64> {
+    let e1 = sub(b, a);
+    let e2 = sub(c, a);
+    let p = cross(direction, e2);
+    let det = dot(e1, p);
+    if det.abs() < 1e-15 {
+        return None;
+    }
+    let inv = 1.0 / det;
+    let s = sub(origin, a);
+    let u = dot(s, p) * inv;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross(s, e1);
+    let v = dot(direction, q) * inv;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some(dot(e2, q) * inv)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Grid of `n` x `n` unit squares in the plane z = `z`.
+    fn grid(n: usize, z: f64) -> Mesh {
+        let mut mesh = Mesh::default();
+        for y in 0..=n {
+            for x in 0..=n {
+                mesh.vertices.push([x as f64, y as f64, z]);
+            }
+        }
+        for y in 0..n {
+            for x in 0..n {
+                let i = y * (n + 1) + x;
+                mesh.faces.push([i, i + 1, i + n + 2]);
+                mesh.faces.push([i, i + n + 2, i + n + 1]);
+            }
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_ray_hit_matches_brute_force() {
+        let mut mesh = grid(10, 0.0);
+        let upper = grid(10, 5.0);
+        let offset = mesh.vertices.len();
+        mesh.vertices.extend(upper.vertices);
+        mesh.faces.extend(upper.faces.iter().map(|f| f.map(|v| v + offset)));
+        let bvh = Bvh::new(&mesh);
+
+        for (origin, direction) in [
+            ([3.3, 4.7, -1.0], [0.0, 0.0, 1.0]),
+            ([3.3, 4.7, 2.0], [0.0, 0.0, 1.0]),
+            ([3.3, 4.7, 9.0], [0.1, -0.2, -1.0]),
+            ([20.0, 20.0, 1.0], [0.0, 0.0, 1.0]),
+        ] {
+            let brute = (0..mesh.faces.len())
+                .filter_map(|face| {
+                    let [a, b, c] = mesh.triangle(face);
+                    ray_triangle(origin, direction, a, b, c).filter(|&t| t > 0.0).map(|t| (t, face))
+                })
+                .min_by(|a, b| a.0.total_cmp(&b.0));
+            let hit = bvh.ray_hit(&mesh, origin, direction, 0.0, None);
+            assert_eq!(hit.map(|h| h.distance), brute.map(|b| b.0));
+        }
+    }
+}
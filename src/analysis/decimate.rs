@@ -0,0 +1,302 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use super::validation::weld_exact;
+use super::vec3::{cross, dot, face_normal, normalize, sub, Vec3};
+use super::Mesh;
+
+/// Triangle budget for the web viewer's preview mesh.
+pub const PREVIEW_MAX_TRIANGLES: usize = 50_000;
+/// Open edges are held in place this much more strongly than surfaces.
+const BOUNDARY_WEIGHT: f64 = 1000.0;
+
+/// Simplifies the mesh to at most `max_faces` triangles by quadric edge collapse
+/// (Garland & Heckbert). Collapses that would flip a face or pinch the surface
+/// are skipped, so very tangled meshes may end up above the budget.
+pub fn decimate(mesh: &Mesh, max_faces: usize) -> Mesh {
+    let mut faces: Vec<[usize; 3]> = weld_exact(mesh)
+        .into_iter()
+        .filter(|f| f[0] != f[1] && f[1] != f[2] && f[0] != f[2])
+        .collect();
+    if faces.len() <= max_faces {
+        return Mesh { vertices: mesh.vertices.clone(), faces }.compact(None);
+    }
+
+    let mut positions = mesh.vertices.clone();
+    let mut quadrics = vec![Quadric::default(); positions.len()];
+    let mut vertex_faces: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+    let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    for (face, f) in faces.iter().enumerate() {
+        let [a, b, c] = f.map(|v| positions[v]);
+        let normal = normalize(face_normal(a, b, c));
+        let plane = Quadric::plane(normal, a, 1.0);
+        for i in 0..3 {
+            quadrics[f[i]].add(&plane);
+            vertex_faces[f[i]].push(face);
+            let key = (f[i].min(f[(i + 1) % 3]), f[i].max(f[(i + 1) % 3]));
+            edges.entry(key).or_insert((0, face)).0 += 1;
+        }
+    }
+    // A plane through each open edge, perpendicular to its face, keeps the outline in place
+    for (&(a, b), &(count, face)) in &edges {
+        if count == 1 {
+            let f = faces[face];
+            let normal = normalize(face_normal(positions[f[0]], positions[f[1]], positions[f[2]]));
+            let across = normalize(cross(sub(positions[b], positions[a]), normal));
+            let plane = Quadric::plane(across, positions[a], BOUNDARY_WEIGHT);
+            quadrics[a].add(&plane);
+            quadrics[b].add(&plane);
+        }
+    }
+
+    let mut alive = vec![true; faces.len()];
+    let mut live_faces = faces.len();
+    let mut version = vec![0u32; positions.len()];
+    let mut heap = BinaryHeap::new();
+    for &(a, b) in edges.keys() {
+        heap.push(collapse_candidate(a, b, &positions, &quadrics, &version));
+    }
+
+    while live_faces > max_faces {
+        let Some(candidate) = heap.pop() else { break };
+        let (u, v) = (candidate.keep, candidate.remove);
+        if version[u] != candidate.versions[0] || version[v] != candidate.versions[1] {
+            continue;
+        }
+        vertex_faces[u].retain(|&face| alive[face]);
+        vertex_faces[v].retain(|&face| alive[face]);
+        let shared: Vec<usize> = vertex_faces[v].iter().copied().filter(|&face| faces[face].contains(&u)).collect();
+        if shared.is_empty() || !link_condition(u, v, &faces, &vertex_faces, shared.len()) {
+            continue;
+        }
+        let target = candidate.position;
+        let flips = [u, v].iter().flat_map(|&w| vertex_faces[w].iter().map(move |&face| (w, face))).any(|(w, face)| {
+            !shared.contains(&face) && flips_face(faces[face], w, target, &positions)
+        });
+        if flips {
+            continue;
+        }
+
+        for &face in &shared {
+            alive[face] = false;
+        }
+        live_faces -= shared.len();
+        let moved: Vec<usize> = vertex_faces[v].iter().copied().filter(|&face| alive[face]).collect();
+        for &face in &moved {
+            for corner in faces[face].iter_mut().filter(|corner| **corner == v) {
+                *corner = u;
+            }
+        }
+        vertex_faces[u].retain(|&face| alive[face]);
+        vertex_faces[u].extend(moved);
+        vertex_faces[v].clear();
+        positions[u] = target;
+        let removed = quadrics[v];
+        quadrics[u].add(&removed);
+        version[u] += 1;
+        version[v] += 1;
+
+        let mut neighbours: Vec<usize> = vertex_faces[u].iter().flat_map(|&face| faces[face]).filter(|&w| w != u).collect();
+        neighbours.sort_unstable();
+        neighbours.dedup();
+        for w in neighbours {
+            heap.push(collapse_candidate(u, w, &positions, &quadrics, &version));
+        }
+    }
+    Mesh { vertices: positions, faces }.compact(Some(&alive))
+}
+
+/// Symmetric 4x4 error quadric: `xx xy xz yy yz zz` of A, then b, then c.
+#[derive(Debug, Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Squared distance to the plane with unit `normal` through `point`, times `weight`.
+    fn plane(normal: Vec3, point: Vec3, weight: f64) -> Self {
+        let [a, b, c] = normal;
+        let d = -dot(normal, point);
+        Quadric([a * a, a * b, a * c, b * b, b * c, c * c, a * d, b * d, c * d, d * d].map(|q| q * weight))
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (q, o) in self.0.iter_mut().zip(other.0) {
+            *q += o;
+        }
+    }
+
+    fn error(&self, p: Vec3) -> f64 {
+        let q = &self.0;
+        let [x, y, z] = p;
+        q[0] * x * x + 2.0 * q[1] * x * y + 2.0 * q[2] * x * z + q[3] * y * y + 2.0 * q[4] * y * z + q[5] * z * z
+            + 2.0 * (q[6] * x + q[7] * y + q[8] * z)
+            + q[9]
+    }
+
+    /// Point of least error, when A is invertible.
+    fn minimum(&self) -> Option<Vec3> {
+        let q = &self.0;
+        let rows = [[q[0], q[1], q[2]], [q[1], q[3], q[4]], [q[2], q[4], q[5]]];
+        let det = dot(rows[0], cross(rows[1], rows[2]));
+        if det.abs() < 1e-9 {
+            return None;
+        }
+        // Cramer's rule for A p = -b
+        let rhs = [-q[6], -q[7], -q[8]];
+        let column = |axis: usize| {
+            let mut m = rows;
+            for (row, value) in m.iter_mut().zip(rhs) {
+                row[axis] = value;
+            }
+            dot(m[0], cross(m[1], m[2])) / det
+        };
+        Some([column(0), column(1), column(2)])
+    }
+}
+
+/// Collapsing `remove` into `keep`, and where the merged vertex goes.
+struct Candidate {
+    cost: f64,
+    keep: usize,
+    remove: usize,
+    position: Vec3,
+    versions: [u32; 2],
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Candidate {}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Candidate {
+    /// Reversed so the max-heap pops the cheapest collapse first.
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+fn collapse_candidate(a: usize, b: usize, positions: &[Vec3], quadrics: &[Quadric], version: &[u32]) -> Candidate {
+    let mut quadric = quadrics[a];
+    quadric.add(&quadrics[b]);
+    let (pa, pb) = (positions[a], positions[b]);
+    let midpoint = [(pa[0] + pb[0]) / 2.0, (pa[1] + pb[1]) / 2.0, (pa[2] + pb[2]) / 2.0];
+    let (position, cost) = quadric
+        .minimum()
+        .into_iter()
+        .chain([pa, pb, midpoint])
+        .map(|p| (p, quadric.error(p)))
+        .min_by(|x, y| x.1.total_cmp(&y.1))
+        .expect("at least the endpoints are candidates");
+    Candidate { cost, keep: a, remove: b, position, versions: [version[a], version[b]] }
+}
+
+/// Vertices adjacent to both ends must be exactly the far corners of the faces
+/// on the edge, or the collapse would fuse two sheets of the surface.
+fn link_condition(u: usize, v: usize, faces: &[[usize; 3]], vertex_faces: &[Vec<usize>], shared: usize) -> bool {
+    let ring = |w: usize| {
+        let mut ring: Vec<usize> = vertex_faces[w].iter().flat_map(|&face| faces[face]).filter(|&x| x != u && x != v).collect();
+        ring.sort_unstable();
+        ring.dedup();
+        ring
+    };
+    let around_v = ring(v);
+    ring(u).iter().filter(|w| around_v.binary_search(w).is_ok()).count() == shared
+}
+
+/// Whether moving corner `moved` of a face to `target` turns the face over.
+fn flips_face(face: [usize; 3], moved: usize, target: Vec3, positions: &[Vec3]) -> bool {
+    let before = face.map(|v| positions[v]);
+    let after = face.map(|v| if v == moved { target } else { positions[v] });
+    dot(face_normal(before[0], before[1], before[2]), face_normal(after[0], after[1], after[2])) <= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_mesh;
+
+    /// UV sphere with outward-facing triangles.
+    fn sphere(radius: f64, rings: usize, segments: usize) -> Mesh {
+        let mut mesh = Mesh::default();
+        mesh.vertices.push([0.0, 0.0, radius]);
+        for ring in 1..rings {
+            let theta = std::f64::consts::PI * ring as f64 / rings as f64;
+            for segment in 0..segments {
+                let phi = std::f64::consts::TAU * segment as f64 / segments as f64;
+                mesh.vertices.push([radius * theta.sin() * phi.cos(), radius * theta.sin() * phi.sin(), radius * theta.cos()]);
+            }
+        }
+        mesh.vertices.push([0.0, 0.0, -radius]);
+        let bottom = mesh.vertices.len() - 1;
+        let at = |ring: usize, segment: usize| 1 + (ring - 1) * segments + segment % segments;
+        for s in 0..segments {
+            mesh.faces.push([0, at(1, s), at(1, s + 1)]);
+            mesh.faces.push([bottom, at(rings - 1, s + 1), at(rings - 1, s)]);
+            for ring in 1..rings - 1 {
+                mesh.faces.push([at(ring, s), at(ring + 1, s), at(ring + 1, s + 1)]);
+                mesh.faces.push([at(ring, s), at(ring + 1, s + 1), at(ring, s + 1)]);
+            }
+        }
+        mesh
+    }
+
+    #[test]
+    fn test_sphere_keeps_its_shape() {
+        let original = sphere(10.0, 30, 60);
+        let decimated = decimate(&original, 400);
+        assert!(decimated.faces.len() <= 400);
+        assert!(decimated.faces.len() > 300);
+
+        let (before, after) = (analyze_mesh(&original), analyze_mesh(&decimated));
+        assert!(after.validation.is_watertight);
+        assert!((after.volume_cm3 - before.volume_cm3).abs() / before.volume_cm3 < 0.05);
+        for v in &decimated.vertices {
+            let radius = dot(*v, *v).sqrt();
+            assert!((radius - 10.0).abs() < 0.5, "vertex drifted to radius {}", radius);
+        }
+    }
+
+    #[test]
+    fn test_flat_sheet_keeps_its_outline() {
+        let n = 10;
+        let mut sheet = Mesh::default();
+        for y in 0..=n {
+            for x in 0..=n {
+                sheet.vertices.push([x as f64, y as f64, 0.0]);
+            }
+        }
+        for y in 0..n {
+            for x in 0..n {
+                let v = y * (n + 1) + x;
+                sheet.faces.push([v, v + 1, v + n + 2]);
+                sheet.faces.push([v, v + n + 2, v + n + 1]);
+            }
+        }
+        let decimated = decimate(&sheet, 20);
+        assert!(decimated.faces.len() <= 20);
+        assert!(decimated.vertices.iter().all(|v| v[2].abs() < 1e-9));
+        assert_eq!(decimated.bounding_box(), sheet.bounding_box());
+        let area: f64 = (0..decimated.faces.len())
+            .map(|face| {
+                let [a, b, c] = decimated.triangle(face);
+                face_normal(a, b, c)[2] / 2.0
+            })
+            .sum();
+        assert!((area - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_small_meshes_are_left_alone() {
+        let original = sphere(5.0, 6, 8);
+        let decimated = decimate(&original, PREVIEW_MAX_TRIANGLES);
+        assert_eq!(decimated.faces.len(), original.faces.len());
+        assert!((analyze_mesh(&decimated).volume_cm3 - analyze_mesh(&original).volume_cm3).abs() < 1e-12);
+    }
+}
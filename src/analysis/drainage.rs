@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use super::bvh::{Bvh, RAY_EPSILON_MM};
+use super::shells::{analyze_shells, Shell};
+use super::vec3::{add, centroid, normalize, scale};
+use super::{BoundingBox, Mesh};
+
+/// Smallest drain hole uncured resin reliably flows through.
+pub const DRAIN_HOLE_DIAMETER_MM: f64 = 3.0;
+
+/// A hole to drill from a sealed cavity to the outside of the part.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrainHole {
+    /// Where the hole opens into the cavity.
+    pub cavity_point: [f64; 3],
+    /// Where the hole breaks through the outer surface.
+    pub surface_point: [f64; 3],
+    /// Unit vector from `cavity_point` to `surface_point`.
+    pub direction: [f64; 3],
+    /// Wall thickness along the hole.
+    pub length_mm: f64,
+    pub diameter_mm: f64,
+}
+
+/// An internal void that would fill with uncured resin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrappedCavity {
+    /// Index of the void in the file's `shells`.
+    pub shell_index: usize,
+    /// Resin trapped once anything inside the cavity is subtracted.
+    pub volume_cm3: f64,
+    pub bounding_box: BoundingBox,
+    /// Drain hole at the cavity's lowest point, then a vent at its highest;
+    /// empty when no straight path leads out of the part.
+    pub drain_holes: Vec<DrainHole>,
+}
+
+/// Sealed cavities in a part printed in resin.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResinDrainage {
+    pub cavities: Vec<TrappedCavity>,
+    pub trapped_volume_cm3: f64,
+    /// No cavity traps resin; otherwise drill the suggested holes before printing.
+    pub is_drainable: bool,
+}
+
+/// Finds internal voids and the shortest holes draining each one.
+pub fn analyze_drainage(mesh: &Mesh) -> ResinDrainage {
+    let shells = analyze_shells(mesh);
+    let mut shell_of_face = vec![0; mesh.faces.len()];
+    for shell in &shells {
+        for &face in &shell.faces {
+            shell_of_face[face] = shell.index;
+        }
+    }
+    let bvh = Bvh::new(mesh);
+
+    let cavities: Vec<TrappedCavity> = shells
+        .iter()
+        .filter(|shell| shell.is_void)
+        .map(|void| {
+            let outer = void.enclosed_by.expect("voids are enclosed");
+            let lowest = extreme_face(mesh, void, |a, b| a < b);
+            let highest = extreme_face(mesh, void, |a, b| a > b);
+            let mut drain_holes: Vec<DrainHole> = [lowest, highest]
+                .into_iter()
+                .filter_map(|face| shortest_hole(mesh, &bvh, &shell_of_face, face, outer))
+                .collect();
+            drain_holes.dedup();
+            TrappedCavity {
+                shell_index: void.index,
+                volume_cm3: void.net_volume_cm3,
+                bounding_box: void.bounding_box,
+                drain_holes,
+            }
+        })
+        .collect();
+
+    ResinDrainage {
+        trapped_volume_cm3: cavities.iter().map(|c| c.volume_cm3).sum(),
+        is_drainable: cavities.is_empty(),
+        cavities,
+    }
+}
+
+/// Face of `shell` whose centroid is furthest along Z in the `better` sense.
+fn extreme_face(mesh: &Mesh, shell: &Shell, better: impl Fn(f64, f64) -> bool) -> usize {
+    let z = |face: usize| {
+        let [a, b, c] = mesh.triangle(face);
+        centroid(a, b, c)[2]
+    };
+    shell.faces.iter().copied().reduce(|best, face| if better(z(face), z(best)) { face } else { best }).expect("shells have faces")
+}
+
+/// Shortest straight hole from the centroid of `face` through the wall to the
+/// surface of shell `outer`, trying the 26 axis and diagonal directions.
+/// Directions that run back into the cavity hit its own faces and are skipped.
+fn shortest_hole(mesh: &Mesh, bvh: &Bvh, shell_of_face: &[usize], face: usize, outer: usize) -> Option<DrainHole> {
+    let [a, b, c] = mesh.triangle(face);
+    let origin = centroid(a, b, c);
+    let mut best: Option<DrainHole> = None;
+    for x in -1..=1 {
+        for y in -1..=1 {
+            // Downward first so ties favour holes that drain by gravity
+            for z in [-1, 0, 1] {
+                if (x, y, z) == (0, 0, 0) {
+                    continue;
+                }
+                let direction = normalize([x as f64, y as f64, z as f64]);
+                let Some(hit) = bvh.ray_hit(mesh, origin, direction, RAY_EPSILON_MM, Some(face)) else {
+                    continue;
+                };
+                if shell_of_face[hit.face] != outer || best.as_ref().is_some_and(|h| h.length_mm <= hit.distance) {
+                    continue;
+                }
+                best = Some(DrainHole {
+                    cavity_point: origin,
+                    surface_point: add(origin, scale(direction, hit.distance)),
+                    direction,
+                    length_mm: hit.distance,
+                    diameter_mm: DRAIN_HOLE_DIAMETER_MM,
+                });
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::add_box;
+
+    #[test]
+    fn test_solid_part_drains() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [20.0; 3], false);
+        let drainage = analyze_drainage(&mesh);
+        assert!(drainage.is_drainable);
+        assert!(drainage.cavities.is_empty());
+        assert_eq!(drainage.trapped_volume_cm3, 0.0);
+    }
+
+    #[test]
+    fn test_hollow_box_gets_drain_and_vent() {
+        // 20 mm box with a 10 mm cavity; floor 2 mm thick, roof 8 mm
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [20.0; 3], false);
+        add_box(&mut mesh, [5.0, 5.0, 2.0], [15.0, 15.0, 12.0], true);
+        let drainage = analyze_drainage(&mesh);
+
+        assert!(!drainage.is_drainable);
+        assert_eq!(drainage.cavities.len(), 1);
+        let cavity = &drainage.cavities[0];
+        assert_eq!(cavity.shell_index, 1);
+        assert!((cavity.volume_cm3 - 1.0).abs() < 1e-9);
+        assert!((drainage.trapped_volume_cm3 - 1.0).abs() < 1e-9);
+
+        let [drain, vent] = &cavity.drain_holes[..] else { panic!("{:?}", cavity.drain_holes) };
+        assert_eq!(drain.direction, [0.0, 0.0, -1.0]);
+        assert!((drain.length_mm - 2.0).abs() < 1e-9);
+        assert!(drain.surface_point[2].abs() < 1e-9);
+        assert_eq!(vent.direction, [0.0, 0.0, 1.0]);
+        assert!((vent.length_mm - 8.0).abs() < 1e-9);
+        assert_eq!(vent.cavity_point[2], 12.0);
+    }
+}
@@ -0,0 +1,87 @@
+use std::fmt;
+use axum::http::StatusCode;
+use serde::{Deserialize, Serialize};
+use super::ModelFormat;
+
+/// Largest mesh we analyze; a full 100 MB binary STL is about 2M triangles.
+pub const MAX_TRIANGLES: u64 = 5_000_000;
+
+/// Why a model could not be read. Serialized with a stable `code` plus the
+/// values a client needs to explain it, e.g. `{"code": "NO_FACES"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AnalysisError {
+    UnsupportedFormat,
+    /// Binary STL shorter than its header's triangle count requires.
+    TruncatedBinary { expected_bytes: u64, actual_bytes: u64 },
+    /// Text that doesn't follow the format (ASCII STL, OBJ, 3MF XML or package).
+    InvalidSyntax { format: ModelFormat, detail: String },
+    NoFaces,
+    /// Vertices used by faces with NaN or infinite coordinates.
+    NonFiniteCoordinates { vertices: usize },
+    TooManyTriangles { triangles: u64, limit: u64 },
+}
+
+impl AnalysisError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AnalysisError::UnsupportedFormat => "UNSUPPORTED_FORMAT",
+            AnalysisError::TruncatedBinary { .. } => "TRUNCATED_BINARY",
+            AnalysisError::InvalidSyntax { .. } => "INVALID_SYNTAX",
+            AnalysisError::NoFaces => "NO_FACES",
+            AnalysisError::NonFiniteCoordinates { .. } => "NON_FINITE_COORDINATES",
+            AnalysisError::TooManyTriangles { .. } => "TOO_MANY_TRIANGLES",
+        }
+    }
+
+    /// HTTP status of a request that failed on this model.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AnalysisError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            _ => StatusCode::UNPROCESSABLE_ENTITY,
+        }
+    }
+
+    pub(crate) fn syntax(format: ModelFormat, detail: impl Into<String>) -> Self {
+        AnalysisError::InvalidSyntax { format, detail: detail.into() }
+    }
+}
+
+impl fmt::Display for AnalysisError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AnalysisError::UnsupportedFormat => write!(f, "Unsupported file format (expected STL, OBJ or 3MF)"),
+            AnalysisError::TruncatedBinary { expected_bytes, actual_bytes } => {
+                write!(f, "Binary STL is truncated: expected {} bytes, got {}", expected_bytes, actual_bytes)
+            }
+            AnalysisError::InvalidSyntax { format, detail } => write!(f, "Invalid {}: {}", format.as_str(), detail),
+            AnalysisError::NoFaces => write!(f, "Model contains no faces"),
+            AnalysisError::NonFiniteCoordinates { vertices } => {
+                write!(f, "Model has {} vertices with NaN or infinite coordinates", vertices)
+            }
+            AnalysisError::TooManyTriangles { triangles, limit } => {
+                write!(f, "Model has {} triangles, more than the {} that can be analyzed", triangles, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AnalysisError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_serialize_with_code() {
+        let error = AnalysisError::TooManyTriangles { triangles: 6_000_000, limit: MAX_TRIANGLES };
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["code"], error.code());
+        assert_eq!(json["limit"], MAX_TRIANGLES);
+        assert_eq!(serde_json::from_value::<AnalysisError>(json).unwrap(), error);
+
+        let syntax = serde_json::to_value(AnalysisError::syntax(ModelFormat::ThreeMf, "bad zip")).unwrap();
+        assert_eq!(syntax, serde_json::json!({ "code": "INVALID_SYNTAX", "format": "3MF", "detail": "bad zip" }));
+        assert_eq!(serde_json::to_value(AnalysisError::NoFaces).unwrap(), serde_json::json!({ "code": "NO_FACES" }));
+    }
+}
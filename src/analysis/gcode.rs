@@ -0,0 +1,369 @@
+use serde::{Deserialize, Serialize};
+use super::slicer::{Contour, SlicedModel};
+use super::toolpath::{estimate_print, MachineProfile};
+
+/// Travel moves longer than this (mm) retract the filament first.
+const RETRACT_MIN_TRAVEL_MM: f64 = 2.0;
+const RETRACT_LENGTH_MM: f64 = 0.8;
+const RETRACT_SPEED_MM_S: f64 = 40.0;
+/// Z hop (mm) when parking the head after the print.
+const PARK_LIFT_MM: f64 = 10.0;
+
+/// Firmware dialect of the start/end sequence (`printers.gcode_flavor`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum GcodeFlavor {
+    Marlin,
+    Klipper,
+}
+
+impl GcodeFlavor {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GcodeFlavor::Marlin => "MARLIN",
+            GcodeFlavor::Klipper => "KLIPPER",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "MARLIN" => Some(GcodeFlavor::Marlin),
+            "KLIPPER" => Some(GcodeFlavor::Klipper),
+            _ => None,
+        }
+    }
+}
+
+/// Job settings that don't come from the machine profile.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GcodeJob {
+    pub flavor: GcodeFlavor,
+    pub nozzle_temperature_c: u32,
+    pub bed_temperature_c: u32,
+    pub infill_percentage: f64,
+    /// Bed position (mm) the part is centered on.
+    pub bed_center: [f64; 2],
+}
+
+/// Emits G-code for the sliced model: perimeters, solid top/bottom skins and
+/// sparse rectilinear infill. Supports are not generated, so the model should
+/// already be in a support-free orientation.
+pub fn generate_gcode(sliced: &SlicedModel, profile: &MachineProfile, job: &GcodeJob) -> String {
+    let estimate = estimate_print(sliced, profile, job.infill_percentage);
+    let width = profile.line_width_mm();
+    let height = sliced.layer_height_mm;
+    let filament_area = std::f64::consts::PI * (profile.filament_diameter_mm / 2.0).powi(2);
+    let shift = centering_shift(sliced, job.bed_center);
+
+    let mut out = Emitter {
+        gcode: String::new(),
+        position: None,
+        e_per_mm: width * height / filament_area,
+        travel_feed: profile.travel_speed_mm_s * 60.0,
+    };
+    out.line(&format!(";FLAVOR:{}", match job.flavor { GcodeFlavor::Marlin => "Marlin", GcodeFlavor::Klipper => "Klipper" }));
+    out.line(&format!(";TIME:{:.0}", estimate.print_time_s));
+    out.line(&format!(";Filament used: {:.3}m", estimate.filament_length_mm / 1000.0));
+    out.line(&format!(";Layer height: {}", height));
+    out.line(&format!(";LAYER_COUNT:{}", sliced.layers.len()));
+    match job.flavor {
+        GcodeFlavor::Marlin => {
+            out.line(&format!("M140 S{}", job.bed_temperature_c));
+            out.line(&format!("M104 S{}", job.nozzle_temperature_c));
+            out.line(&format!("M190 S{}", job.bed_temperature_c));
+            out.line(&format!("M109 S{}", job.nozzle_temperature_c));
+            out.line("G28");
+            out.line(&format!("M204 S{:.0}", profile.acceleration_mm_s2));
+        }
+        GcodeFlavor::Klipper => {
+            out.line(&format!("SET_VELOCITY_LIMIT ACCEL={:.0}", profile.acceleration_mm_s2));
+            out.line(&format!("PRINT_START BED={} EXTRUDER={}", job.bed_temperature_c, job.nozzle_temperature_c));
+        }
+    }
+    out.line("G90");
+    out.line("M83");
+    out.line("G92 E0");
+
+    let solid = profile.solid_layers as usize;
+    let areas: Vec<f64> = sliced.layers.iter().map(|layer| layer.area_mm2).collect();
+    for (index, layer) in sliced.layers.iter().enumerate() {
+        out.line(&format!(";LAYER:{}", index));
+        if index == 1 {
+            out.line("M106 S255");
+        }
+        out.line(&format!("G0 Z{:.3} F{:.0}", (index + 1) as f64 * height, out.travel_feed));
+
+        let contours: Vec<Vec<[f64; 2]>> = layer
+            .contours
+            .iter()
+            .filter(|c| c.closed && c.area_mm2 != 0.0)
+            .map(|c| c.points.iter().map(|p| [p[0] + shift[0], p[1] + shift[1]]).collect())
+            .collect();
+
+        // Perimeters from the outside in
+        let perimeter_feed = profile.perimeter_speed_mm_s * 60.0;
+        for ring in 0..profile.perimeters {
+            for contour in &contours {
+                if let Some(path) = inset(contour, width * (ring as f64 + 0.5)) {
+                    out.extrude_loop(&path, perimeter_feed);
+                }
+            }
+        }
+
+        // Layers not covered by `solid` layers above and below get solid infill
+        let exposed = index < solid
+            || index + solid >= areas.len()
+            || areas[index - solid..=index + solid].iter().any(|&area| area < layer.area_mm2 - width * width);
+        let spacing = if exposed {
+            width
+        } else if job.infill_percentage > 0.0 {
+            width / (job.infill_percentage / 100.0).min(1.0)
+        } else {
+            continue;
+        };
+        let region: Vec<Vec<[f64; 2]>> = contours
+            .iter()
+            .filter_map(|contour| inset(contour, width * profile.perimeters as f64))
+            .collect();
+        let infill_feed = profile.infill_speed_mm_s * 60.0;
+        for [start, end] in scanlines(&region, spacing, index % 2 == 1) {
+            out.travel(start);
+            out.extrude(end, infill_feed);
+        }
+    }
+
+    out.line("M107");
+    match job.flavor {
+        GcodeFlavor::Marlin => {
+            out.line("M104 S0");
+            out.line("M140 S0");
+            out.line("G91");
+            out.line(&format!("G0 Z{} F{:.0}", PARK_LIFT_MM, out.travel_feed));
+            out.line("G90");
+            out.line("M84");
+        }
+        GcodeFlavor::Klipper => out.line("PRINT_END"),
+    }
+    out.gcode
+}
+
+struct Emitter {
+    gcode: String,
+    position: Option<[f64; 2]>,
+    /// Filament (mm) fed per mm of extruded line.
+    e_per_mm: f64,
+    travel_feed: f64,
+}
+
+impl Emitter {
+    fn line(&mut self, line: &str) {
+        self.gcode.push_str(line);
+        self.gcode.push('\n');
+    }
+
+    fn travel(&mut self, to: [f64; 2]) {
+        let distance = self.position.map_or(f64::INFINITY, |from| distance(from, to));
+        if distance < 1e-6 {
+            return;
+        }
+        let retract = distance > RETRACT_MIN_TRAVEL_MM && self.position.is_some();
+        if retract {
+            self.line(&format!("G1 E-{} F{:.0}", RETRACT_LENGTH_MM, RETRACT_SPEED_MM_S * 60.0));
+        }
+        self.line(&format!("G0 X{:.3} Y{:.3} F{:.0}", to[0], to[1], self.travel_feed));
+        if retract {
+            self.line(&format!("G1 E{} F{:.0}", RETRACT_LENGTH_MM, RETRACT_SPEED_MM_S * 60.0));
+        }
+        self.position = Some(to);
+    }
+
+    fn extrude(&mut self, to: [f64; 2], feed: f64) {
+        let length = self.position.map_or(0.0, |from| distance(from, to));
+        self.line(&format!("G1 X{:.3} Y{:.3} E{:.5} F{:.0}", to[0], to[1], length * self.e_per_mm, feed));
+        self.position = Some(to);
+    }
+
+    fn extrude_loop(&mut self, points: &[[f64; 2]], feed: f64) {
+        self.travel(points[0]);
+        for &point in points.iter().skip(1).chain(std::iter::once(&points[0])) {
+            self.extrude(point, feed);
+        }
+    }
+}
+
+/// XY shift putting the middle of the sliced footprint on `center`.
+fn centering_shift(sliced: &SlicedModel, center: [f64; 2]) -> [f64; 2] {
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for point in sliced.layers.iter().flat_map(|layer| layer.contours.iter()).flat_map(|c: &Contour| c.points.iter()) {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(point[axis]);
+            max[axis] = max[axis].max(point[axis]);
+        }
+    }
+    if min[0] > max[0] {
+        return [0.0; 2];
+    }
+    [center[0] - (min[0] + max[0]) / 2.0, center[1] - (min[1] + max[1]) / 2.0]
+}
+
+/// Offsets a contour by `amount` into the material (left of its direction).
+/// Returns `None` once the feature is too thin for the offset.
+fn inset(points: &[[f64; 2]], amount: f64) -> Option<Vec<[f64; 2]>> {
+    let n = points.len();
+    if n < 3 {
+        return None;
+    }
+    let left_normal = |a: [f64; 2], b: [f64; 2]| {
+        let length = distance(a, b).max(1e-12);
+        [-(b[1] - a[1]) / length, (b[0] - a[0]) / length]
+    };
+    let moved: Vec<[f64; 2]> = (0..n)
+        .map(|i| {
+            let (prev, point, next) = (points[(i + n - 1) % n], points[i], points[(i + 1) % n]);
+            let (n1, n2) = (left_normal(prev, point), left_normal(point, next));
+            let bisector = [n1[0] + n2[0], n1[1] + n2[1]];
+            let length = (bisector[0] * bisector[0] + bisector[1] * bisector[1]).sqrt();
+            if length < 1e-9 {
+                return [point[0] + n1[0] * amount, point[1] + n1[1] * amount];
+            }
+            // Miter, limited to twice the offset on sharp corners
+            let cos = ((bisector[0] * n1[0] + bisector[1] * n1[1]) / length).max(0.5);
+            let scale = amount / cos / length;
+            [point[0] + bisector[0] * scale, point[1] + bisector[1] * scale]
+        })
+        .collect();
+
+    // Outer boundaries shrink and holes grow; a flipped outline has collapsed
+    let (before, after) = (shoelace(points), shoelace(&moved));
+    let valid = if before > 0.0 { after > 0.0 && after < before } else { after < before };
+    valid.then_some(moved)
+}
+
+/// Even-odd fill lines across the polygons, `spacing` apart along Y (or X when
+/// `along_y`), alternating direction.
+fn scanlines(polygons: &[Vec<[f64; 2]>], spacing: f64, along_y: bool) -> Vec<[[f64; 2]; 2]> {
+    let swap = |p: [f64; 2]| if along_y { [p[1], p[0]] } else { p };
+    let (lo, hi) = polygons
+        .iter()
+        .flatten()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &p| (lo.min(swap(p)[1]), hi.max(swap(p)[1])));
+
+    let mut lines = Vec::new();
+    let mut y = lo + spacing / 2.0;
+    while y < hi {
+        let mut crossings: Vec<f64> = Vec::new();
+        for polygon in polygons {
+            for i in 0..polygon.len() {
+                let (a, b) = (swap(polygon[i]), swap(polygon[(i + 1) % polygon.len()]));
+                if (a[1] > y) != (b[1] > y) {
+                    crossings.push(a[0] + (y - a[1]) / (b[1] - a[1]) * (b[0] - a[0]));
+                }
+            }
+        }
+        crossings.sort_by(f64::total_cmp);
+        for pair in crossings.chunks_exact(2) {
+            let (start, end) = if lines.len() % 2 == 0 { (pair[0], pair[1]) } else { (pair[1], pair[0]) };
+            lines.push([swap([start, y]), swap([end, y])]);
+        }
+        y += spacing;
+    }
+    lines
+}
+
+fn shoelace(points: &[[f64; 2]]) -> f64 {
+    let mut twice = 0.0;
+    for i in 0..points.len() {
+        let (p, q) = (points[i], points[(i + 1) % points.len()]);
+        twice += p[0] * q[1] - q[0] * p[1];
+    }
+    twice / 2.0
+}
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::slicer::Layer;
+
+    fn square_column(size: f64, count: usize) -> SlicedModel {
+        let points = vec![[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]];
+        let layer = |i: usize| Layer {
+            z: (i as f64 + 0.5) * 0.2,
+            contours: vec![Contour { points: points.clone(), closed: true, area_mm2: size * size }],
+            area_mm2: size * size,
+        };
+        SlicedModel { layer_height_mm: 0.2, layers: (0..count).map(layer).collect() }
+    }
+
+    fn job(flavor: GcodeFlavor, infill_percentage: f64) -> GcodeJob {
+        GcodeJob { flavor, nozzle_temperature_c: 210, bed_temperature_c: 60, infill_percentage, bed_center: [125.0, 105.0] }
+    }
+
+    /// Total filament fed by extrusion moves (retractions cancel out).
+    fn extruded(gcode: &str) -> f64 {
+        gcode
+            .lines()
+            .filter(|line| line.starts_with("G1 X"))
+            .filter_map(|line| line.split_whitespace().find_map(|word| word.strip_prefix('E')))
+            .map(|e| e.parse::<f64>().unwrap())
+            .sum()
+    }
+
+    #[test]
+    fn test_flavor_start_and_end() {
+        let sliced = square_column(20.0, 10);
+        let marlin = generate_gcode(&sliced, &MachineProfile::default(), &job(GcodeFlavor::Marlin, 20.0));
+        assert!(marlin.starts_with(";FLAVOR:Marlin\n"));
+        assert!(marlin.contains("M109 S210") && marlin.contains("M190 S60"));
+        assert!(marlin.trim_end().ends_with("M84"));
+
+        let klipper = generate_gcode(&sliced, &MachineProfile::default(), &job(GcodeFlavor::Klipper, 20.0));
+        assert!(klipper.contains("PRINT_START BED=60 EXTRUDER=210"));
+        assert!(klipper.trim_end().ends_with("PRINT_END"));
+        assert_eq!(klipper.matches(";LAYER:").count(), 10);
+    }
+
+    #[test]
+    fn test_perimeters_are_inset_and_centered() {
+        // No skins or infill: two loops per layer, 0.225 and 0.675 mm inside the edge
+        let profile = MachineProfile { solid_layers: 0, ..Default::default() };
+        let gcode = generate_gcode(&square_column(20.0, 5), &profile, &job(GcodeFlavor::Marlin, 0.0));
+
+        let loops = 4.0 * (20.0 - 0.45) + 4.0 * (20.0 - 1.35);
+        let per_mm = 0.45 * 0.2 / (std::f64::consts::PI * 0.875 * 0.875);
+        assert!((extruded(&gcode) - 5.0 * loops * per_mm).abs() < 1e-3);
+
+        let xs: Vec<f64> = gcode
+            .lines()
+            .filter(|line| line.starts_with("G1 X"))
+            .map(|line| line[4..].split_whitespace().next().unwrap().parse().unwrap())
+            .collect();
+        let (min, max) = xs.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+        assert!((min - (125.0 - 9.775)).abs() < 1e-3 && (max - (125.0 + 9.775)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_more_infill_extrudes_more() {
+        let sliced = square_column(20.0, 20);
+        let sparse = generate_gcode(&sliced, &MachineProfile::default(), &job(GcodeFlavor::Marlin, 20.0));
+        let solid = generate_gcode(&sliced, &MachineProfile::default(), &job(GcodeFlavor::Marlin, 100.0));
+        assert!(extruded(&solid) > extruded(&sparse));
+        // Solid infill should come close to the part volume (8 cm3 over 2.405 mm2 filament)
+        let part = 20.0 * 20.0 * 4.0 / (std::f64::consts::PI * 0.875 * 0.875);
+        assert!((extruded(&solid) - part).abs() / part < 0.1, "{} vs {}", extruded(&solid), part);
+    }
+
+    #[test]
+    fn test_inset_drops_thin_features() {
+        let strip = [[0.0, 0.0], [10.0, 0.0], [10.0, 0.5], [0.0, 0.5]];
+        assert!(inset(&strip, 0.2).is_some());
+        assert!(inset(&strip, 0.3).is_none());
+        // Holes (clockwise) grow into the surrounding material
+        let hole: Vec<[f64; 2]> = strip.iter().rev().copied().collect();
+        assert!(shoelace(&inset(&hole, 0.2).unwrap()) < shoelace(&hole));
+    }
+}
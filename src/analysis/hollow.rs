@@ -0,0 +1,326 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use super::voxel::{classify, Grid, MAX_RESOLUTION};
+use super::Mesh;
+
+/// Wall left around the cavity when the request doesn't say.
+pub const DEFAULT_WALL_THICKNESS_MM: f64 = 2.0;
+/// Thinner shells crack when the cavity is drained and cured.
+pub const MIN_WALL_THICKNESS_MM: f64 = 1.0;
+/// Thickness of the lattice struts joining opposite walls.
+pub const LATTICE_STRUT_MM: f64 = 1.5;
+/// Grids are at least this fine along the longest side, however thick the wall.
+const MIN_RESOLUTION: usize = 16;
+
+fn default_wall_thickness() -> f64 {
+    DEFAULT_WALL_THICKNESS_MM
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HollowSettings {
+    #[serde(default = "default_wall_thickness")]
+    pub wall_thickness_mm: f64,
+    /// Spacing of a cubic lattice of struts left standing in the cavity; none when omitted.
+    pub lattice_spacing_mm: Option<f64>,
+}
+
+impl Default for HollowSettings {
+    fn default() -> Self {
+        Self { wall_thickness_mm: DEFAULT_WALL_THICKNESS_MM, lattice_spacing_mm: None }
+    }
+}
+
+impl HollowSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        let wall = self.wall_thickness_mm;
+        if !wall.is_finite() || wall < MIN_WALL_THICKNESS_MM {
+            return Err(format!("wall_thickness_mm must be at least {} mm", MIN_WALL_THICKNESS_MM));
+        }
+        if let Some(spacing) = self.lattice_spacing_mm
+            && !(spacing.is_finite() && spacing >= 2.0 * LATTICE_STRUT_MM)
+        {
+            return Err(format!("lattice_spacing_mm must be at least {} mm", 2.0 * LATTICE_STRUT_MM));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HollowReport {
+    pub wall_thickness_mm: f64,
+    pub lattice_spacing_mm: Option<f64>,
+    /// Edge length of the voxels the cavity is built from.
+    pub cell_mm: f64,
+    /// Material removed from the part.
+    pub cavity_volume_cm3: f64,
+}
+
+/// Hollows a closed part, keeping walls at least `wall_thickness_mm` thick.
+///
+/// The inside of the mesh is voxelized and every cell further than the wall
+/// thickness from the outside becomes cavity, except cells on lattice struts.
+/// The cavity's boundary is added to the mesh as an inward-facing shell, so the
+/// result has a sealed void (see `drainage` for where to drill it out).
+/// Cavity walls follow the voxel grid and are stepped, not smooth.
+pub fn hollow_mesh(mesh: &Mesh, settings: &HollowSettings) -> Result<(Mesh, HollowReport), String> {
+    settings.validate()?;
+    let wall = settings.wall_thickness_mm;
+
+    let size = mesh.bounding_box().size();
+    let longest = size[0].max(size[1]).max(size[2]);
+    let resolution = ((longest / (wall / 2.0)).ceil() as usize).clamp(MIN_RESOLUTION, MAX_RESOLUTION);
+    let voxels = classify(mesh, resolution).ok_or("Model has no volume to hollow")?;
+    let grid = &voxels.grid;
+
+    // Cell centres at least a wall (plus half a cell for where the surface
+    // crosses the boundary cells) from the nearest outside cell
+    let depth = squared_distance_to_outside(grid, &voxels.inside);
+    let min_depth = wall / grid.cell + 0.5;
+    let mut cavity: Vec<bool> = depth.iter().map(|&d| d >= min_depth * min_depth).collect();
+    if let Some(spacing) = settings.lattice_spacing_mm {
+        carve_lattice(grid, &mut cavity, spacing);
+    }
+    remove_diagonal_contacts(grid, &mut cavity);
+
+    let cells = cavity.iter().filter(|&&c| c).count();
+    if cells == 0 {
+        return Err(format!("Model is too thin to hollow with {} mm walls", wall));
+    }
+
+    let mut hollowed = mesh.clone();
+    add_cavity_surface(grid, &cavity, &mut hollowed);
+    Ok((hollowed, HollowReport {
+        wall_thickness_mm: wall,
+        lattice_spacing_mm: settings.lattice_spacing_mm,
+        cell_mm: grid.cell,
+        cavity_volume_cm3: cells as f64 * grid.cell.powi(3) / 1000.0,
+    }))
+}
+
+/// Squared distance (in cells) from each cell centre to the nearest outside
+/// cell centre; everything beyond the grid counts as outside.
+fn squared_distance_to_outside(grid: &Grid, inside: &[bool]) -> Vec<f64> {
+    let mut distance: Vec<f64> = inside.iter().map(|&i| if i { f64::MAX } else { 0.0 }).collect();
+    for axis in 0..3 {
+        let n = grid.dims[axis];
+        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut line = vec![0.0; n];
+        for j in 0..grid.dims[v] {
+            for i in 0..grid.dims[u] {
+                let index = |k: usize| {
+                    let mut cell = [0; 3];
+                    cell[axis] = k;
+                    cell[u] = i;
+                    cell[v] = j;
+                    grid.index(cell)
+                };
+                for (k, value) in line.iter_mut().enumerate() {
+                    *value = distance[index(k)];
+                }
+                for (k, value) in lower_envelope(&line).into_iter().enumerate() {
+                    distance[index(k)] = value;
+                }
+            }
+        }
+    }
+    distance
+}
+
+/// One pass of the Felzenszwalb–Huttenlocher distance transform:
+/// `min over q of (p - q)² + f(q)`, with zero-cost sites just past both ends.
+fn lower_envelope(f: &[f64]) -> Vec<f64> {
+    let n = f.len();
+    // Sites shifted by one, with the outside cells at 0 and n + 1
+    let site = |q: usize| if q == 0 || q == n + 1 { 0.0 } else { f[q - 1] };
+    let mut hull = vec![0usize];
+    let mut bounds = vec![f64::NEG_INFINITY, f64::INFINITY];
+    for q in 1..n + 2 {
+        if site(q) == f64::MAX {
+            continue;
+        }
+        loop {
+            let r = *hull.last().unwrap();
+            let s = ((site(q) + (q * q) as f64) - (site(r) + (r * r) as f64)) / (2.0 * (q - r) as f64);
+            if s <= bounds[hull.len() - 1] {
+                hull.pop();
+                bounds.pop();
+                continue;
+            }
+            hull.push(q);
+            *bounds.last_mut().unwrap() = s;
+            bounds.push(f64::INFINITY);
+            break;
+        }
+    }
+
+    let mut k = 0;
+    (1..=n)
+        .map(|p| {
+            while bounds[k + 1] < p as f64 {
+                k += 1;
+            }
+            let q = hull[k];
+            let offset = p as f64 - q as f64;
+            offset * offset + site(q)
+        })
+        .collect()
+}
+
+/// Keeps cells within half a strut of two lattice planes solid, leaving rods
+/// along X, Y and Z every `spacing` mm.
+fn carve_lattice(grid: &Grid, cavity: &mut [bool], spacing: f64) {
+    let half_strut = (LATTICE_STRUT_MM / 2.0).max(grid.cell / 2.0);
+    let near_plane = |axis: usize, i: usize| {
+        let offset = (grid.center(axis, i) - grid.origin[axis]) / spacing;
+        (offset - offset.round()).abs() * spacing <= half_strut
+    };
+    let [nx, ny, nz] = grid.dims;
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                let planes = [near_plane(0, x), near_plane(1, y), near_plane(2, z)].iter().filter(|&&n| n).count();
+                if planes >= 2 {
+                    cavity[grid.index([x, y, z])] = false;
+                }
+            }
+        }
+    }
+}
+
+/// Fills cavity cells that touch another only along an edge, which would make
+/// that edge non-manifold (four faces). Filling only thickens the walls.
+fn remove_diagonal_contacts(grid: &Grid, cavity: &mut [bool]) {
+    let [nx, ny, nz] = grid.dims;
+    loop {
+        let mut changed = false;
+        for z in 0..nz {
+            for y in 0..ny {
+                for x in 0..nx {
+                    // The 2 x 2 blocks around the cell's three positive-direction edges
+                    for (a, b) in [(0, 1), (1, 2), (0, 2)] {
+                        let corner = [x, y, z];
+                        let mut pa = corner;
+                        pa[a] += 1;
+                        let mut pb = corner;
+                        pb[b] += 1;
+                        let mut ab = pa;
+                        ab[b] += 1;
+                        if ab[a] >= grid.dims[a] || ab[b] >= grid.dims[b] {
+                            continue;
+                        }
+                        let at = |cell: [usize; 3]| cavity[grid.index(cell)];
+                        let (c00, c11, c10, c01) = (at(corner), at(ab), at(pa), at(pb));
+                        if c00 == c11 && c10 == c01 && c00 != c10 {
+                            let fill = if c00 { corner } else { pa };
+                            cavity[grid.index(fill)] = false;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        if !changed {
+            return;
+        }
+    }
+}
+
+/// Adds a quad for every cavity cell face next to solid, facing into the cavity.
+fn add_cavity_surface(grid: &Grid, cavity: &[bool], mesh: &mut Mesh) {
+    let [nx, ny, nz] = grid.dims;
+    let mut vertex_of: HashMap<[usize; 3], usize> = HashMap::new();
+    let mut vertex = |mesh: &mut Mesh, corner: [usize; 3]| {
+        *vertex_of.entry(corner).or_insert_with(|| {
+            mesh.vertices.push([0, 1, 2].map(|axis| grid.origin[axis] + corner[axis] as f64 * grid.cell));
+            mesh.vertices.len() - 1
+        })
+    };
+
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                let cell = [x, y, z];
+                if !cavity[grid.index(cell)] {
+                    continue;
+                }
+                for axis in 0..3 {
+                    for positive in [false, true] {
+                        let mut neighbour = cell;
+                        let open = if positive {
+                            neighbour[axis] += 1;
+                            neighbour[axis] < grid.dims[axis] && cavity[grid.index(neighbour)]
+                        } else {
+                            neighbour[axis] > 0 && {
+                                neighbour[axis] -= 1;
+                                cavity[grid.index(neighbour)]
+                            }
+                        };
+                        if open {
+                            continue;
+                        }
+                        // Corners of the face, counter-clockwise seen from +axis
+                        let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+                        let mut base = cell;
+                        base[axis] += positive as usize;
+                        let mut corners = [base; 4];
+                        corners[1][u] += 1;
+                        corners[2][u] += 1;
+                        corners[2][v] += 1;
+                        corners[3][v] += 1;
+                        let [a, b, c, d] = corners.map(|corner| vertex(mesh, corner));
+                        // The normal must point into the cavity
+                        if positive {
+                            mesh.faces.extend([[a, c, b], [a, d, c]]);
+                        } else {
+                            mesh.faces.extend([[a, b, c], [a, c, d]]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::shells::{analyze_shells, total_volume_cm3};
+    use crate::analysis::test_util::cube;
+    use crate::analysis::validation::validate_mesh;
+
+    #[test]
+    fn test_hollow_cube_keeps_walls() {
+        let (hollowed, report) = hollow_mesh(&cube(20.0), &HollowSettings::default()).unwrap();
+        // 1 mm cells: 2 mm walls leave a 16 mm cavity
+        assert_eq!(report.cell_mm, 1.0);
+        assert!((report.cavity_volume_cm3 - 4.096).abs() < 1e-9);
+
+        let validation = validate_mesh(&hollowed);
+        assert!(validation.is_printable(), "{:?}", validation);
+        let shells = analyze_shells(&hollowed);
+        assert_eq!(shells.len(), 2);
+        assert!(shells[1].is_void);
+        assert!((total_volume_cm3(&shells) - (8.0 - 4.096)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_lattice_and_thin_parts() {
+        let plain = hollow_mesh(&cube(20.0), &HollowSettings::default()).unwrap().1;
+        let settings = HollowSettings { lattice_spacing_mm: Some(5.0), ..Default::default() };
+        let (latticed, report) = hollow_mesh(&cube(20.0), &settings).unwrap();
+        assert!(report.cavity_volume_cm3 < plain.cavity_volume_cm3);
+        assert!(validate_mesh(&latticed).is_printable());
+
+        assert!(hollow_mesh(&cube(4.0), &HollowSettings::default()).unwrap_err().contains("too thin"));
+        let thin_walls = HollowSettings { wall_thickness_mm: 0.2, ..Default::default() };
+        assert!(hollow_mesh(&cube(20.0), &thin_walls).is_err());
+    }
+
+    #[test]
+    fn test_distance_transform() {
+        // Outside beyond both ends, and one outside cell in the middle
+        let line = [f64::MAX, f64::MAX, f64::MAX, 0.0, f64::MAX];
+        assert_eq!(lower_envelope(&line), vec![1.0, 4.0, 1.0, 0.0, 1.0]);
+        assert_eq!(lower_envelope(&[f64::MAX; 3]), vec![1.0, 4.0, 1.0]);
+    }
+}
@@ -0,0 +1,123 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::bvh::{ray_triangle, Bvh};
+use super::validation::{is_degenerate_triangle, weld_exact};
+use super::vec3::{add, scale, sub, Vec3};
+use super::Mesh;
+
+/// Intersecting pairs listed individually; the rest are only counted.
+pub const MAX_REPORTED_INTERSECTIONS: usize = 50;
+/// Crossings this close (as a fraction of the edge) to an edge's end are treated as touching.
+const EDGE_EPSILON: f64 = 1e-9;
+
+/// Two faces cutting through each other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intersection {
+    pub faces: [usize; 2],
+    /// A point on the line where they cross.
+    pub location: [f64; 3],
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SelfIntersections {
+    pub pair_count: usize,
+    /// First `MAX_REPORTED_INTERSECTIONS` pairs.
+    pub intersections: Vec<Intersection>,
+}
+
+/// Finds pairs of non-adjacent faces that cross each other. Faces sharing a
+/// vertex and coplanar overlaps are not reported.
+pub fn find_self_intersections(mesh: &Mesh) -> SelfIntersections {
+    let welded = weld_exact(mesh);
+    let bvh = Bvh::new(mesh);
+
+    // Each face checked in parallel against higher-numbered faces; results are
+    // gathered in face order so the reported pairs are the same on every run
+    let per_face: Vec<(usize, Vec<Intersection>)> = (0..welded.len())
+        .into_par_iter()
+        .map(|face| face_intersections(mesh, &welded, &bvh, face))
+        .collect();
+
+    let mut report = SelfIntersections::default();
+    for (count, intersections) in per_face {
+        report.pair_count += count;
+        let room = MAX_REPORTED_INTERSECTIONS - report.intersections.len();
+        report.intersections.extend(intersections.into_iter().take(room));
+    }
+    report
+}
+
+/// Number of faces after `face` that cross it, and the first few of them.
+fn face_intersections(mesh: &Mesh, welded: &[[usize; 3]], bvh: &Bvh, face: usize) -> (usize, Vec<Intersection>) {
+    let f = welded[face];
+    let [a, b, c] = mesh.triangle(face);
+    let mut count = 0;
+    let mut intersections = Vec::new();
+    if is_degenerate_triangle(a, b, c) {
+        return (count, intersections);
+    }
+    let min = [0, 1, 2].map(|axis| a[axis].min(b[axis]).min(c[axis]));
+    let max = [0, 1, 2].map(|axis| a[axis].max(b[axis]).max(c[axis]));
+    let mut candidates = bvh.faces_in_box(min, max);
+    candidates.sort_unstable();
+
+    for other in candidates.into_iter().filter(|&other| other > face) {
+        let g = welded[other];
+        if f.iter().any(|v| g.contains(v)) {
+            continue;
+        }
+        let [d, e, h] = mesh.triangle(other);
+        if is_degenerate_triangle(d, e, h) {
+            continue;
+        }
+        if let Some(location) = crossing(&[a, b, c], &[d, e, h]).or_else(|| crossing(&[d, e, h], &[a, b, c])) {
+            count += 1;
+            if intersections.len() < MAX_REPORTED_INTERSECTIONS {
+                intersections.push(Intersection { faces: [face, other], location });
+            }
+        }
+    }
+    (count, intersections)
+}
+
+/// Where an edge of `edges_of` passes through `triangle`, if any does.
+fn crossing(edges_of: &[Vec3; 3], triangle: &[Vec3; 3]) -> Option<Vec3> {
+    (0..3).find_map(|i| {
+        let (p, q) = (edges_of[i], edges_of[(i + 1) % 3]);
+        let direction = sub(q, p);
+        ray_triangle(p, direction, triangle[0], triangle[1], triangle[2])
+            .filter(|&t| t > EDGE_EPSILON && t < 1.0 - EDGE_EPSILON)
+            .map(|t| add(p, scale(direction, t)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::add_box;
+
+    #[test]
+    fn test_closed_box_does_not_intersect_itself() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        add_box(&mut mesh, [20.0, 0.0, 0.0], [30.0, 10.0, 10.0], false);
+        assert_eq!(find_self_intersections(&mesh), SelfIntersections::default());
+    }
+
+    #[test]
+    fn test_overlapping_boxes_intersect() {
+        // Unioned in CAD without a boolean: the second box pokes through the first
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        add_box(&mut mesh, [5.0, 2.0, 2.0], [15.0, 8.0, 8.0], false);
+        let report = find_self_intersections(&mesh);
+
+        assert!(report.pair_count > 0);
+        assert_eq!(report.intersections.len(), report.pair_count.min(MAX_REPORTED_INTERSECTIONS));
+        for intersection in &report.intersections {
+            // Every crossing lies on the first box's +X face
+            assert!(intersection.faces[0] < 12 && intersection.faces[1] >= 12);
+            assert!((intersection.location[0] - 10.0).abs() < 1e-9 || (intersection.location[0] - 5.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::shells::Shell;
+use super::vec3::{self, dot};
+use super::{Mesh, FACE_CHUNK};
+
+/// Density the inertia tensor is reported for (g/mm3, i.e. 1 g/cm3).
+const UNIT_DENSITY_G_PER_MM3: f64 = 0.001;
+/// Vertices this close (mm) to the lowest point rest on the bed.
+pub const BED_CONTACT_TOLERANCE_MM: f64 = 0.05;
+
+/// Mass distribution of the solid, assuming uniform density.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MassProperties {
+    /// Centre of mass (mm).
+    pub center_of_mass: [f64; 3],
+    /// Inertia tensor about the centre of mass (g·mm2) for a density of
+    /// 1 g/cm3; multiply by the material's density.
+    pub inertia_tensor: [[f64; 3]; 3],
+    pub stability: Stability,
+}
+
+/// Whether the part stands up as uploaded, resting on its lowest point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stability {
+    /// Convex hull (XY, mm) of the vertices touching the bed, counter-clockwise.
+    pub footprint: Vec<[f64; 2]>,
+    /// Distance from the centre of mass, projected onto the bed, to the
+    /// footprint's edge; negative when it falls outside.
+    pub com_margin_mm: f64,
+    /// Tilt (degrees) the part survives before its centre of mass passes the
+    /// footprint's edge; zero when it tips over by itself.
+    pub tip_angle_deg: f64,
+    pub is_stable: bool,
+}
+
+/// Volume, first and second moments (`∫x dV`, `∫x xᵀ dV`) of a set of tetrahedra.
+#[derive(Clone, Copy, Default)]
+struct Moments {
+    volume: f64,
+    first: [f64; 3],
+    second: [[f64; 3]; 3],
+}
+
+impl Moments {
+    fn add(mut self, other: Moments, sign: f64) -> Self {
+        self.volume += sign * other.volume;
+        for i in 0..3 {
+            self.first[i] += sign * other.first[i];
+            for j in 0..3 {
+                self.second[i][j] += sign * other.second[i][j];
+            }
+        }
+        self
+    }
+}
+
+/// Centre of mass, inertia and stability from the tetrahedra each face forms
+/// with the origin, like the volume. Each shell counts as solid or void the
+/// way `total_volume_cm3` does, whichever way it is wound. `None` when the
+/// mesh encloses no volume.
+pub fn mass_properties(mesh: &Mesh, shells: &[Shell]) -> Option<MassProperties> {
+    let total = shells.iter().fold(Moments::default(), |total, shell| {
+        let moments = shell_moments(mesh, &shell.faces);
+        let outward = if moments.volume < 0.0 { -1.0 } else { 1.0 };
+        total.add(moments, if shell.is_void { -outward } else { outward })
+    });
+    if total.volume <= 0.0 {
+        return None;
+    }
+
+    let com = total.first.map(|m| m / total.volume);
+    // Parallel axis theorem, then I = tr(C)·1 - C for the covariance C
+    let mut covariance = total.second;
+    for i in 0..3 {
+        for j in 0..3 {
+            covariance[i][j] -= total.volume * com[i] * com[j];
+        }
+    }
+    let trace = covariance[0][0] + covariance[1][1] + covariance[2][2];
+    let mut inertia = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            let identity = if i == j { trace } else { 0.0 };
+            inertia[i][j] = (identity - covariance[i][j]) * UNIT_DENSITY_G_PER_MM3;
+        }
+    }
+
+    Some(MassProperties { center_of_mass: com, inertia_tensor: inertia, stability: stability(mesh, com) })
+}
+
+fn shell_moments(mesh: &Mesh, faces: &[usize]) -> Moments {
+    let chunks: Vec<Moments> = faces
+        .par_chunks(FACE_CHUNK)
+        .map(|chunk| {
+            chunk.iter().fold(Moments::default(), |total, &face| total.add(tetrahedron_moments(mesh.triangle(face)), 1.0))
+        })
+        .collect();
+    chunks.into_iter().fold(Moments::default(), |total, m| total.add(m, 1.0))
+}
+
+/// Moments of the tetrahedron `(0, a, b, c)`: with `A = [a b c]`, the second
+/// moment is `det(A) · A · C · Aᵀ` for the canonical tetrahedron's `C`.
+fn tetrahedron_moments([a, b, c]: [[f64; 3]; 3]) -> Moments {
+    let det = dot(a, vec3::cross(b, c));
+    let mut second = [[0.0; 3]; 3];
+    for i in 0..3 {
+        for j in 0..3 {
+            // A · C · Aᵀ with C = (1 + δ) / 120
+            let sum = (a[i] + b[i] + c[i]) * (a[j] + b[j] + c[j]);
+            let diagonal = a[i] * a[j] + b[i] * b[j] + c[i] * c[j];
+            second[i][j] = det * (sum + diagonal) / 120.0;
+        }
+    }
+    Moments {
+        volume: det / 6.0,
+        first: [0, 1, 2].map(|i| det / 24.0 * (a[i] + b[i] + c[i])),
+        second,
+    }
+}
+
+fn stability(mesh: &Mesh, com: [f64; 3]) -> Stability {
+    let bed = mesh.bounding_box().min[2];
+    let contacts: Vec<[f64; 2]> = mesh
+        .faces
+        .iter()
+        .flatten()
+        .map(|&v| mesh.vertices[v])
+        .filter(|v| v[2] <= bed + BED_CONTACT_TOLERANCE_MM)
+        .map(|v| [v[0], v[1]])
+        .collect();
+    let footprint = convex_hull(contacts);
+
+    let point = [com[0], com[1]];
+    let edge_distance = (0..footprint.len())
+        .map(|i| segment_distance(point, footprint[i], footprint[(i + 1) % footprint.len()]))
+        .fold(f64::INFINITY, f64::min);
+    let com_margin_mm = if contains(&footprint, point) { edge_distance } else { -edge_distance };
+    let is_stable = com_margin_mm > 0.0;
+    let tip_angle_deg = if is_stable { com_margin_mm.atan2(com[2] - bed).to_degrees() } else { 0.0 };
+    Stability { footprint, com_margin_mm, tip_angle_deg, is_stable }
+}
+
+/// Andrew's monotone chain; counter-clockwise without collinear points.
+fn convex_hull(mut points: Vec<[f64; 2]>) -> Vec<[f64; 2]> {
+    points.sort_by(|p, q| p[0].total_cmp(&q[0]).then(p[1].total_cmp(&q[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<[f64; 2]> = Vec::new();
+    // Lower chain left to right, then upper chain right to left
+    for chain in [points.clone(), points.iter().rev().copied().collect()] {
+        let start = hull.len();
+        for p in chain {
+            while hull.len() >= start + 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        // The last point starts the other chain
+        hull.pop();
+    }
+    hull
+}
+
+/// Twice the signed area of `abc`; positive when counter-clockwise.
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+/// Strictly inside a counter-clockwise convex polygon (always false below three points).
+fn contains(polygon: &[[f64; 2]], p: [f64; 2]) -> bool {
+    polygon.len() >= 3 && (0..polygon.len()).all(|i| cross(polygon[i], polygon[(i + 1) % polygon.len()], p) > 0.0)
+}
+
+fn segment_distance(p: [f64; 2], a: [f64; 2], b: [f64; 2]) -> f64 {
+    let ab = [b[0] - a[0], b[1] - a[1]];
+    let length2 = ab[0] * ab[0] + ab[1] * ab[1];
+    let t = if length2 > 0.0 { (((p[0] - a[0]) * ab[0] + (p[1] - a[1]) * ab[1]) / length2).clamp(0.0, 1.0) } else { 0.0 };
+    let closest = [a[0] + t * ab[0], a[1] + t * ab[1]];
+    ((p[0] - closest[0]).powi(2) + (p[1] - closest[1]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::shells::analyze_shells;
+    use crate::analysis::test_util::add_box;
+
+    fn properties(mesh: &Mesh) -> MassProperties {
+        mass_properties(mesh, &analyze_shells(mesh)).unwrap()
+    }
+
+    #[test]
+    fn test_cube_mass_properties() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        let cube = properties(&mesh);
+        assert!(cube.center_of_mass.iter().all(|c| (c - 5.0).abs() < 1e-9));
+        // 1 g cube: I = m (a² + b²) / 12
+        for i in 0..3 {
+            for j in 0..3 {
+                let expected = if i == j { 200.0 / 12.0 } else { 0.0 };
+                assert!((cube.inertia_tensor[i][j] - expected).abs() < 1e-9, "{:?}", cube.inertia_tensor);
+            }
+        }
+        assert_eq!(cube.stability.footprint, vec![[0.0, 0.0], [10.0, 0.0], [10.0, 10.0], [0.0, 10.0]]);
+        assert!((cube.stability.com_margin_mm - 5.0).abs() < 1e-9);
+        assert!((cube.stability.tip_angle_deg - 45.0).abs() < 1e-9);
+        assert!(cube.stability.is_stable);
+
+        // Winding doesn't matter
+        for face in &mut mesh.faces {
+            face.swap(1, 2);
+        }
+        assert_eq!(properties(&mesh).center_of_mass, cube.center_of_mass);
+    }
+
+    #[test]
+    fn test_cantilever_tips_over() {
+        // A 10 mm pedestal with a long block resting on one side of it
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0, 10.0, 2.0], false);
+        add_box(&mut mesh, [0.0, 0.0, 2.0], [40.0, 10.0, 12.0], false);
+        let cantilever = properties(&mesh);
+        assert!((cantilever.center_of_mass[0] - (200.0 * 5.0 + 4000.0 * 20.0) / 4200.0).abs() < 1e-9);
+        assert!(!cantilever.stability.is_stable);
+        assert!((cantilever.stability.com_margin_mm + (cantilever.center_of_mass[0] - 10.0)).abs() < 1e-9);
+        assert_eq!(cantilever.stability.tip_angle_deg, 0.0);
+    }
+
+    #[test]
+    fn test_void_shifts_center_of_mass() {
+        // Cavity in the upper half moves the centre of mass down
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [20.0; 3], false);
+        add_box(&mut mesh, [5.0, 5.0, 10.0], [15.0, 15.0, 18.0], true);
+        let hollow = properties(&mesh);
+        let expected_z = (8000.0 * 10.0 - 800.0 * 14.0) / 7200.0;
+        assert!((hollow.center_of_mass[2] - expected_z).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,172 @@
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::bvh::{Bvh, RAY_EPSILON_MM};
+use super::vec3::{add, dot, face_normal, length, normalize, scale, sub, Vec3};
+use super::{Mesh, FACE_CHUNK};
+
+/// What to measure. Picked points are snapped to the closest point on the
+/// full-resolution mesh, since the viewer only shows the decimated preview.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MeasureRequest {
+    /// Straight distance between two surface points.
+    PointToPoint { from: Vec3, to: Vec3 },
+    /// Wall thickness behind a surface point, along the inward normal.
+    Thickness { point: Vec3 },
+    /// Overall size of the model along a direction.
+    Extent { direction: Vec3 },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub from: Vec3,
+    pub to: Vec3,
+    /// Distance from `from` to `to`; for `EXTENT`, the span along the
+    /// direction between the extreme vertices `from` and `to`.
+    pub distance_mm: f64,
+    /// `to - from` along X, Y and Z.
+    pub delta_mm: Vec3,
+}
+
+pub fn measure(mesh: &Mesh, request: &MeasureRequest) -> Result<Measurement, String> {
+    if mesh.faces.is_empty() {
+        return Err("Model has no faces".to_string());
+    }
+    match *request {
+        MeasureRequest::PointToPoint { from, to } => {
+            let (from, to) = (closest_point(mesh, check_finite(from)?).1, closest_point(mesh, check_finite(to)?).1);
+            Ok(between(from, to, length(sub(to, from))))
+        }
+        MeasureRequest::Thickness { point } => {
+            let (face, from) = closest_point(mesh, check_finite(point)?);
+            let [a, b, c] = mesh.triangle(face);
+            let normal = face_normal(a, b, c);
+            if length(normal) == 0.0 {
+                return Err("Point lies on a degenerate face".to_string());
+            }
+            let inward = scale(normalize(normal), -1.0);
+            let hit = Bvh::new(mesh)
+                .ray_hit(mesh, from, inward, RAY_EPSILON_MM, Some(face))
+                .ok_or("No wall behind this point")?;
+            Ok(between(from, add(from, scale(inward, hit.distance)), hit.distance))
+        }
+        MeasureRequest::Extent { direction } => {
+            let direction = check_finite(direction)?;
+            if length(direction) == 0.0 {
+                return Err("Direction must be a non-zero vector".to_string());
+            }
+            let direction = normalize(direction);
+            let along = |&v: &Vec3| dot(v, direction);
+            let lowest = mesh.vertices.iter().copied().min_by(|a, b| along(a).total_cmp(&along(b))).expect("mesh has faces");
+            let highest = mesh.vertices.iter().copied().max_by(|a, b| along(a).total_cmp(&along(b))).expect("mesh has faces");
+            Ok(between(lowest, highest, along(&highest) - along(&lowest)))
+        }
+    }
+}
+
+fn check_finite(v: Vec3) -> Result<Vec3, String> {
+    if v.iter().all(|c| c.is_finite()) { Ok(v) } else { Err("Coordinates must be finite".to_string()) }
+}
+
+fn between(from: Vec3, to: Vec3, distance_mm: f64) -> Measurement {
+    Measurement { from, to, distance_mm, delta_mm: sub(to, from) }
+}
+
+/// Face and point of the surface closest to `p`.
+fn closest_point(mesh: &Mesh, p: Vec3) -> (usize, Vec3) {
+    (0..mesh.faces.len())
+        .into_par_iter()
+        .with_min_len(FACE_CHUNK)
+        .map(|face| {
+            let [a, b, c] = mesh.triangle(face);
+            let q = closest_on_triangle(p, a, b, c);
+            (length(sub(q, p)), face, q)
+        })
+        .min_by(|x, y| x.0.total_cmp(&y.0).then(x.1.cmp(&y.1)))
+        .map(|(_, face, q)| (face, q))
+        .expect("mesh has faces")
+}
+
+/// Closest point to `p` on triangle `abc`, by the Voronoi region `p` falls in
+/// (Ericson, Real-Time Collision Detection, 5.1.5).
+fn closest_on_triangle(p: Vec3, a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    let (ab, ac, ap) = (sub(b, a), sub(c, a), sub(p, a));
+    let (d1, d2) = (dot(ab, ap), dot(ac, ap));
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = sub(p, b);
+    let (d3, d4) = (dot(ab, bp), dot(ac, bp));
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return add(a, scale(ab, d1 / (d1 - d3)));
+    }
+    let cp = sub(p, c);
+    let (d5, d6) = (dot(ab, cp), dot(ac, cp));
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return add(a, scale(ac, d2 / (d2 - d6)));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && d4 - d3 >= 0.0 && d5 - d6 >= 0.0 {
+        return add(b, scale(sub(c, b), (d4 - d3) / ((d4 - d3) + (d5 - d6))));
+    }
+    let denom = va + vb + vc;
+    if denom == 0.0 {
+        // Degenerate triangle; its vertices are all handled above
+        return a;
+    }
+    add(a, add(scale(ab, vb / denom), scale(ac, vc / denom)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::cuboid;
+
+    /// 20 x 10 x 4 mm plate.
+    fn plate() -> Mesh {
+        cuboid([0.0; 3], [20.0, 10.0, 4.0])
+    }
+
+    #[test]
+    fn test_point_to_point_snaps_to_surface() {
+        // Points picked slightly off the surface, as from the decimated preview
+        let m = measure(&plate(), &MeasureRequest::PointToPoint { from: [-0.5, 5.0, 2.0], to: [20.3, 5.0, 2.0] }).unwrap();
+        assert_eq!(m.from, [0.0, 5.0, 2.0]);
+        assert_eq!(m.to, [20.0, 5.0, 2.0]);
+        assert!((m.distance_mm - 20.0).abs() < 1e-9);
+        assert_eq!(m.delta_mm, [20.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_thickness_behind_point() {
+        let m = measure(&plate(), &MeasureRequest::Thickness { point: [5.0, 5.0, 4.1] }).unwrap();
+        assert!((m.distance_mm - 4.0).abs() < 1e-9);
+        assert!((m.to[2]).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_extent_along_diagonal() {
+        let m = measure(&plate(), &MeasureRequest::Extent { direction: [1.0, 1.0, 0.0] }).unwrap();
+        assert!((m.distance_mm - 30.0 / 2f64.sqrt()).abs() < 1e-9);
+        assert_eq!(m.delta_mm[0], 20.0);
+        assert_eq!(m.delta_mm[1], 10.0);
+        assert!(measure(&plate(), &MeasureRequest::Extent { direction: [0.0; 3] }).is_err());
+    }
+
+    #[test]
+    fn test_closest_on_triangle_regions() {
+        let (a, b, c) = ([0.0, 0.0, 0.0], [4.0, 0.0, 0.0], [0.0, 4.0, 0.0]);
+        assert_eq!(closest_on_triangle([1.0, 1.0, 3.0], a, b, c), [1.0, 1.0, 0.0]);
+        assert_eq!(closest_on_triangle([-1.0, -1.0, 0.0], a, b, c), a);
+        assert_eq!(closest_on_triangle([2.0, -3.0, 0.0], a, b, c), [2.0, 0.0, 0.0]);
+        assert_eq!(closest_on_triangle([3.0, 3.0, 0.0], a, b, c), [2.0, 2.0, 0.0]);
+    }
+}
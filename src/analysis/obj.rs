@@ -0,0 +1,180 @@
+use super::Mesh;
+
+/// A named `g`/`o` section of an OBJ file, as a range of triangles in the mesh.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjGroup {
+    pub name: String,
+    pub first_face: usize,
+    pub face_count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObjModel {
+    pub mesh: Mesh,
+    pub groups: Vec<ObjGroup>,
+}
+
+/// Parses a Wavefront OBJ file.
+///
+/// Only geometry is read: `v` and `f` records plus `g`/`o` group names. Texture
+/// coordinates, normals and materials are ignored. Polygons with more than three
+/// vertices are fan-triangulated, which is exact for the convex quads and n-gons
+/// that CAD exporters produce.
+pub fn parse_obj(data: &[u8]) -> Result<ObjModel, String> {
+    let text = String::from_utf8_lossy(data);
+    let mut mesh = Mesh::default();
+    let mut groups: Vec<ObjGroup> = Vec::new();
+    let mut pending = String::new();
+
+    for (line_no, raw_line) in text.lines().enumerate() {
+        // A trailing backslash continues the record on the next line
+        if let Some(stripped) = raw_line.strip_suffix('\\') {
+            pending.push_str(stripped);
+            pending.push(' ');
+            continue;
+        }
+        pending.push_str(raw_line);
+        let line = std::mem::take(&mut pending);
+
+        let line = line.split('#').next().unwrap_or("").trim();
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+
+        match keyword {
+            "v" => {
+                let mut coords = [0.0; 3];
+                for coord in coords.iter_mut() {
+                    let token = tokens
+                        .next()
+                        .ok_or_else(|| format!("line {}: vertex needs 3 coordinates", line_no + 1))?;
+                    *coord = token
+                        .parse::<f64>()
+                        .map_err(|_| format!("line {}: invalid coordinate '{}'", line_no + 1, token))?;
+                }
+                mesh.vertices.push(coords);
+            }
+            "f" => {
+                let indices = tokens
+                    .map(|token| resolve_index(token, mesh.vertices.len()))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("line {}: {}", line_no + 1, e))?;
+                if indices.len() < 3 {
+                    return Err(format!("line {}: face needs at least 3 vertices", line_no + 1));
+                }
+                for i in 1..indices.len() - 1 {
+                    mesh.faces.push([indices[0], indices[i], indices[i + 1]]);
+                }
+            }
+            "g" | "o" => {
+                let name = line[keyword.len()..].trim();
+                let name = if name.is_empty() { "default" } else { name };
+                groups.retain(|g| g.face_count > 0);
+                groups.push(ObjGroup {
+                    name: name.to_string(),
+                    first_face: mesh.faces.len(),
+                    face_count: 0,
+                });
+            }
+            _ => {}
+        }
+
+        if let Some(group) = groups.last_mut() {
+            group.face_count = mesh.faces.len() - group.first_face;
+        }
+    }
+
+    groups.retain(|g| g.face_count > 0);
+
+    Ok(ObjModel { mesh, groups })
+}
+
+/// Resolves a `v/vt/vn` face token to a zero-based vertex index.
+/// Negative indices count back from the most recently declared vertex.
+fn resolve_index(token: &str, vertex_count: usize) -> Result<usize, String> {
+    let vertex = token.split('/').next().unwrap_or("");
+    let index = vertex
+        .parse::<i64>()
+        .map_err(|_| format!("invalid face index '{}'", token))?;
+
+    let resolved = if index > 0 {
+        index - 1
+    } else if index < 0 {
+        vertex_count as i64 + index
+    } else {
+        return Err("face index 0 is not valid".to_string());
+    };
+
+    if resolved < 0 || resolved >= vertex_count as i64 {
+        return Err(format!("face index {} out of range", index));
+    }
+
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_mesh;
+
+    const CUBE_OBJ: &str = "# 10mm cube, quads
+o cube
+v 0 0 0
+v 10 0 0
+v 10 10 0
+v 0 10 0
+v 0 0 10
+v 10 0 10
+v 10 10 10
+v 0 10 10
+f 1 4 3 2
+f 5 6 7 8
+f 1 2 6 5
+f 2 3 7 6
+f 3 4 8 7
+f 4 1 5 8
+";
+
+    #[test]
+    fn test_obj_cube_quads() {
+        let model = parse_obj(CUBE_OBJ.as_bytes()).unwrap();
+        assert_eq!(model.mesh.vertices.len(), 8);
+        assert_eq!(model.mesh.faces.len(), 12);
+
+        let analysis = analyze_mesh(&model.mesh);
+        assert!((analysis.volume_cm3 - 1.0).abs() < 1e-9);
+        assert!((analysis.surface_area_cm2 - 6.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_obj_negative_indices_and_groups() {
+        let data = "g first
+v 0 0 0
+v 1 0 0
+v 0 1 0
+f -3/1/1 -2/2/1 -1/3/1
+g second
+v 0 0 1
+v 1 0 1
+v 1 1 1
+v 0 1 1
+f -4//1 -3//1 -2//1 -1//1
+";
+        let model = parse_obj(data.as_bytes()).unwrap();
+        assert_eq!(model.mesh.faces, vec![[0, 1, 2], [3, 4, 5], [3, 5, 6]]);
+        assert_eq!(
+            model.groups,
+            vec![
+                ObjGroup { name: "first".to_string(), first_face: 0, face_count: 1 },
+                ObjGroup { name: "second".to_string(), first_face: 1, face_count: 2 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_obj_invalid_index() {
+        let err = parse_obj(b"v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap_err();
+        assert!(err.contains("line 3"), "unexpected error: {}", err);
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use super::supports::{estimate_supports, SupportEstimate, BED_TOLERANCE_MM};
+use super::vec3::{cross, dot, face_normal, length, normalize, scale, Vec3};
+use super::{face_range_totals, Mesh};
+
+/// Normal clusters (largest first) tried as the bottom face, on top of the six axis directions.
+const MAX_CLUSTER_CANDIDATES: usize = 8;
+/// Alternatives returned next to the best orientation.
+const MAX_ALTERNATIVES: usize = 3;
+
+// Score weights; lower scores are better
+const SUPPORT_WEIGHT: f64 = 1.0;
+const HEIGHT_WEIGHT: f64 = 0.5;
+const CONTACT_WEIGHT: f64 = 0.25;
+
+/// One candidate print orientation and its scores.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrientationCandidate {
+    /// Rotation applied to the uploaded mesh (row-major, `v' = R v`).
+    pub rotation: [[f64; 3]; 3],
+    /// Direction in the uploaded model that ends up facing the bed.
+    pub down: [f64; 3],
+    pub height_mm: f64,
+    pub bed_contact_area_cm2: f64,
+    pub supports: SupportEstimate,
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrientationSearch {
+    pub best: OrientationCandidate,
+    /// Next best candidates, best first.
+    pub alternatives: Vec<OrientationCandidate>,
+}
+
+/// Tries the axis-aligned orientations plus the model's largest flat faces
+/// placed on the bed and ranks them by support volume, height and bed contact.
+pub fn optimize_orientation(mesh: &Mesh, overhang_angle_deg: f64) -> OrientationSearch {
+    let (volume, _) = face_range_totals(mesh, 0..mesh.faces.len());
+    let [sx, sy, sz] = mesh.bounding_box().size();
+    let diagonal = (sx * sx + sy * sy + sz * sz).sqrt().max(1e-9);
+    // Fall back to the box volume for open meshes
+    let volume_cm3 = (if volume.abs() > 1e-9 { volume.abs() } else { sx * sy * sz }).max(1e-9) / 1000.0;
+
+    let mut candidates: Vec<OrientationCandidate> = candidate_directions(mesh)
+        .into_iter()
+        .map(|down| {
+            let rotation = rotation_to_bed(down);
+            let rotated = mesh.rotated(&rotation);
+            let height_mm = rotated.bounding_box().size()[2];
+            let contact_mm2 = bed_contact_area(&rotated);
+            let supports = estimate_supports(&rotated, overhang_angle_deg);
+            let score = SUPPORT_WEIGHT * supports.support_volume_cm3 / volume_cm3
+                + HEIGHT_WEIGHT * height_mm / diagonal
+                - CONTACT_WEIGHT * contact_mm2 / (diagonal * diagonal);
+            OrientationCandidate {
+                rotation,
+                down,
+                height_mm,
+                bed_contact_area_cm2: contact_mm2 / 100.0,
+                supports,
+                score,
+            }
+        })
+        .collect();
+
+    // Stable sort keeps the uploaded orientation first on ties
+    candidates.sort_by(|a, b| a.score.total_cmp(&b.score));
+    let best = candidates.remove(0);
+    candidates.truncate(MAX_ALTERNATIVES);
+    OrientationSearch { best, alternatives: candidates }
+}
+
+/// Candidate "down" directions: the uploaded orientation first, the other
+/// axis directions, then the normals of the largest coplanar face groups.
+fn candidate_directions(mesh: &Mesh) -> Vec<Vec3> {
+    let mut directions: Vec<Vec3> = vec![
+        [0.0, 0.0, -1.0], [0.0, 0.0, 1.0],
+        [1.0, 0.0, 0.0], [-1.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0], [0.0, -1.0, 0.0],
+    ];
+
+    // Group faces by quantized normal, weighting by area
+    let mut clusters: HashMap<[i32; 3], (f64, Vec3)> = HashMap::new();
+    for face in 0..mesh.faces.len() {
+        let [a, b, c] = mesh.triangle(face);
+        let normal = face_normal(a, b, c);
+        let area2 = length(normal);
+        if area2 == 0.0 {
+            continue;
+        }
+        let unit = scale(normal, 1.0 / area2);
+        let key = unit.map(|x| (x * 20.0).round() as i32);
+        let entry = clusters.entry(key).or_insert((0.0, [0.0; 3]));
+        entry.0 += area2;
+        entry.1 = [entry.1[0] + normal[0], entry.1[1] + normal[1], entry.1[2] + normal[2]];
+    }
+    let mut clusters: Vec<(f64, Vec3)> = clusters.into_values().collect();
+    clusters.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let same = 1f64.to_radians().cos();
+    for (_, normal) in clusters.into_iter().take(MAX_CLUSTER_CANDIDATES) {
+        let direction = normalize(normal);
+        if direction != [0.0; 3] && directions.iter().all(|d| dot(*d, direction) < same) {
+            directions.push(direction);
+        }
+    }
+    directions
+}
+
+/// Rotation taking the unit vector `down` onto -Z.
+fn rotation_to_bed(down: Vec3) -> [[f64; 3]; 3] {
+    let target = [0.0, 0.0, -1.0];
+    let cos = dot(down, target);
+    if cos > 1.0 - 1e-12 {
+        return [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    }
+    if cos < -1.0 + 1e-12 {
+        // Upside down: half turn about X
+        return [[1.0, 0.0, 0.0], [0.0, -1.0, 0.0], [0.0, 0.0, -1.0]];
+    }
+
+    // Rodrigues' formula about the axis perpendicular to both
+    let axis = normalize(cross(down, target));
+    let sin = (1.0 - cos * cos).sqrt();
+    let [x, y, z] = axis;
+    let t = 1.0 - cos;
+    [
+        [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y],
+        [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x],
+        [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos],
+    ]
+}
+
+/// Area (mm2) of downward faces lying on the bed.
+fn bed_contact_area(mesh: &Mesh) -> f64 {
+    let bed = mesh.bounding_box().min[2];
+    (0..mesh.faces.len())
+        .map(|face| {
+            let [a, b, c] = mesh.triangle(face);
+            let normal = face_normal(a, b, c);
+            let area2 = length(normal);
+            let on_bed = a[2].max(b[2]).max(c[2]) <= bed + BED_TOLERANCE_MM;
+            if on_bed && area2 > 0.0 && normal[2] < 0.0 { area2 / 2.0 } else { 0.0 }
+        })
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::cuboid;
+    use crate::analysis::vec3::sub;
+
+    #[test]
+    fn test_rotation_maps_down_to_bed() {
+        let down = normalize([1.0, -2.0, 0.5]);
+        let r = rotation_to_bed(down);
+        let mapped = [dot(r[0], down), dot(r[1], down), dot(r[2], down)];
+        assert!(length(sub(mapped, [0.0, 0.0, -1.0])) < 1e-12);
+    }
+
+    #[test]
+    fn test_standing_plate_is_laid_flat() {
+        let search = optimize_orientation(&cuboid([0.0; 3], [10.0, 2.0, 40.0]), 45.0);
+        assert!((search.best.height_mm - 2.0).abs() < 1e-9);
+        assert!((search.best.bed_contact_area_cm2 - 4.0).abs() < 1e-9);
+        assert_eq!(search.alternatives.len(), MAX_ALTERNATIVES);
+        assert!(search.alternatives.iter().all(|alt| alt.score >= search.best.score));
+    }
+
+    #[test]
+    fn test_upside_down_pyramid_is_flipped() {
+        // 40x40 base on top, apex on the bed: every side overhangs
+        let mesh = Mesh {
+            vertices: vec![
+                [0.0, 0.0, 10.0], [40.0, 0.0, 10.0], [40.0, 40.0, 10.0], [0.0, 40.0, 10.0],
+                [20.0, 20.0, 0.0],
+            ],
+            faces: vec![[0, 1, 2], [0, 2, 3], [0, 4, 1], [1, 4, 2], [2, 4, 3], [3, 4, 0]],
+        };
+        let uploaded = estimate_supports(&mesh, 45.0);
+        assert!(uploaded.support_volume_cm3 > 0.0);
+
+        let search = optimize_orientation(&mesh, 45.0);
+        assert_eq!(search.best.down, [0.0, 0.0, 1.0]);
+        assert_eq!(search.best.supports.support_volume_cm3, 0.0);
+        assert!((search.best.bed_contact_area_cm2 - 16.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,334 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::{Deserialize, Serialize};
+use super::shells::analyze_shells;
+use super::validation::is_degenerate_triangle;
+use super::vec3::{add, scale};
+use super::{signed_volume, Mesh};
+
+/// Vertices closer than this (mm) are merged.
+pub const WELD_TOLERANCE_MM: f64 = 1e-3;
+/// Boundary loops longer than this are not considered "simple" holes.
+const MAX_HOLE_EDGES: usize = 500;
+
+/// Fixes applied by `repair_mesh`, shown to the customer for approval.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RepairReport {
+    /// Vertices merged into a neighbour within `WELD_TOLERANCE_MM`.
+    pub welded_vertices: usize,
+    pub removed_degenerate_faces: usize,
+    pub removed_duplicate_faces: usize,
+    pub flipped_faces: usize,
+    pub flipped_shells: usize,
+    pub filled_holes: usize,
+    pub added_faces: usize,
+}
+
+impl RepairReport {
+    pub fn has_changes(&self) -> bool {
+        *self != RepairReport::default()
+    }
+}
+
+/// Repairs common defects: near-duplicate vertices, degenerate and duplicate
+/// faces, inconsistent winding, inside-out shells and small holes.
+pub fn repair_mesh(mesh: &Mesh) -> (Mesh, RepairReport) {
+    let mut report = RepairReport::default();
+
+    let (vertices, remap) = weld_vertices(mesh, WELD_TOLERANCE_MM);
+    // Only count vertices that actually moved; exact duplicates along seams are not a defect
+    let referenced: HashSet<usize> = mesh.faces.iter().flatten().copied().collect();
+    report.welded_vertices = referenced.iter().filter(|&&v| mesh.vertices[v] != vertices[remap[v]]).count();
+
+    // Drop degenerate and duplicate faces
+    let mut faces = Vec::with_capacity(mesh.faces.len());
+    let mut seen = HashSet::new();
+    for face in &mesh.faces {
+        let f = [remap[face[0]], remap[face[1]], remap[face[2]]];
+        if f[0] == f[1] || f[1] == f[2] || f[0] == f[2]
+            || is_degenerate_triangle(vertices[f[0]], vertices[f[1]], vertices[f[2]])
+        {
+            report.removed_degenerate_faces += 1;
+            continue;
+        }
+        let mut key = f;
+        key.sort_unstable();
+        if !seen.insert(key) {
+            report.removed_duplicate_faces += 1;
+            continue;
+        }
+        faces.push(f);
+    }
+
+    let mut repaired = Mesh { vertices, faces };
+    let mut shells = unify_orientation(&mut repaired, &mut report);
+    fill_holes(&mut repaired, &mut shells, &mut report);
+    flip_inverted_shells(&mut repaired, &shells, &mut report);
+
+    (repaired.compact(None), report)
+}
+
+/// Merges vertices within `tolerance` using a spatial hash.
+/// Returns the merged vertex list and a map from old to new indices.
+fn weld_vertices(mesh: &Mesh, tolerance: f64) -> (Vec<[f64; 3]>, Vec<usize>) {
+    let cell = |v: f64| (v / tolerance).floor() as i64;
+    let mut grid: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+    let mut vertices: Vec<[f64; 3]> = Vec::new();
+    let mut remap = Vec::with_capacity(mesh.vertices.len());
+
+    for v in &mesh.vertices {
+        let (cx, cy, cz) = (cell(v[0]), cell(v[1]), cell(v[2]));
+        let mut found = None;
+        'search: for dx in -1..=1 {
+            for dy in -1..=1 {
+                for dz in -1..=1 {
+                    if let Some(candidates) = grid.get(&(cx + dx, cy + dy, cz + dz)) {
+                        for &candidate in candidates {
+                            let c = vertices[candidate];
+                            let dist2 = (c[0] - v[0]).powi(2) + (c[1] - v[1]).powi(2) + (c[2] - v[2]).powi(2);
+                            if dist2 <= tolerance * tolerance {
+                                found = Some(candidate);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let index = found.unwrap_or_else(|| {
+            vertices.push(*v);
+            grid.entry((cx, cy, cz)).or_default().push(vertices.len() - 1);
+            vertices.len() - 1
+        });
+        remap.push(index);
+    }
+
+    (vertices, remap)
+}
+
+/// Makes neighbouring faces agree on winding by walking each connected shell
+/// across manifold edges, keeping whichever orientation most faces already
+/// have. Returns the shell index of every face.
+fn unify_orientation(mesh: &mut Mesh, report: &mut RepairReport) -> Vec<usize> {
+    let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (index, f) in mesh.faces.iter().enumerate() {
+        for i in 0..3 {
+            let (a, b) = (f[i], f[(i + 1) % 3]);
+            edge_faces.entry((a.min(b), a.max(b))).or_default().push(index);
+        }
+    }
+
+    let mut shell = vec![usize::MAX; mesh.faces.len()];
+    let mut shell_count = 0;
+    for start in 0..mesh.faces.len() {
+        if shell[start] != usize::MAX {
+            continue;
+        }
+        shell[start] = shell_count;
+        let mut members = vec![start];
+        let mut flipped = 0;
+        let mut queue = VecDeque::from([start]);
+        while let Some(face) = queue.pop_front() {
+            let f = mesh.faces[face];
+            for i in 0..3 {
+                let (a, b) = (f[i], f[(i + 1) % 3]);
+                let neighbours = &edge_faces[&(a.min(b), a.max(b))];
+                if neighbours.len() != 2 {
+                    continue;
+                }
+                let other = if neighbours[0] == face { neighbours[1] } else { neighbours[0] };
+                if shell[other] != usize::MAX {
+                    continue;
+                }
+                // The neighbour must traverse the shared edge as b -> a
+                let g = mesh.faces[other];
+                if (0..3).any(|j| g[j] == a && g[(j + 1) % 3] == b) {
+                    mesh.faces[other] = [g[0], g[2], g[1]];
+                    flipped += 1;
+                }
+                shell[other] = shell_count;
+                members.push(other);
+                queue.push_back(other);
+            }
+        }
+
+        // The seed face was the odd one out: flip the shell back so only it changes
+        if flipped * 2 > members.len() {
+            for &face in &members {
+                let f = mesh.faces[face];
+                mesh.faces[face] = [f[0], f[2], f[1]];
+            }
+            flipped = members.len() - flipped;
+        }
+        report.flipped_faces += flipped;
+        shell_count += 1;
+    }
+
+    shell
+}
+
+/// Closes boundary loops that form a simple cycle with a fan of triangles.
+fn fill_holes(mesh: &mut Mesh, shells: &mut Vec<usize>, report: &mut RepairReport) {
+    let mut directed: HashSet<(usize, usize)> = HashSet::new();
+    let mut edge_shell: HashMap<(usize, usize), usize> = HashMap::new();
+    for (index, f) in mesh.faces.iter().enumerate() {
+        for i in 0..3 {
+            directed.insert((f[i], f[(i + 1) % 3]));
+            edge_shell.insert((f[i], f[(i + 1) % 3]), shells[index]);
+        }
+    }
+
+    // Boundary edges have no twin; a simple loop has one outgoing edge per vertex
+    let mut next: HashMap<usize, usize> = HashMap::new();
+    let mut ambiguous: HashSet<usize> = HashSet::new();
+    for &(a, b) in &directed {
+        if !directed.contains(&(b, a)) && next.insert(a, b).is_some() {
+            ambiguous.insert(a);
+        }
+    }
+
+    let mut visited: HashSet<usize> = HashSet::new();
+    let mut starts: Vec<usize> = next.keys().copied().collect();
+    starts.sort_unstable();
+    for start in starts {
+        if visited.contains(&start) || ambiguous.contains(&start) {
+            continue;
+        }
+
+        let mut hole = vec![start];
+        let mut current = start;
+        let closed = loop {
+            let Some(&following) = next.get(&current) else { break false };
+            if following == start {
+                break true;
+            }
+            if ambiguous.contains(&following) || hole.len() > MAX_HOLE_EDGES || hole.contains(&following) {
+                break false;
+            }
+            hole.push(following);
+            current = following;
+        };
+        visited.extend(hole.iter().copied());
+        if !closed || hole.len() < 3 {
+            continue;
+        }
+
+        let shell = edge_shell[&(hole[0], hole[1])];
+        if hole.len() == 3 {
+            mesh.faces.push([hole[0], hole[2], hole[1]]);
+            shells.push(shell);
+            report.added_faces += 1;
+        } else {
+            let center = hole.iter().fold([0.0; 3], |acc, &v| add(acc, mesh.vertices[v]));
+            mesh.vertices.push(scale(center, 1.0 / hole.len() as f64));
+            let c = mesh.vertices.len() - 1;
+            for i in 0..hole.len() {
+                mesh.faces.push([c, hole[(i + 1) % hole.len()], hole[i]]);
+                shells.push(shell);
+            }
+            report.added_faces += hole.len();
+        }
+        report.filled_holes += 1;
+    }
+}
+
+/// Reverses every face of shells wound against their nesting: parts should
+/// enclose a positive volume and the cavities inside them a negative one.
+fn flip_inverted_shells(mesh: &mut Mesh, shells: &[usize], report: &mut RepairReport) {
+    let mut void_faces = vec![false; mesh.faces.len()];
+    for shell in analyze_shells(mesh).into_iter().filter(|s| s.is_void) {
+        for face in shell.faces {
+            void_faces[face] = true;
+        }
+    }
+
+    let mut volumes: HashMap<usize, f64> = HashMap::new();
+    let mut is_void: HashMap<usize, bool> = HashMap::new();
+    for (index, &shell) in shells.iter().enumerate() {
+        let [a, b, c] = mesh.triangle(index);
+        *volumes.entry(shell).or_insert(0.0) += signed_volume(a, b, c);
+        is_void.entry(shell).or_insert(void_faces[index]);
+    }
+    let inverted = |shell: &usize| (volumes[shell] < 0.0) != is_void[shell];
+
+    for (index, shell) in shells.iter().enumerate() {
+        if inverted(shell) {
+            let f = mesh.faces[index];
+            mesh.faces[index] = [f[0], f[2], f[1]];
+        }
+    }
+    report.flipped_shells = volumes.keys().filter(|shell| inverted(shell)).count();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_mesh;
+    use crate::analysis::test_util::{add_box, cube};
+    use crate::analysis::validation::validate_mesh;
+
+    #[test]
+    fn test_clean_mesh_is_unchanged() {
+        let (repaired, report) = repair_mesh(&cube(10.0));
+        assert!(!report.has_changes());
+        assert_eq!(repaired.faces.len(), 12);
+    }
+
+    fn triangles(mesh: &Mesh) -> Vec<[[f64; 3]; 3]> {
+        (0..mesh.faces.len()).map(|face| mesh.triangle(face)).collect()
+    }
+
+    #[test]
+    fn test_cavity_keeps_its_winding() {
+        let mut hollow = cube(20.0);
+        add_box(&mut hollow, [5.0; 3], [15.0; 3], true);
+        let (repaired, report) = repair_mesh(&hollow);
+        assert!(!report.has_changes(), "{:?}", report);
+        assert_eq!(triangles(&repaired), triangles(&hollow));
+
+        // A cavity wound outward is the inverted shell, not the part around it
+        let mut solid_cavity = cube(20.0);
+        add_box(&mut solid_cavity, [5.0; 3], [15.0; 3], false);
+        let (repaired, report) = repair_mesh(&solid_cavity);
+        assert_eq!(report.flipped_shells, 1);
+        assert_eq!(triangles(&repaired), triangles(&hollow));
+        assert!((analyze_mesh(&repaired).volume_cm3 - 7.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_repairs_flipped_inverted_and_open_cube() {
+        let mut mesh = cube(10.0);
+        // Remove the top quad, flip one face and turn the rest inside out
+        mesh.faces.retain(|f| *f != [4, 5, 7] && *f != [4, 7, 6]);
+        for f in mesh.faces.iter_mut() {
+            *f = [f[0], f[2], f[1]];
+        }
+        mesh.faces[0] = [0, 2, 3];
+        // A vertex that is almost, but not exactly, a duplicate of vertex 1
+        mesh.vertices.push([10.0 + 1e-5, 0.0, 0.0]);
+        mesh.faces[2] = mesh.faces[2].map(|v| if v == 1 { 8 } else { v });
+
+        assert!(!validate_mesh(&mesh).is_printable());
+
+        let (repaired, report) = repair_mesh(&mesh);
+        assert_eq!(report.welded_vertices, 1);
+        assert_eq!(report.flipped_faces, 1);
+        assert_eq!(report.flipped_shells, 1);
+        assert_eq!(report.filled_holes, 1);
+
+        assert!(validate_mesh(&repaired).is_printable());
+        assert!((analyze_mesh(&repaired).volume_cm3 - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_drops_degenerate_and_duplicate_faces() {
+        let mut mesh = cube(10.0);
+        mesh.faces.push([0, 0, 1]);
+        mesh.faces.push([1, 7, 5]);
+        let (repaired, report) = repair_mesh(&mesh);
+        assert_eq!(report.removed_degenerate_faces, 1);
+        assert_eq!(report.removed_duplicate_faces, 1);
+        assert_eq!(repaired.faces.len(), 12);
+        assert!(validate_mesh(&repaired).is_printable());
+    }
+}
@@ -0,0 +1,171 @@
+use std::fmt::Write;
+use serde::{Deserialize, Serialize};
+use super::slicer::{slice_layer, Contour};
+use super::validation::weld_exact;
+use super::vec3::{add, cross, dot, length, normalize, scale, sub, Vec3};
+use super::Mesh;
+
+/// Margin (mm) around the section in SVG output.
+const SVG_MARGIN_MM: f64 = 1.0;
+
+/// The model cut by a plane, in the plane's own 2D coordinates.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossSection {
+    /// Unit normal of the cutting plane.
+    pub normal: Vec3,
+    /// Distance (mm) of the plane from the origin along `normal`.
+    pub offset_mm: f64,
+    /// Model-space directions of the contours' X and Y axes; horizontal
+    /// sections keep the model's X and Y.
+    pub u_axis: Vec3,
+    pub v_axis: Vec3,
+    /// CCW outer boundaries and CW holes, seen from the side `normal` points to.
+    pub contours: Vec<Contour>,
+    pub area_mm2: f64,
+    /// Lower corner of the contours along `u_axis` and `v_axis`.
+    pub min: [f64; 2],
+    /// Width and height of the section along `u_axis` and `v_axis`.
+    pub size_mm: [f64; 2],
+}
+
+/// Cuts the mesh with the plane `p · normal = offset`; without an offset the
+/// plane passes through the middle of the bounding box.
+pub fn cross_section(mesh: &Mesh, normal: Vec3, offset: Option<f64>) -> Result<CrossSection, String> {
+    if !normal.iter().all(|c| c.is_finite()) || length(normal) == 0.0 {
+        return Err("Normal must be a non-zero vector".to_string());
+    }
+    if offset.is_some_and(|o| !o.is_finite()) {
+        return Err("Offset must be finite".to_string());
+    }
+    let normal = normalize(normal);
+    let (u_axis, v_axis) = plane_axes(normal);
+    let bbox = mesh.bounding_box();
+    let offset_mm = offset.unwrap_or_else(|| dot(scale(add(bbox.min, bbox.max), 0.5), normal));
+
+    // Slice a copy expressed in (u, v, normal) coordinates at height `offset`
+    let local = Mesh {
+        vertices: mesh.vertices.iter().map(|&p| [dot(p, u_axis), dot(p, v_axis), dot(p, normal)]).collect(),
+        faces: mesh.faces.clone(),
+    };
+    let faces = weld_exact(&local);
+    let bucket: Vec<usize> = (0..faces.len())
+        .filter(|&face| {
+            let heights = faces[face].map(|v| local.vertices[v][2]);
+            heights.iter().any(|&z| z >= offset_mm) && heights.iter().any(|&z| z < offset_mm)
+        })
+        .collect();
+    let layer = slice_layer(&local, &faces, &bucket, offset_mm);
+
+    let points = layer.contours.iter().flat_map(|c| &c.points);
+    let (min, max) = points.fold(([f64::INFINITY; 2], [f64::NEG_INFINITY; 2]), |(min, max), p| {
+        ([min[0].min(p[0]), min[1].min(p[1])], [max[0].max(p[0]), max[1].max(p[1])])
+    });
+    let (min, size_mm) = if layer.contours.is_empty() { ([0.0; 2], [0.0; 2]) } else { (min, [max[0] - min[0], max[1] - min[1]]) };
+
+    Ok(CrossSection { normal, offset_mm, u_axis, v_axis, contours: layer.contours, area_mm2: layer.area_mm2, min, size_mm })
+}
+
+/// Right-handed in-plane axes: X is projected onto the plane, or Y when the
+/// normal is (nearly) along X.
+fn plane_axes(normal: Vec3) -> (Vec3, Vec3) {
+    let reference = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+    let u = normalize(sub(reference, scale(normal, dot(reference, normal))));
+    (u, cross(normal, u))
+}
+
+/// The section as an SVG drawing in millimetres, Y up. Closed contours are
+/// filled even-odd so holes stay empty; open ones (mesh holes) are stroked red.
+pub fn section_svg(section: &CrossSection) -> String {
+    let [width, height] = section.size_mm.map(|s| s + 2.0 * SVG_MARGIN_MM);
+    let left = section.min[0] - SVG_MARGIN_MM;
+    // Y is flipped, so the top of the view is the section's highest point
+    let top = -(section.min[1] + section.size_mm[1]) - SVG_MARGIN_MM;
+
+    let mut filled = String::new();
+    let mut open = String::new();
+    for contour in &section.contours {
+        let path = if contour.closed { &mut filled } else { &mut open };
+        for (i, p) in contour.points.iter().enumerate() {
+            let _ = write!(path, "{}{:.4} {:.4} ", if i == 0 { "M" } else { "L" }, p[0], -p[1]);
+        }
+        if contour.closed {
+            path.push_str("Z ");
+        }
+    }
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.4}mm\" height=\"{h:.4}mm\" viewBox=\"{left:.4} {top:.4} {w:.4} {h:.4}\">\n",
+        w = width,
+        h = height,
+    );
+    if !filled.is_empty() {
+        let _ = writeln!(svg, "  <path d=\"{}\" fill=\"#4a90d9\" fill-rule=\"evenodd\" stroke=\"#1f4e79\" stroke-width=\"0.1\"/>", filled.trim_end());
+    }
+    if !open.is_empty() {
+        let _ = writeln!(svg, "  <path d=\"{}\" fill=\"none\" stroke=\"#d0021b\" stroke-width=\"0.2\"/>", open.trim_end());
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::add_box;
+
+    #[test]
+    fn test_horizontal_section_keeps_model_axes() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [20.0, 10.0, 5.0], false);
+        let section = cross_section(&mesh, [0.0, 0.0, 2.0], Some(1.0)).unwrap();
+        assert_eq!(section.normal, [0.0, 0.0, 1.0]);
+        assert_eq!((section.u_axis, section.v_axis), ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]));
+        assert_eq!(section.contours.len(), 1);
+        assert!(section.contours[0].area_mm2 > 0.0);
+        assert!((section.area_mm2 - 200.0).abs() < 1e-9);
+        assert_eq!(section.min, [0.0, 0.0]);
+        assert_eq!(section.size_mm, [20.0, 10.0]);
+    }
+
+    #[test]
+    fn test_diagonal_section_through_middle() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        let section = cross_section(&mesh, [1.0, 0.0, 1.0], None).unwrap();
+        assert!((section.offset_mm - 10.0 / 2f64.sqrt()).abs() < 1e-9);
+        // A 10 mm by 10√2 mm rectangle
+        assert!((section.area_mm2 - 100.0 * 2f64.sqrt()).abs() < 1e-9);
+        let mut size = section.size_mm;
+        size.sort_by(f64::total_cmp);
+        assert!((size[0] - 10.0).abs() < 1e-9 && (size[1] - 10.0 * 2f64.sqrt()).abs() < 1e-9);
+        assert!((dot(section.u_axis, section.v_axis)).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_section_through_cavity_and_svg() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        add_box(&mut mesh, [2.0; 3], [8.0; 3], true);
+        let section = cross_section(&mesh, [0.0, 1.0, 0.0], Some(5.0)).unwrap();
+        assert_eq!(section.contours.len(), 2);
+        assert!((section.area_mm2 - 64.0).abs() < 1e-9);
+
+        let svg = section_svg(&section);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("width=\"12.0000mm\""));
+        assert_eq!(svg.matches('Z').count(), 2);
+        assert!(svg.contains("fill-rule=\"evenodd\""));
+    }
+
+    #[test]
+    fn test_plane_missing_the_model() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        let section = cross_section(&mesh, [0.0, 0.0, 1.0], Some(50.0)).unwrap();
+        assert!(section.contours.is_empty());
+        assert_eq!(section.area_mm2, 0.0);
+        assert_eq!(section.size_mm, [0.0, 0.0]);
+        assert!(cross_section(&mesh, [0.0; 3], None).is_err());
+        assert!(cross_section(&mesh, [0.0, 0.0, 1.0], Some(f64::NAN)).is_err());
+    }
+}
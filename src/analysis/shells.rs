@@ -0,0 +1,224 @@
+use serde::{Deserialize, Serialize};
+use super::bvh::ray_triangle;
+use super::validation::weld_exact;
+use super::{signed_volume, triangle_area, BoundingBox, Mesh};
+
+/// A connected set of faces (sharing vertices) in the mesh.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Shell {
+    pub index: usize,
+    pub face_count: usize,
+    /// Volume enclosed by this shell alone, ignoring what it contains.
+    pub volume_cm3: f64,
+    pub surface_area_cm2: f64,
+    pub bounding_box: BoundingBox,
+    /// Innermost shell this one lies inside.
+    pub enclosed_by: Option<usize>,
+    /// Inside an odd number of shells: a cavity in the part around it.
+    pub is_void: bool,
+    /// Volume of this part once its voids (and parts inside those) are accounted for.
+    pub net_volume_cm3: f64,
+    #[serde(skip)]
+    pub faces: Vec<usize>,
+}
+
+/// Splits the mesh into connected shells and works out which ones are nested.
+/// Shells are ordered by their first face.
+pub fn analyze_shells(mesh: &Mesh) -> Vec<Shell> {
+    let mut shells: Vec<Shell> = connected_faces(mesh)
+        .into_iter()
+        .enumerate()
+        .map(|(index, faces)| {
+            let (mut volume, mut area) = (0.0, 0.0);
+            for &face in &faces {
+                let [a, b, c] = mesh.triangle(face);
+                volume += signed_volume(a, b, c);
+                area += triangle_area(a, b, c);
+            }
+            Shell {
+                index,
+                face_count: faces.len(),
+                volume_cm3: volume.abs() / 1000.0,
+                surface_area_cm2: area / 100.0,
+                bounding_box: faces_bounding_box(mesh, &faces),
+                enclosed_by: None,
+                is_void: false,
+                net_volume_cm3: 0.0,
+                faces,
+            }
+        })
+        .collect();
+
+    // Innermost container: the smallest enclosing shell by volume
+    let containers: Vec<Option<usize>> = shells
+        .iter()
+        .map(|inner| {
+            shells
+                .iter()
+                .filter(|outer| outer.index != inner.index && outer.volume_cm3 > inner.volume_cm3)
+                .filter(|outer| box_contains(&outer.bounding_box, &inner.bounding_box))
+                .filter(|outer| point_inside(mesh, &outer.faces, mesh.triangle(inner.faces[0])[0]))
+                .min_by(|a, b| a.volume_cm3.total_cmp(&b.volume_cm3))
+                .map(|outer| outer.index)
+        })
+        .collect();
+    for (shell, container) in shells.iter_mut().zip(&containers) {
+        shell.enclosed_by = *container;
+    }
+    for index in 0..shells.len() {
+        let mut depth = 0;
+        let mut parent = containers[index];
+        while let Some(p) = parent {
+            depth += 1;
+            parent = containers[p];
+        }
+        shells[index].is_void = depth % 2 == 1;
+    }
+
+    // Every shell nested in a part adds or removes its own volume from it
+    for index in 0..shells.len() {
+        let mut net = 0.0;
+        for descendant in nested_in(&shells, index) {
+            let shell = &shells[descendant];
+            net += if shell.is_void { -shell.volume_cm3 } else { shell.volume_cm3 };
+        }
+        shells[index].net_volume_cm3 = net.abs();
+    }
+    shells
+}
+
+/// Total printed volume (cm3): solid shells minus the voids inside them.
+pub fn total_volume_cm3(shells: &[Shell]) -> f64 {
+    shells
+        .iter()
+        .map(|shell| if shell.is_void { -shell.volume_cm3 } else { shell.volume_cm3 })
+        .sum::<f64>()
+        .max(0.0)
+}
+
+/// The part `index` with everything nested inside it, as its own mesh.
+pub fn part_mesh(mesh: &Mesh, shells: &[Shell], index: usize) -> Mesh {
+    let mut part = Mesh { vertices: mesh.vertices.clone(), faces: Vec::new() };
+    for shell in nested_in(shells, index) {
+        part.faces.extend(shells[shell].faces.iter().map(|&face| mesh.faces[face]));
+    }
+    part
+}
+
+/// `index` and all shells nested inside it, at any depth.
+fn nested_in(shells: &[Shell], index: usize) -> Vec<usize> {
+    let mut found = vec![index];
+    let mut next = 0;
+    while next < found.len() {
+        let parent = found[next];
+        found.extend(shells.iter().filter(|s| s.enclosed_by == Some(parent)).map(|s| s.index));
+        next += 1;
+    }
+    found
+}
+
+/// Groups faces into connected components through shared (welded) vertices.
+fn connected_faces(mesh: &Mesh) -> Vec<Vec<usize>> {
+    let faces = weld_exact(mesh);
+    let mut parent: Vec<usize> = (0..mesh.vertices.len()).collect();
+    fn find(parent: &mut [usize], mut v: usize) -> usize {
+        while parent[v] != v {
+            parent[v] = parent[parent[v]];
+            v = parent[v];
+        }
+        v
+    }
+    for f in &faces {
+        for i in 1..3 {
+            let (a, b) = (find(&mut parent, f[0]), find(&mut parent, f[i]));
+            if a != b {
+                parent[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut component_of: Vec<Option<usize>> = vec![None; mesh.vertices.len()];
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for (face, f) in faces.iter().enumerate() {
+        let root = find(&mut parent, f[0]);
+        let component = *component_of[root].get_or_insert_with(|| {
+            components.push(Vec::new());
+            components.len() - 1
+        });
+        components[component].push(face);
+    }
+    components
+}
+
+fn faces_bounding_box(mesh: &Mesh, faces: &[usize]) -> BoundingBox {
+    let mut min = [f64::INFINITY; 3];
+    let mut max = [f64::NEG_INFINITY; 3];
+    for &face in faces {
+        for v in mesh.triangle(face) {
+            for axis in 0..3 {
+                min[axis] = min[axis].min(v[axis]);
+                max[axis] = max[axis].max(v[axis]);
+            }
+        }
+    }
+    BoundingBox { min, max }
+}
+
+fn box_contains(outer: &BoundingBox, inner: &BoundingBox) -> bool {
+    (0..3).all(|axis| outer.min[axis] <= inner.min[axis] && inner.max[axis] <= outer.max[axis])
+}
+
+/// Parity test: a ray from `point` crosses a closed shell an odd number of times
+/// when it starts inside. The ray direction is skewed to avoid hitting edges.
+fn point_inside(mesh: &Mesh, faces: &[usize], point: [f64; 3]) -> bool {
+    let direction = [0.5773, 0.5774, 0.5775];
+    faces
+        .iter()
+        .filter(|&&face| {
+            let [a, b, c] = mesh.triangle(face);
+            ray_triangle(point, direction, a, b, c).is_some_and(|t| t > 0.0)
+        })
+        .count()
+        % 2
+        == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::add_box;
+
+    #[test]
+    fn test_kit_of_parts() {
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [10.0; 3], false);
+        add_box(&mut mesh, [20.0, 0.0, 0.0], [40.0, 10.0, 10.0], false);
+        let shells = analyze_shells(&mesh);
+        assert_eq!(shells.len(), 2);
+        assert_eq!(shells[0].face_count, 12);
+        assert!((shells[1].volume_cm3 - 2.0).abs() < 1e-9);
+        assert!((shells[1].bounding_box.size()[0] - 20.0).abs() < 1e-9);
+        assert!(shells.iter().all(|s| s.enclosed_by.is_none() && !s.is_void));
+        assert!((total_volume_cm3(&shells) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_void_is_not_double_counted() {
+        // Cavity modelled with outward normals, so the signed volumes would add up
+        let mut mesh = Mesh::default();
+        add_box(&mut mesh, [0.0; 3], [20.0; 3], false);
+        add_box(&mut mesh, [5.0; 3], [15.0; 3], false);
+        // A loose part inside the cavity
+        add_box(&mut mesh, [8.0; 3], [12.0; 3], true);
+        let shells = analyze_shells(&mesh);
+
+        assert_eq!(shells[1].enclosed_by, Some(0));
+        assert!(shells[1].is_void);
+        assert_eq!(shells[2].enclosed_by, Some(1));
+        assert!(!shells[2].is_void);
+        assert!((total_volume_cm3(&shells) - (8.0 - 1.0 + 0.064)).abs() < 1e-9);
+        assert!((shells[0].net_volume_cm3 - (8.0 - 1.0 + 0.064)).abs() < 1e-9);
+        assert!((shells[2].net_volume_cm3 - 0.064).abs() < 1e-9);
+        assert_eq!(part_mesh(&mesh, &shells, 1).faces.len(), 24);
+    }
+}
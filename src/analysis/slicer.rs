@@ -0,0 +1,252 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use super::validation::weld_exact;
+use super::Mesh;
+
+/// Refuse to slice into more layers than this.
+pub const MAX_LAYERS: usize = 20_000;
+
+/// A polygon in a layer, counter-clockwise for outer boundaries and
+/// clockwise for holes (seen from above).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Contour {
+    pub points: Vec<[f64; 2]>,
+    /// False when the mesh has a hole and the loop could not be closed.
+    pub closed: bool,
+    /// Signed area (mm2): positive for outer boundaries, negative for holes.
+    pub area_mm2: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    /// Height of the slicing plane (middle of the layer).
+    pub z: f64,
+    pub contours: Vec<Contour>,
+    /// Cross-section area (mm2) enclosed by the closed contours.
+    pub area_mm2: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlicedModel {
+    pub layer_height_mm: f64,
+    pub layers: Vec<Layer>,
+}
+
+impl SlicedModel {
+    /// Volume (cm3) of the stacked layers.
+    pub fn volume_cm3(&self) -> f64 {
+        self.layers.iter().map(|layer| layer.area_mm2).sum::<f64>() * self.layer_height_mm / 1000.0
+    }
+}
+
+/// Cuts the mesh with horizontal planes through the middle of each layer,
+/// starting at the lowest point of the mesh.
+pub fn slice_mesh(mesh: &Mesh, layer_height_mm: f64) -> Result<SlicedModel, String> {
+    if layer_height_mm.is_nan() || layer_height_mm <= 0.0 {
+        return Err("Layer height must be positive".to_string());
+    }
+    let bbox = mesh.bounding_box();
+    let layer_count = (bbox.size()[2] / layer_height_mm).ceil() as usize;
+    if layer_count > MAX_LAYERS {
+        return Err(format!("Model needs {} layers at {} mm (limit {})", layer_count, layer_height_mm, MAX_LAYERS));
+    }
+    let plane = |layer: usize| bbox.min[2] + (layer as f64 + 0.5) * layer_height_mm;
+
+    // Link segments through the welded edges they cross, so loops close exactly
+    let faces = weld_exact(mesh);
+    let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (face, f) in faces.iter().enumerate() {
+        let (lo, hi) = f.iter().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+            (lo.min(mesh.vertices[v][2]), hi.max(mesh.vertices[v][2]))
+        });
+        let first = ((lo - bbox.min[2]) / layer_height_mm - 0.5).ceil().max(0.0) as usize;
+        let last = (((hi - bbox.min[2]) / layer_height_mm - 0.5).floor().max(-1.0) + 1.0) as usize;
+        for bucket in buckets.iter_mut().take(last.min(layer_count)).skip(first) {
+            bucket.push(face);
+        }
+    }
+
+    let layers = buckets
+        .iter()
+        .enumerate()
+        .map(|(layer, bucket)| slice_layer(mesh, &faces, bucket, plane(layer)))
+        .collect();
+    Ok(SlicedModel { layer_height_mm, layers })
+}
+
+type EdgeKey = (usize, usize);
+
+pub(crate) fn slice_layer(mesh: &Mesh, faces: &[[usize; 3]], bucket: &[usize], z: f64) -> Layer {
+    // Vertices on the plane count as above it, so every crossing is on an edge
+    let above = |v: usize| mesh.vertices[v][2] >= z;
+    let crossing = |(a, b): EdgeKey| {
+        let (pa, pb) = (mesh.vertices[a], mesh.vertices[b]);
+        let t = (z - pa[2]) / (pb[2] - pa[2]);
+        [pa[0] + t * (pb[0] - pa[0]), pa[1] + t * (pb[1] - pa[1])]
+    };
+
+    // Segments join the two edges a face crosses; winding is ignored so
+    // inconsistently oriented meshes still close (ordered for stable output)
+    let mut links: BTreeMap<EdgeKey, Vec<EdgeKey>> = BTreeMap::new();
+    for &face in bucket {
+        let f = faces[face];
+        let cut: Vec<EdgeKey> = (0..3)
+            .map(|i| (f[i], f[(i + 1) % 3]))
+            .filter(|&(a, b)| above(a) != above(b))
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+        if cut.len() != 2 {
+            continue;
+        }
+        links.entry(cut[0]).or_default().push(cut[1]);
+        links.entry(cut[1]).or_default().push(cut[0]);
+    }
+
+    let mut loops = Vec::new();
+    while let Some(first) = next_start(&links) {
+        let mut points = vec![crossing(first)];
+        let mut edge = first;
+        let mut closed = false;
+        while let Some(next) = take_link(&mut links, edge) {
+            edge = next;
+            if edge == first {
+                closed = true;
+                break;
+            }
+            points.push(crossing(edge));
+        }
+        if points.len() >= 2 {
+            loops.push((points, closed));
+        }
+    }
+
+    // Outer boundaries are nested inside an even number of other loops
+    let closed_loops: Vec<&Vec<[f64; 2]>> = loops.iter().filter(|l| l.1).map(|l| &l.0).collect();
+    let is_hole: Vec<bool> = loops
+        .iter()
+        .map(|(points, closed)| {
+            *closed && closed_loops.iter().filter(|&&other| !std::ptr::eq(other, points) && contains(other, points[0])).count() % 2 == 1
+        })
+        .collect();
+
+    let contours: Vec<Contour> = loops
+        .into_iter()
+        .zip(is_hole)
+        .map(|((mut points, closed), hole)| {
+            if !closed {
+                return Contour { points, closed, area_mm2: 0.0 };
+            }
+            let area = shoelace(&points);
+            if (area < 0.0) != hole {
+                points.reverse();
+            }
+            Contour { points, closed, area_mm2: if hole { -area.abs() } else { area.abs() } }
+        })
+        .collect();
+
+    let area_mm2 = contours.iter().map(|c| c.area_mm2).sum::<f64>().max(0.0);
+    Layer { z, contours, area_mm2 }
+}
+
+/// Starts open chains at one of their ends so they come out in one piece.
+fn next_start(links: &BTreeMap<EdgeKey, Vec<EdgeKey>>) -> Option<EdgeKey> {
+    links
+        .iter()
+        .find(|(_, next)| next.len() == 1)
+        .or_else(|| links.iter().next())
+        .map(|(&edge, _)| edge)
+}
+
+/// Follows one link from `edge`, removing it in both directions.
+fn take_link(links: &mut BTreeMap<EdgeKey, Vec<EdgeKey>>, edge: EdgeKey) -> Option<EdgeKey> {
+    let next = links.get_mut(&edge)?.pop()?;
+    for (from, to) in [(edge, next), (next, edge)] {
+        if let Some(list) = links.get_mut(&from) {
+            if let Some(i) = list.iter().position(|&e| e == to) {
+                list.swap_remove(i);
+            }
+            if list.is_empty() {
+                links.remove(&from);
+            }
+        }
+    }
+    Some(next)
+}
+
+/// Even-odd point-in-polygon test.
+fn contains(polygon: &[[f64; 2]], point: [f64; 2]) -> bool {
+    let mut inside = false;
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        if (a[1] > point[1]) != (b[1] > point[1])
+            && point[0] < a[0] + (point[1] - a[1]) / (b[1] - a[1]) * (b[0] - a[0])
+        {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn shoelace(points: &[[f64; 2]]) -> f64 {
+    let mut twice = 0.0;
+    for i in 0..points.len() {
+        let (p, q) = (points[i], points[(i + 1) % points.len()]);
+        twice += p[0] * q[1] - q[0] * p[1];
+    }
+    twice / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::{add_box, cuboid};
+
+    #[test]
+    fn test_cube_layers() {
+        let sliced = slice_mesh(&cuboid([0.0; 3], [10.0; 3]), 0.2).unwrap();
+        assert_eq!(sliced.layers.len(), 50);
+        assert!((sliced.layers[0].z - 0.1).abs() < 1e-9);
+        for layer in &sliced.layers {
+            assert_eq!(layer.contours.len(), 1);
+            assert!(layer.contours[0].closed);
+            assert!((layer.area_mm2 - 100.0).abs() < 1e-9);
+        }
+        assert!((sliced.volume_cm3() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_hole_contour_is_clockwise() {
+        // 10x10 box with a 6x6 cavity (inner faces point inward)
+        let mut mesh = cuboid([0.0; 3], [10.0; 3]);
+        add_box(&mut mesh, [2.0; 3], [8.0; 3], true);
+
+        let sliced = slice_mesh(&mesh, 1.0).unwrap();
+        let middle = &sliced.layers[5];
+        assert_eq!(middle.contours.len(), 2);
+        let mut areas: Vec<f64> = middle.contours.iter().map(|c| c.area_mm2).collect();
+        areas.sort_by(f64::total_cmp);
+        assert!((areas[0] + 36.0).abs() < 1e-9);
+        assert!((areas[1] - 100.0).abs() < 1e-9);
+        assert!((middle.area_mm2 - 64.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_inconsistent_winding_still_closes() {
+        let mut cube = cuboid([0.0; 3], [10.0; 3]);
+        for face in cube.faces.iter_mut().step_by(3) {
+            face.swap(1, 2);
+        }
+        let sliced = slice_mesh(&cube, 1.0).unwrap();
+        for layer in &sliced.layers {
+            assert_eq!(layer.contours.len(), 1);
+            assert!((layer.area_mm2 - 100.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_layer_height() {
+        let cube = cuboid([0.0; 3], [10.0; 3]);
+        assert!(slice_mesh(&cube, 0.0).is_err());
+        assert!(slice_mesh(&cube, 1e-6).is_err());
+    }
+}
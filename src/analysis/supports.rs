@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use super::vec3::{face_normal, length, sub};
+use super::Mesh;
+
+/// Overhangs steeper than this (degrees from the build direction) need support.
+pub const DEFAULT_OVERHANG_ANGLE_DEG: f64 = 45.0;
+/// Faces within this distance (mm) of the bed rest on it and need no support.
+pub(crate) const BED_TOLERANCE_MM: f64 = 0.01;
+/// Target spacing (mm) of the sample points dropped from each overhang face.
+const SAMPLE_SPACING_MM: f64 = 2.0;
+const MAX_SUBDIVISIONS: usize = 16;
+
+/// Support requirements for printing a mesh along +Z.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SupportEstimate {
+    pub overhang_angle_deg: f64,
+    pub overhang_faces: usize,
+    pub overhang_area_cm2: f64,
+    /// Space between the overhangs and the bed or the part below them.
+    pub support_volume_cm3: f64,
+}
+
+/// Finds faces overhanging more than `overhang_angle_deg` from vertical and
+/// estimates the support volume by dropping each one onto whatever lies below.
+pub fn estimate_supports(mesh: &Mesh, overhang_angle_deg: f64) -> SupportEstimate {
+    let mut estimate = SupportEstimate { overhang_angle_deg, ..Default::default() };
+    if mesh.faces.is_empty() {
+        return estimate;
+    }
+
+    let bed = mesh.bounding_box().min[2];
+    // A face overhangs when its normal points further down than this
+    let threshold = -overhang_angle_deg.to_radians().sin();
+    let columns = ColumnIndex::new(mesh);
+
+    let mut overhang_area = 0.0;
+    let mut support_volume = 0.0;
+    for face in 0..mesh.faces.len() {
+        let [a, b, c] = mesh.triangle(face);
+        let normal = face_normal(a, b, c);
+        let area2 = length(normal);
+        if area2 == 0.0 || normal[2] / area2 >= threshold {
+            continue;
+        }
+        if a[2].max(b[2]).max(c[2]) <= bed + BED_TOLERANCE_MM {
+            continue;
+        }
+
+        estimate.overhang_faces += 1;
+        overhang_area += area2 / 2.0;
+
+        // Split the face into k^2 equal sub-triangles and drop a column from each centroid
+        let longest = length(sub(b, a)).max(length(sub(c, b))).max(length(sub(a, c)));
+        let k = ((longest / SAMPLE_SPACING_MM).ceil() as usize).clamp(1, MAX_SUBDIVISIONS);
+        let projected_area = normal[2].abs() / 2.0 / (k * k) as f64;
+        let kf = k as f64;
+        let point = |u: f64, v: f64| {
+            let w = 1.0 - u - v;
+            [
+                w * a[0] + u * b[0] + v * c[0],
+                w * a[1] + u * b[1] + v * c[1],
+                w * a[2] + u * b[2] + v * c[2],
+            ]
+        };
+        for i in 0..k {
+            for j in 0..k - i {
+                let (i, j) = (i as f64, j as f64);
+                let mut samples = vec![point((i + 1.0 / 3.0) / kf, (j + 1.0 / 3.0) / kf)];
+                if i + j + 1.0 < kf {
+                    samples.push(point((i + 2.0 / 3.0) / kf, (j + 2.0 / 3.0) / kf));
+                }
+                for p in samples {
+                    let floor = columns.highest_below(mesh, p, face).unwrap_or(bed).max(bed);
+                    support_volume += projected_area * (p[2] - floor).max(0.0);
+                }
+            }
+        }
+    }
+
+    // mm2 -> cm2, mm3 -> cm3
+    estimate.overhang_area_cm2 = overhang_area / 100.0;
+    estimate.support_volume_cm3 = support_volume / 1000.0;
+    estimate
+}
+
+/// Uniform XY grid of faces for vertical ray queries.
+struct ColumnIndex {
+    origin: [f64; 2],
+    cell: f64,
+    dims: [usize; 2],
+    cells: Vec<Vec<usize>>,
+}
+
+impl ColumnIndex {
+    fn new(mesh: &Mesh) -> Self {
+        let bbox = mesh.bounding_box();
+        let [sx, sy, _] = bbox.size();
+        // Roughly one face per cell, capped to keep memory bounded
+        let per_axis = (mesh.faces.len() as f64).sqrt().ceil().clamp(1.0, 512.0);
+        let cell = (sx.max(sy) / per_axis).max(1e-6);
+        let dims = [(sx / cell) as usize + 1, (sy / cell) as usize + 1];
+        let mut index = ColumnIndex { origin: [bbox.min[0], bbox.min[1]], cell, dims, cells: vec![Vec::new(); dims[0] * dims[1]] };
+
+        for face in 0..mesh.faces.len() {
+            let [a, b, c] = mesh.triangle(face);
+            let (x0, y0) = index.cell_of(a[0].min(b[0]).min(c[0]), a[1].min(b[1]).min(c[1]));
+            let (x1, y1) = index.cell_of(a[0].max(b[0]).max(c[0]), a[1].max(b[1]).max(c[1]));
+            for x in x0..=x1 {
+                for y in y0..=y1 {
+                    index.cells[y * dims[0] + x].push(face);
+                }
+            }
+        }
+        index
+    }
+
+    fn cell_of(&self, x: f64, y: f64) -> (usize, usize) {
+        let cx = ((x - self.origin[0]) / self.cell).max(0.0) as usize;
+        let cy = ((y - self.origin[1]) / self.cell).max(0.0) as usize;
+        (cx.min(self.dims[0] - 1), cy.min(self.dims[1] - 1))
+    }
+
+    /// Height of the highest surface strictly below `p`, ignoring face `skip`.
+    fn highest_below(&self, mesh: &Mesh, p: [f64; 3], skip: usize) -> Option<f64> {
+        let (cx, cy) = self.cell_of(p[0], p[1]);
+        let mut best: Option<f64> = None;
+        for &face in &self.cells[cy * self.dims[0] + cx] {
+            if face == skip {
+                continue;
+            }
+            let [a, b, c] = mesh.triangle(face);
+            if let Some(z) = vertical_hit(a, b, c, p[0], p[1])
+                && z < p[2] - 1e-6
+                && best.is_none_or(|best| z > best)
+            {
+                best = Some(z);
+            }
+        }
+        best
+    }
+}
+
+/// Height at which the vertical line through (x, y) crosses the triangle, if it does.
+fn vertical_hit(a: [f64; 3], b: [f64; 3], c: [f64; 3], x: f64, y: f64) -> Option<f64> {
+    let det = (b[1] - c[1]) * (a[0] - c[0]) + (c[0] - b[0]) * (a[1] - c[1]);
+    if det.abs() < 1e-12 {
+        // Vertical face
+        return None;
+    }
+    let u = ((b[1] - c[1]) * (x - c[0]) + (c[0] - b[0]) * (y - c[1])) / det;
+    let v = ((c[1] - a[1]) * (x - c[0]) + (a[0] - c[0]) * (y - c[1])) / det;
+    let w = 1.0 - u - v;
+    if u < 0.0 || v < 0.0 || w < 0.0 {
+        return None;
+    }
+    Some(u * a[2] + v * b[2] + w * c[2])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::{add_box, cuboid};
+
+    #[test]
+    fn test_part_on_bed_needs_no_support() {
+        let estimate = estimate_supports(&cuboid([0.0; 3], [10.0; 3]), DEFAULT_OVERHANG_ANGLE_DEG);
+        assert_eq!(estimate.overhang_faces, 0);
+        assert_eq!(estimate.support_volume_cm3, 0.0);
+    }
+
+    #[test]
+    fn test_overhang_is_supported_from_part_below() {
+        // 20x20 slab floating at z 20..30 over a 10x10 pillar standing on the bed
+        let mut mesh = cuboid([5.0, 5.0, 0.0], [15.0, 15.0, 10.0]);
+        add_box(&mut mesh, [0.0, 0.0, 20.0], [20.0, 20.0, 30.0], false);
+        let estimate = estimate_supports(&mesh, DEFAULT_OVERHANG_ANGLE_DEG);
+
+        assert_eq!(estimate.overhang_faces, 2);
+        assert!((estimate.overhang_area_cm2 - 4.0).abs() < 1e-9);
+        // 300 mm2 dropping 20 mm to the bed plus 100 mm2 dropping 10 mm onto the pillar
+        let expected = (300.0 * 20.0 + 100.0 * 10.0) / 1000.0;
+        assert!((estimate.support_volume_cm3 - expected).abs() / expected < 0.05, "got {}", estimate.support_volume_cm3);
+    }
+
+    #[test]
+    fn test_overhang_angle_threshold() {
+        // Wedge whose sloped underside is 30 degrees from vertical
+        let t = 30f64.to_radians().tan() * 10.0;
+        let mesh = Mesh {
+            vertices: vec![
+                [0.0, 0.0, 0.0], [0.0, 10.0, 0.0], [t, 0.0, 10.0], [t, 10.0, 10.0],
+                [0.0, 0.0, 10.0], [0.0, 10.0, 10.0],
+            ],
+            faces: vec![
+                [0, 1, 3], [0, 3, 2], [4, 2, 3], [4, 3, 5],
+                [0, 4, 5], [0, 5, 1], [0, 2, 4], [1, 5, 3],
+            ],
+        };
+        assert_eq!(estimate_supports(&mesh, 45.0).overhang_faces, 0);
+        assert_eq!(estimate_supports(&mesh, 20.0).overhang_faces, 2);
+    }
+}
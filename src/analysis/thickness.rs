@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use super::bvh::{Bvh, RAY_EPSILON_MM};
+use super::vec3::{centroid, face_normal, length, scale, sub, Vec3};
+use super::Mesh;
+
+/// Thin spots closer together than this (mm) are reported once.
+const REGION_SPACING_MM: f64 = 2.0;
+const MAX_THIN_REGIONS: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThinRegion {
+    /// Face centroid where the thin wall was measured.
+    pub location: [f64; 3],
+    pub thickness_mm: f64,
+}
+
+/// Wall thickness measured by casting a ray inward from every face centroid.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WallThickness {
+    pub required_mm: f64,
+    pub min_mm: Option<f64>,
+    /// 5% of the surface (by area) is thinner than this.
+    pub p5_mm: Option<f64>,
+    pub median_mm: Option<f64>,
+    /// Surface area whose wall is thinner than `required_mm`.
+    pub thin_area_cm2: f64,
+    /// Thinnest spots below `required_mm`, thinnest first.
+    pub thin_regions: Vec<ThinRegion>,
+    pub is_thick_enough: bool,
+}
+
+/// Measures wall thickness and flags regions thinner than `required_mm`.
+pub fn analyze_wall_thickness(mesh: &Mesh, required_mm: f64) -> WallThickness {
+    let bvh = Bvh::new(mesh);
+
+    // (thickness, area, centroid) per face whose inward ray hits the far side
+    let mut samples: Vec<(f64, f64, Vec3)> = Vec::with_capacity(mesh.faces.len());
+    for face in 0..mesh.faces.len() {
+        let [a, b, c] = mesh.triangle(face);
+        let normal = face_normal(a, b, c);
+        let area2 = length(normal);
+        if area2 == 0.0 {
+            continue;
+        }
+        let inward = scale(normal, -1.0 / area2);
+        let origin = centroid(a, b, c);
+        if let Some(hit) = bvh.ray_hit(mesh, origin, inward, RAY_EPSILON_MM, Some(face)) {
+            samples.push((hit.distance, area2 / 2.0, origin));
+        }
+    }
+
+    let mut report = WallThickness { required_mm, is_thick_enough: true, ..Default::default() };
+    if samples.is_empty() {
+        return report;
+    }
+    samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let total_area: f64 = samples.iter().map(|s| s.1).sum();
+    let percentile = |fraction: f64| {
+        let mut covered = 0.0;
+        for &(thickness, area, _) in &samples {
+            covered += area;
+            if covered >= fraction * total_area {
+                return thickness;
+            }
+        }
+        samples[samples.len() - 1].0
+    };
+    report.min_mm = Some(samples[0].0);
+    report.p5_mm = Some(percentile(0.05));
+    report.median_mm = Some(percentile(0.5));
+
+    for &(thickness, area, location) in samples.iter().take_while(|s| s.0 < required_mm) {
+        report.thin_area_cm2 += area / 100.0;
+        let is_new = report
+            .thin_regions
+            .iter()
+            .all(|region| length(sub(region.location, location)) > REGION_SPACING_MM);
+        if is_new && report.thin_regions.len() < MAX_THIN_REGIONS {
+            report.thin_regions.push(ThinRegion { location, thickness_mm: thickness });
+        }
+    }
+    report.is_thick_enough = report.thin_regions.is_empty();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::cuboid;
+
+    #[test]
+    fn test_solid_cube_is_thick_enough() {
+        let report = analyze_wall_thickness(&cuboid([0.0; 3], [10.0, 10.0, 10.0]), 0.8);
+        assert_eq!(report.min_mm, Some(10.0));
+        assert!(report.is_thick_enough);
+        assert!(report.thin_regions.is_empty());
+    }
+
+    #[test]
+    fn test_thin_plate_is_flagged() {
+        // 0.5 mm plate: the two large faces measure 0.5, the edges 20 and 30
+        let report = analyze_wall_thickness(&cuboid([0.0; 3], [20.0, 30.0, 0.5]), 0.8);
+        assert_eq!(report.min_mm, Some(0.5));
+        assert_eq!(report.p5_mm, Some(0.5));
+        assert!(!report.is_thick_enough);
+        assert!((report.thin_area_cm2 - 12.0).abs() < 1e-9);
+        assert!(report.thin_regions.iter().all(|r| r.thickness_mm == 0.5));
+
+        assert!(analyze_wall_thickness(&cuboid([0.0; 3], [20.0, 30.0, 0.5]), 0.4).is_thick_enough);
+    }
+}
@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use super::error::{AnalysisError, MAX_TRIANGLES};
+use super::{Mesh, ModelFormat, ModelObject};
+
+const DEFAULT_MODEL_PATH: &str = "3D/3dmodel.model";
+const MODEL_RELATIONSHIP: &str = "http://schemas.microsoft.com/3dmanufacturing/2013/01/3dmodel";
+const MAX_MODEL_BYTES: u64 = 512 * 1024 * 1024; // refuse zip bombs
+const MAX_COMPONENT_DEPTH: usize = 16;
+
+/// Affine transform in 3MF order: `m00 m01 m02 m10 m11 m12 m20 m21 m22 m30 m31 m32`.
+/// Points are row vectors, so `x' = x*m00 + y*m10 + z*m20 + m30`.
+type Transform = [f64; 12];
+
+const IDENTITY: Transform = [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0];
+
+#[derive(Debug, Default)]
+struct Object {
+    name: Option<String>,
+    printable: bool,
+    vertices: Vec<[f64; 3]>,
+    triangles: Vec<[usize; 3]>,
+    components: Vec<(u32, Transform)>,
+}
+
+/// Reads a 3MF package and flattens its build plate into a single mesh in millimetres.
+///
+/// Every build item becomes one `ModelObject`, with the item transform (and any
+/// nested component transforms) applied and the `<model unit>` converted to mm.
+pub fn parse_3mf(data: &[u8]) -> Result<(Mesh, Vec<ModelObject>), AnalysisError> {
+    let syntax = |e: String| AnalysisError::syntax(ModelFormat::ThreeMf, e);
+    let mut archive = zip::ZipArchive::new(Cursor::new(data)).map_err(|e| syntax(format!("invalid 3MF package: {}", e)))?;
+
+    let model_path = read_entry(&mut archive, "_rels/.rels")
+        .ok()
+        .and_then(|rels| find_model_path(&rels))
+        .unwrap_or_else(|| DEFAULT_MODEL_PATH.to_string());
+    let model_xml = read_entry(&mut archive, &model_path).map_err(syntax)?;
+
+    parse_model_xml(&model_xml, MAX_TRIANGLES)
+}
+
+fn read_entry<R: Read + std::io::Seek>(archive: &mut zip::ZipArchive<R>, path: &str) -> Result<String, String> {
+    let entry = archive
+        .by_name(path)
+        .map_err(|_| format!("3MF package has no '{}'", path))?;
+    if entry.size() > MAX_MODEL_BYTES {
+        return Err(format!("'{}' is too large", path));
+    }
+    let mut content = String::with_capacity(entry.size() as usize);
+    entry
+        .take(MAX_MODEL_BYTES)
+        .read_to_string(&mut content)
+        .map_err(|e| format!("failed to read '{}': {}", path, e))?;
+    Ok(content)
+}
+
+fn find_model_path(rels: &str) -> Option<String> {
+    let mut reader = Reader::from_str(rels);
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"Relationship" => {
+                let attrs = attributes(&e);
+                if attrs.get("Type").map(String::as_str) == Some(MODEL_RELATIONSHIP) {
+                    return attrs.get("Target").map(|t| t.trim_start_matches('/').to_string());
+                }
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+    }
+}
+
+/// Objects, build items and unit of a model file, before flattening.
+struct Resources {
+    unit_scale: f64,
+    objects: HashMap<u32, Object>,
+    items: Vec<(u32, Transform)>,
+}
+
+/// Flattens the build plate, failing as soon as it grows past `max_triangles`.
+fn parse_model_xml(xml: &str, max_triangles: u64) -> Result<(Mesh, Vec<ModelObject>), AnalysisError> {
+    let syntax = |e: String| AnalysisError::syntax(ModelFormat::ThreeMf, e);
+    let Resources { unit_scale, objects, mut items } = read_resources(xml).map_err(syntax)?;
+
+    // A package without build items is malformed, but slicers still open it
+    // by placing every printable object once.
+    if items.is_empty() {
+        let mut ids: Vec<u32> = objects.iter().filter(|(_, o)| o.printable).map(|(id, _)| *id).collect();
+        ids.sort_unstable();
+        items = ids.into_iter().map(|id| (id, IDENTITY)).collect();
+    }
+
+    let mut mesh = Mesh::default();
+    let mut model_objects = Vec::new();
+    for (object_id, transform) in items {
+        let object = objects
+            .get(&object_id)
+            .ok_or_else(|| syntax(format!("build item references unknown object {}", object_id)))?;
+        if !object.printable {
+            continue;
+        }
+
+        let first_face = mesh.faces.len();
+        append_object(&objects, object_id, &transform, unit_scale, &mut mesh, 0, max_triangles)?;
+        model_objects.push(ModelObject {
+            name: object.name.clone().unwrap_or_else(|| format!("Object {}", object_id)),
+            first_face,
+            face_count: mesh.faces.len() - first_face,
+        });
+    }
+
+    Ok((mesh, model_objects))
+}
+
+fn read_resources(xml: &str) -> Result<Resources, String> {
+    let mut reader = Reader::from_str(xml);
+    let mut unit_scale = 1.0;
+    let mut objects: HashMap<u32, Object> = HashMap::new();
+    let mut current: Option<(u32, Object)> = None;
+    let mut items: Vec<(u32, Transform)> = Vec::new();
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("invalid 3MF model XML at byte {}: {}", reader.buffer_position(), e))?;
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let attrs = attributes(e);
+                match e.local_name().as_ref() {
+                    b"model" => {
+                        let unit = attrs.get("unit").map(String::as_str).unwrap_or("millimeter");
+                        unit_scale = unit_to_mm(unit).ok_or_else(|| format!("unsupported 3MF unit '{}'", unit))?;
+                    }
+                    b"object" => {
+                        let id = parse_id(&attrs, "id")?;
+                        let kind = attrs.get("type").map(String::as_str).unwrap_or("model");
+                        let object = Object {
+                            name: attrs.get("name").cloned(),
+                            printable: matches!(kind, "model" | "solidsupport"),
+                            ..Default::default()
+                        };
+                        if matches!(event, Event::Empty(_)) {
+                            objects.insert(id, object);
+                        } else {
+                            current = Some((id, object));
+                        }
+                    }
+                    b"vertex" => {
+                        let (_, object) = current.as_mut().ok_or("vertex outside of an object")?;
+                        object.vertices.push([
+                            parse_f64(&attrs, "x")?,
+                            parse_f64(&attrs, "y")?,
+                            parse_f64(&attrs, "z")?,
+                        ]);
+                    }
+                    b"triangle" => {
+                        let (_, object) = current.as_mut().ok_or("triangle outside of an object")?;
+                        object.triangles.push([
+                            parse_id(&attrs, "v1")? as usize,
+                            parse_id(&attrs, "v2")? as usize,
+                            parse_id(&attrs, "v3")? as usize,
+                        ]);
+                    }
+                    b"component" => {
+                        let (_, object) = current.as_mut().ok_or("component outside of an object")?;
+                        object.components.push((parse_id(&attrs, "objectid")?, parse_transform(&attrs)?));
+                    }
+                    b"item" => {
+                        items.push((parse_id(&attrs, "objectid")?, parse_transform(&attrs)?));
+                    }
+                    _ => {}
+                }
+            }
+            Event::End(ref e) if e.local_name().as_ref() == b"object" => {
+                if let Some((id, object)) = current.take() {
+                    objects.insert(id, object);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    for (id, object) in &objects {
+        if object.triangles.iter().flatten().any(|&v| v >= object.vertices.len()) {
+            return Err(format!("object {} references a vertex that does not exist", id));
+        }
+    }
+
+    Ok(Resources { unit_scale, objects, items })
+}
+
+fn append_object(
+    objects: &HashMap<u32, Object>,
+    object_id: u32,
+    transform: &Transform,
+    unit_scale: f64,
+    mesh: &mut Mesh,
+    depth: usize,
+    max_triangles: u64,
+) -> Result<(), AnalysisError> {
+    if depth > MAX_COMPONENT_DEPTH {
+        return Err(AnalysisError::syntax(ModelFormat::ThreeMf, "3MF components are nested too deeply (cyclic reference?)"));
+    }
+    let object = objects
+        .get(&object_id)
+        .ok_or_else(|| AnalysisError::syntax(ModelFormat::ThreeMf, format!("component references unknown object {}", object_id)))?;
+    // Components can reuse an object many times over, so a small file can
+    // expand to any size; stop before allocating past the limit
+    let triangles = (mesh.faces.len() + object.triangles.len()) as u64;
+    if triangles > max_triangles {
+        return Err(AnalysisError::TooManyTriangles { triangles, limit: max_triangles });
+    }
+
+    let offset = mesh.vertices.len();
+    for v in &object.vertices {
+        let p = apply_transform(transform, *v);
+        mesh.vertices.push([p[0] * unit_scale, p[1] * unit_scale, p[2] * unit_scale]);
+    }
+    // A mirroring transform turns the surface inside out; restore outward winding
+    let mirrored = determinant(transform) < 0.0;
+    for t in &object.triangles {
+        let face = [t[0] + offset, t[1] + offset, t[2] + offset];
+        mesh.faces.push(if mirrored { [face[0], face[2], face[1]] } else { face });
+    }
+
+    for (child_id, child_transform) in &object.components {
+        let combined = compose(child_transform, transform);
+        append_object(objects, *child_id, &combined, unit_scale, mesh, depth + 1, max_triangles)?;
+    }
+
+    Ok(())
+}
+
+fn unit_to_mm(unit: &str) -> Option<f64> {
+    match unit {
+        "micron" => Some(0.001),
+        "millimeter" => Some(1.0),
+        "centimeter" => Some(10.0),
+        "inch" => Some(25.4),
+        "foot" => Some(304.8),
+        "meter" => Some(1000.0),
+        _ => None,
+    }
+}
+
+fn apply_transform(m: &Transform, p: [f64; 3]) -> [f64; 3] {
+    [
+        p[0] * m[0] + p[1] * m[3] + p[2] * m[6] + m[9],
+        p[0] * m[1] + p[1] * m[4] + p[2] * m[7] + m[10],
+        p[0] * m[2] + p[1] * m[5] + p[2] * m[8] + m[11],
+    ]
+}
+
+/// Returns the transform equivalent to applying `first`, then `second`.
+fn compose(first: &Transform, second: &Transform) -> Transform {
+    let mut out = [0.0; 12];
+    for row in 0..3 {
+        for col in 0..3 {
+            out[row * 3 + col] = (0..3).map(|k| first[row * 3 + k] * second[k * 3 + col]).sum();
+        }
+    }
+    let translation = apply_transform(second, [first[9], first[10], first[11]]);
+    out[9..12].copy_from_slice(&translation);
+    out
+}
+
+fn determinant(m: &Transform) -> f64 {
+    m[0] * (m[4] * m[8] - m[5] * m[7]) - m[1] * (m[3] * m[8] - m[5] * m[6]) + m[2] * (m[3] * m[7] - m[4] * m[6])
+}
+
+fn attributes(e: &BytesStart) -> HashMap<String, String> {
+    e.attributes()
+        .flatten()
+        .filter_map(|attr| {
+            let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_string();
+            attr.unescape_value().ok().map(|value| (key, value.to_string()))
+        })
+        .collect()
+}
+
+fn parse_id(attrs: &HashMap<String, String>, key: &str) -> Result<u32, String> {
+    let value = attrs.get(key).ok_or_else(|| format!("missing '{}' attribute", key))?;
+    value.trim().parse().map_err(|_| format!("invalid '{}' attribute '{}'", key, value))
+}
+
+fn parse_f64(attrs: &HashMap<String, String>, key: &str) -> Result<f64, String> {
+    let value = attrs.get(key).ok_or_else(|| format!("missing '{}' attribute", key))?;
+    value.trim().parse().map_err(|_| format!("invalid '{}' attribute '{}'", key, value))
+}
+
+fn parse_transform(attrs: &HashMap<String, String>) -> Result<Transform, String> {
+    let Some(value) = attrs.get("transform") else {
+        return Ok(IDENTITY);
+    };
+    let numbers = value
+        .split_whitespace()
+        .map(|n| n.parse::<f64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| format!("invalid transform '{}'", value))?;
+    numbers.try_into().map_err(|_| format!("transform must have 12 values: '{}'", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::analyze_mesh;
+    use std::io::Write;
+
+    const CUBE_OBJECT: &str = r#"<object id="1" name="Cube" type="model">
+      <mesh>
+        <vertices>
+          <vertex x="0" y="0" z="0"/><vertex x="1" y="0" z="0"/>
+          <vertex x="1" y="1" z="0"/><vertex x="0" y="1" z="0"/>
+          <vertex x="0" y="0" z="1"/><vertex x="1" y="0" z="1"/>
+          <vertex x="1" y="1" z="1"/><vertex x="0" y="1" z="1"/>
+        </vertices>
+        <triangles>
+          <triangle v1="0" v2="2" v3="1"/><triangle v1="0" v2="3" v3="2"/>
+          <triangle v1="4" v2="5" v3="6"/><triangle v1="4" v2="6" v3="7"/>
+          <triangle v1="0" v2="1" v3="5"/><triangle v1="0" v2="5" v3="4"/>
+          <triangle v1="1" v2="2" v3="6"/><triangle v1="1" v2="6" v3="5"/>
+          <triangle v1="2" v2="3" v3="7"/><triangle v1="2" v2="7" v3="6"/>
+          <triangle v1="3" v2="0" v3="4"/><triangle v1="3" v2="4" v3="7"/>
+        </triangles>
+      </mesh>
+    </object>"#;
+
+    fn package(model_xml: &str) -> Vec<u8> {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut writer = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            writer.start_file("3D/3dmodel.model", options).unwrap();
+            writer.write_all(model_xml.as_bytes()).unwrap();
+            writer.finish().unwrap();
+        }
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_3mf_units_and_build_items() {
+        // 1cm cube placed twice, once translated and once scaled 2x and mirrored in X
+        let xml = format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <model unit="centimeter" xmlns="http://schemas.microsoft.com/3dmanufacturing/core/2015/02">
+              <resources>{}</resources>
+              <build>
+                <item objectid="1" transform="1 0 0 0 1 0 0 0 1 5 0 0"/>
+                <item objectid="1" transform="-2 0 0 0 2 0 0 0 2 0 0 0"/>
+              </build>
+            </model>"#,
+            CUBE_OBJECT
+        );
+
+        let (mesh, objects) = parse_3mf(&package(&xml)).unwrap();
+        assert_eq!(objects.len(), 2);
+        assert_eq!(objects[0].name, "Cube");
+        assert_eq!(objects[1].first_face, 12);
+
+        // 1 cm3 + 8 cm3; mirroring must not cancel out the volume
+        let analysis = analyze_mesh(&mesh);
+        assert!((analysis.volume_cm3 - 9.0).abs() < 1e-9, "got {}", analysis.volume_cm3);
+        assert!(mesh.vertices.iter().any(|v| (v[0] - 60.0).abs() < 1e-9));
+    }
+
+    #[test]
+    fn test_3mf_components() {
+        let xml = format!(
+            r#"<model unit="millimeter">
+              <resources>{}
+                <object id="2" name="Assembly">
+                  <components>
+                    <component objectid="1" transform="1 0 0 0 1 0 0 0 1 0 0 0"/>
+                    <component objectid="1" transform="1 0 0 0 1 0 0 0 1 0 0 3"/>
+                  </components>
+                </object>
+              </resources>
+              <build><item objectid="2" transform="10 0 0 0 10 0 0 0 10 0 0 0"/></build>
+            </model>"#,
+            CUBE_OBJECT
+        );
+
+        let (mesh, objects) = parse_3mf(&package(&xml)).unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].name, "Assembly");
+        assert_eq!(objects[0].face_count, 24);
+        assert!((analyze_mesh(&mesh).volume_cm3 - 2.0).abs() < 1e-9);
+        // Component offset is scaled by the item transform: z = 3 * 10 + 10
+        let max_z = mesh.vertices.iter().map(|v| v[2]).fold(f64::MIN, f64::max);
+        assert!((max_z - 40.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_3mf_component_fan_out_is_limited() {
+        // Each level uses the one below it twice: 2^16 cubes from a few lines
+        let mut resources = CUBE_OBJECT.to_string();
+        for id in 2..=17 {
+            resources.push_str(&format!(
+                r#"<object id="{id}"><components><component objectid="{child}"/><component objectid="{child}"/></components></object>"#,
+                child = id - 1,
+            ));
+        }
+        let xml = format!(r#"<model unit="millimeter"><resources>{}</resources><build><item objectid="17"/></build></model>"#, resources);
+
+        assert_eq!(
+            parse_model_xml(&xml, 1_000).unwrap_err(),
+            AnalysisError::TooManyTriangles { triangles: 1_008, limit: 1_000 },
+        );
+        // Under the real limit the same tree expands in full
+        let (mesh, _) = parse_model_xml(&xml, MAX_TRIANGLES).unwrap();
+        assert_eq!(mesh.faces.len(), 12 << 16);
+    }
+
+    #[test]
+    fn test_3mf_rejects_unknown_unit() {
+        let xml = format!(r#"<model unit="furlong"><resources>{}</resources></model>"#, CUBE_OBJECT);
+        assert!(parse_3mf(&package(&xml)).is_err());
+    }
+}
@@ -0,0 +1,216 @@
+use std::io::Write;
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use super::vec3::{dot, face_normal, normalize, Vec3};
+use super::Mesh;
+
+/// Edge length (pixels) of stored thumbnails.
+pub const THUMBNAIL_SIZE: usize = 256;
+/// Rendered at this multiple of the output size and averaged down, to smooth edges.
+const SUPERSAMPLE: usize = 2;
+/// Fraction of the image left empty on each side.
+const MARGIN: f64 = 0.08;
+const BASE_COLOR: [f64; 3] = [74.0, 128.0, 196.0];
+const AMBIENT: f64 = 0.3;
+
+/// Renders the mesh as a shaded RGBA PNG on a transparent background, seen
+/// from the front-right-top corner (isometric) and scaled to fill the frame.
+pub fn render_thumbnail(mesh: &Mesh, size: usize) -> Vec<u8> {
+    let scaled = size * SUPERSAMPLE;
+    let pixels = rasterize(mesh, scaled);
+
+    let mut rgba = vec![0u8; size * size * 4];
+    for y in 0..size {
+        for x in 0..size {
+            let mut sum = [0.0; 4];
+            for sy in 0..SUPERSAMPLE {
+                for sx in 0..SUPERSAMPLE {
+                    if let Some(color) = pixels[(y * SUPERSAMPLE + sy) * scaled + x * SUPERSAMPLE + sx] {
+                        for channel in 0..3 {
+                            sum[channel] += color[channel];
+                        }
+                        sum[3] += 255.0;
+                    }
+                }
+            }
+            let samples = (SUPERSAMPLE * SUPERSAMPLE) as f64;
+            let coverage = sum[3] / samples;
+            let offset = (y * size + x) * 4;
+            if coverage > 0.0 {
+                // Colour averaged over the covered samples only; coverage goes to alpha
+                let covered = sum[3] / 255.0;
+                for channel in 0..3 {
+                    rgba[offset + channel] = (sum[channel] / covered).round() as u8;
+                }
+                rgba[offset + 3] = coverage.round() as u8;
+            }
+        }
+    }
+    encode_png(size, size, &rgba)
+}
+
+/// Shaded colour per pixel (row-major, top row first), `None` where the mesh doesn't cover.
+fn rasterize(mesh: &Mesh, size: usize) -> Vec<Option<[f64; 3]>> {
+    let mut pixels = vec![None; size * size];
+    if mesh.faces.is_empty() {
+        return pixels;
+    }
+
+    // Camera looks down from +X, -Y (the front), +Z
+    let toward_camera = normalize([1.0, -1.0, 1.0]);
+    let right = normalize([1.0, 1.0, 0.0]);
+    let up = normalize([-1.0, 1.0, 2.0]);
+    let light = normalize([0.3, -0.5, 1.0]);
+
+    let projected: Vec<Vec3> = mesh
+        .vertices
+        .iter()
+        .map(|&v| [dot(v, right), dot(v, up), dot(v, toward_camera)])
+        .collect();
+    let mut min = [f64::INFINITY; 2];
+    let mut max = [f64::NEG_INFINITY; 2];
+    for &vertex in mesh.faces.iter().flatten() {
+        for axis in 0..2 {
+            min[axis] = min[axis].min(projected[vertex][axis]);
+            max[axis] = max[axis].max(projected[vertex][axis]);
+        }
+    }
+    let extent = (max[0] - min[0]).max(max[1] - min[1]);
+    let pixels_per_unit = if extent > 0.0 { size as f64 * (1.0 - 2.0 * MARGIN) / extent } else { 1.0 };
+    let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+    let half = size as f64 / 2.0;
+    let to_screen = |p: Vec3| -> Vec3 {
+        [half + (p[0] - center[0]) * pixels_per_unit, half - (p[1] - center[1]) * pixels_per_unit, p[2]]
+    };
+
+    let mut depth = vec![f64::NEG_INFINITY; size * size];
+    for (face, f) in mesh.faces.iter().enumerate() {
+        let [a, b, c] = mesh.triangle(face);
+        let normal = normalize(face_normal(a, b, c));
+        // Lit from both sides so inverted faces don't render black
+        let shade = AMBIENT + (1.0 - AMBIENT) * dot(normal, light).abs();
+        let color = BASE_COLOR.map(|channel| channel * shade);
+
+        let [p0, p1, p2] = f.map(|v| to_screen(projected[v]));
+        let area = edge(p0, p1, p2);
+        if area.abs() < 1e-12 {
+            continue;
+        }
+        let x_start = p0[0].min(p1[0]).min(p2[0]).floor().max(0.0) as usize;
+        let x_end = (p0[0].max(p1[0]).max(p2[0]).ceil().max(0.0) as usize).min(size);
+        let y_start = p0[1].min(p1[1]).min(p2[1]).floor().max(0.0) as usize;
+        let y_end = (p0[1].max(p1[1]).max(p2[1]).ceil().max(0.0) as usize).min(size);
+
+        for y in y_start..y_end {
+            for x in x_start..x_end {
+                let sample = [x as f64 + 0.5, y as f64 + 0.5, 0.0];
+                let w0 = edge(p1, p2, sample) / area;
+                let w1 = edge(p2, p0, sample) / area;
+                let w2 = edge(p0, p1, sample) / area;
+                if w0 < 0.0 || w1 < 0.0 || w2 < 0.0 {
+                    continue;
+                }
+                let z = w0 * p0[2] + w1 * p1[2] + w2 * p2[2];
+                let index = y * size + x;
+                if z > depth[index] {
+                    depth[index] = z;
+                    pixels[index] = Some(color);
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Twice the signed area of the screen-space triangle `a`, `b`, `p`.
+fn edge(a: Vec3, b: Vec3, p: Vec3) -> f64 {
+    (b[0] - a[0]) * (p[1] - a[1]) - (b[1] - a[1]) * (p[0] - a[0])
+}
+
+/// Minimal PNG encoder for 8-bit RGBA images.
+fn encode_png(width: usize, height: usize, rgba: &[u8]) -> Vec<u8> {
+    let mut raw = Vec::with_capacity(height * (width * 4 + 1));
+    for row in rgba.chunks(width * 4) {
+        raw.push(0); // no filter
+        raw.extend_from_slice(row);
+    }
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&raw).expect("writing to a Vec cannot fail");
+    let compressed = encoder.finish().expect("writing to a Vec cannot fail");
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    header.extend_from_slice(&[8, 6, 0, 0, 0]); // 8-bit RGBA, deflate, no interlace
+
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &compressed);
+    write_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(kind);
+    crc.update(data);
+    png.extend_from_slice(&crc.finalize().to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use flate2::read::ZlibDecoder;
+    use crate::analysis::test_util::cube;
+
+    /// Decodes a PNG written by `encode_png` back into RGBA rows.
+    fn decode(png: &[u8]) -> (usize, usize, Vec<u8>) {
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        let width = u32::from_be_bytes(png[16..20].try_into().unwrap()) as usize;
+        let height = u32::from_be_bytes(png[20..24].try_into().unwrap()) as usize;
+        let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+        assert_eq!(&png[37..41], b"IDAT");
+        let mut raw = Vec::new();
+        ZlibDecoder::new(&png[41..41 + idat_len]).read_to_end(&mut raw).unwrap();
+        let rgba = raw.chunks(width * 4 + 1).flat_map(|row| row[1..].to_vec()).collect();
+        (width, height, rgba)
+    }
+
+    #[test]
+    fn test_cube_fills_the_frame() {
+        let (width, height, rgba) = decode(&render_thumbnail(&cube(10.0), 64));
+        assert_eq!((width, height), (64, 64));
+        let alpha = |x: usize, y: usize| rgba[(y * width + x) * 4 + 3];
+        // Centre is covered, corners are transparent
+        assert_eq!(alpha(32, 32), 255);
+        assert_eq!(alpha(0, 0), 0);
+        assert_eq!(alpha(63, 63), 0);
+
+        // Same picture whatever the model's size or position
+        let moved = cube(1000.0).scaled(0.5);
+        let moved = Mesh { vertices: moved.vertices.iter().map(|v| [v[0] + 50.0, v[1], v[2]]).collect(), faces: moved.faces };
+        assert_eq!(decode(&render_thumbnail(&moved, 64)).2, rgba);
+    }
+
+    #[test]
+    fn test_visible_sides_are_shaded_differently() {
+        let (width, _, rgba) = decode(&render_thumbnail(&cube(10.0), 128));
+        let pixel = |x: usize, y: usize| rgba[(y * width + x) * 4..(y * width + x) * 4 + 3].to_vec();
+        // Top face near the upper middle, front and right faces lower down on either side
+        let top = pixel(64, 30);
+        let front = pixel(40, 80);
+        let right = pixel(88, 80);
+        assert_ne!(top, front);
+        assert_ne!(front, right);
+    }
+
+    #[test]
+    fn test_empty_mesh_is_transparent() {
+        let (_, _, rgba) = decode(&render_thumbnail(&Mesh::default(), 16));
+        assert!(rgba.iter().all(|&b| b == 0));
+    }
+}
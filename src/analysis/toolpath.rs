@@ -0,0 +1,237 @@
+use serde::{Deserialize, Serialize};
+use super::slicer::{Contour, SlicedModel};
+
+/// Direction changes sharper than this (degrees) force the head to slow down.
+const SHARP_CORNER_DEG: f64 = 60.0;
+/// Z move, retraction and cooling overhead per layer (seconds).
+const LAYER_CHANGE_S: f64 = 0.5;
+/// Thinnest layer we offer on FDM machines.
+pub const MIN_LAYER_HEIGHT_MM: f64 = 0.05;
+
+/// FDM machine settings used for the estimate (see `printers`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MachineProfile {
+    pub nozzle_diameter_mm: f64,
+    pub filament_diameter_mm: f64,
+    pub perimeter_speed_mm_s: f64,
+    pub infill_speed_mm_s: f64,
+    pub travel_speed_mm_s: f64,
+    pub acceleration_mm_s2: f64,
+    pub perimeters: u32,
+    /// Solid layers printed at the top and bottom of every region.
+    pub solid_layers: u32,
+}
+
+impl Default for MachineProfile {
+    fn default() -> Self {
+        Self {
+            nozzle_diameter_mm: 0.4,
+            filament_diameter_mm: 1.75,
+            perimeter_speed_mm_s: 45.0,
+            infill_speed_mm_s: 80.0,
+            travel_speed_mm_s: 150.0,
+            acceleration_mm_s2: 1000.0,
+            perimeters: 2,
+            solid_layers: 4,
+        }
+    }
+}
+
+impl MachineProfile {
+    /// Extrusion width, slightly wider than the nozzle as most slicers default to.
+    pub fn line_width_mm(&self) -> f64 {
+        self.nozzle_diameter_mm * 1.125
+    }
+
+    /// Thickest layer the nozzle can lay down reliably.
+    pub fn max_layer_height_mm(&self) -> f64 {
+        self.nozzle_diameter_mm * 0.8
+    }
+
+    /// Time (s) for a straight move with trapezoidal acceleration from and to rest.
+    fn move_time(&self, length: f64, speed: f64) -> f64 {
+        let accel = self.acceleration_mm_s2;
+        if length <= 0.0 {
+            0.0
+        } else if length >= speed * speed / accel {
+            length / speed + speed / accel
+        } else {
+            2.0 * (length / accel).sqrt()
+        }
+    }
+}
+
+/// Print time and material for an FDM job.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PrintEstimate {
+    pub layer_count: usize,
+    pub layer_height_mm: f64,
+    pub infill_percentage: f64,
+    pub perimeter_length_mm: f64,
+    pub infill_length_mm: f64,
+    pub travel_length_mm: f64,
+    pub filament_length_mm: f64,
+    pub filament_volume_cm3: f64,
+    pub print_time_s: f64,
+}
+
+impl PrintEstimate {
+    pub fn print_time_hours(&self) -> f64 {
+        self.print_time_s / 3600.0
+    }
+}
+
+/// Approximates perimeters, top/bottom skins and sparse infill for every layer
+/// and times them on the given machine.
+pub fn estimate_print(sliced: &SlicedModel, profile: &MachineProfile, infill_percentage: f64) -> PrintEstimate {
+    let width = profile.line_width_mm();
+    let height = sliced.layer_height_mm;
+    let density = (infill_percentage / 100.0).clamp(0.0, 1.0);
+    let solid = profile.solid_layers as usize;
+    let areas: Vec<f64> = sliced.layers.iter().map(|layer| layer.area_mm2).collect();
+
+    let mut estimate = PrintEstimate {
+        layer_count: sliced.layers.len(),
+        layer_height_mm: height,
+        infill_percentage,
+        ..Default::default()
+    };
+
+    for (index, layer) in sliced.layers.iter().enumerate() {
+        if layer.area_mm2 <= 0.0 {
+            continue;
+        }
+        let contours: Vec<&Contour> = layer.contours.iter().filter(|c| c.closed).collect();
+
+        // Perimeters, capped by the area they can fill on thin features
+        let loop_length: f64 = contours.iter().map(|c| polygon_length(&c.points)).sum();
+        let perimeter_length = (loop_length * profile.perimeters as f64).min(layer.area_mm2 / width);
+        let perimeter_scale = if loop_length > 0.0 { perimeter_length / (loop_length * profile.perimeters as f64) } else { 0.0 };
+        let perimeter_time: f64 = contours
+            .iter()
+            .map(|c| corner_runs(&c.points).iter().map(|&run| profile.move_time(run, profile.perimeter_speed_mm_s)).sum::<f64>())
+            .sum::<f64>()
+            * profile.perimeters as f64
+            * perimeter_scale;
+
+        // Whatever is not covered by the layers above and below within `solid` layers is skin
+        let inner = (layer.area_mm2 - perimeter_length * width).max(0.0);
+        let covered = if index < solid || index + solid >= areas.len() {
+            0.0
+        } else {
+            areas[index - solid..=index + solid].iter().copied().fold(f64::INFINITY, f64::min)
+        };
+        let skin = (layer.area_mm2 - covered).clamp(0.0, inner);
+        let infill_length = skin / width + (inner - skin) * density / width;
+
+        // Infill as back-and-forth lines across the region
+        let line = inner.sqrt().max(width);
+        let infill_time = infill_length / line * profile.move_time(line, profile.infill_speed_mm_s);
+
+        // Travel between islands, returning to the first one
+        let centroids: Vec<[f64; 2]> = contours.iter().filter(|c| c.area_mm2 > 0.0).map(|c| polygon_centroid(&c.points)).collect();
+        let hops: Vec<f64> = if centroids.len() > 1 {
+            (0..centroids.len()).map(|i| distance(centroids[i], centroids[(i + 1) % centroids.len()])).collect()
+        } else {
+            Vec::new()
+        };
+        let travel_time: f64 = hops.iter().map(|&hop| profile.move_time(hop, profile.travel_speed_mm_s)).sum();
+
+        estimate.perimeter_length_mm += perimeter_length;
+        estimate.infill_length_mm += infill_length;
+        estimate.travel_length_mm += hops.iter().sum::<f64>();
+        estimate.print_time_s += perimeter_time + infill_time + travel_time + LAYER_CHANGE_S;
+    }
+
+    let extruded_mm3 = (estimate.perimeter_length_mm + estimate.infill_length_mm) * width * height;
+    let filament_area = std::f64::consts::PI * (profile.filament_diameter_mm / 2.0).powi(2);
+    estimate.filament_volume_cm3 = extruded_mm3 / 1000.0;
+    estimate.filament_length_mm = extruded_mm3 / filament_area;
+    estimate
+}
+
+fn polygon_length(points: &[[f64; 2]]) -> f64 {
+    (0..points.len())
+        .map(|i| distance(points[i], points[(i + 1) % points.len()]))
+        .sum()
+}
+
+/// Lengths of the stretches of a closed polygon between sharp corners.
+fn corner_runs(points: &[[f64; 2]]) -> Vec<f64> {
+    let n = points.len();
+    let limit = SHARP_CORNER_DEG.to_radians().cos();
+    let mut runs = Vec::new();
+    let mut current = 0.0;
+    for i in 0..n {
+        let (a, b, c) = (points[i], points[(i + 1) % n], points[(i + 2) % n]);
+        current += distance(a, b);
+        let (u, v) = ([b[0] - a[0], b[1] - a[1]], [c[0] - b[0], c[1] - b[1]]);
+        let norms = distance(a, b) * distance(b, c);
+        if norms > 0.0 && (u[0] * v[0] + u[1] * v[1]) / norms < limit {
+            runs.push(current);
+            current = 0.0;
+        }
+    }
+    if current > 0.0 {
+        runs.push(current);
+    }
+    runs
+}
+
+fn polygon_centroid(points: &[[f64; 2]]) -> [f64; 2] {
+    let n = points.len().max(1) as f64;
+    let sum = points.iter().fold([0.0, 0.0], |acc, p| [acc[0] + p[0], acc[1] + p[1]]);
+    [sum[0] / n, sum[1] / n]
+}
+
+fn distance(a: [f64; 2], b: [f64; 2]) -> f64 {
+    ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::slicer::Layer;
+
+    /// `count` layers of a `size` x `size` square.
+    fn square_column(size: f64, count: usize, layer_height: f64) -> SlicedModel {
+        let points = vec![[0.0, 0.0], [size, 0.0], [size, size], [0.0, size]];
+        let layer = |i: usize| Layer {
+            z: (i as f64 + 0.5) * layer_height,
+            contours: vec![Contour { points: points.clone(), closed: true, area_mm2: size * size }],
+            area_mm2: size * size,
+        };
+        SlicedModel { layer_height_mm: layer_height, layers: (0..count).map(layer).collect() }
+    }
+
+    #[test]
+    fn test_move_time_accelerates() {
+        let profile = MachineProfile { acceleration_mm_s2: 1000.0, ..Default::default() };
+        // Long move: cruise plus one accel/decel ramp
+        assert!((profile.move_time(100.0, 50.0) - (2.0 + 0.05)).abs() < 1e-12);
+        // Short move never reaches speed
+        assert!((profile.move_time(1.0, 50.0) - 2.0 * (0.001f64).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_solid_cube_uses_all_material_at_full_infill() {
+        // 20 mm cube at 0.2 mm: extruded volume should match the part at 100% infill
+        let profile = MachineProfile::default();
+        let full = estimate_print(&square_column(20.0, 100, 0.2), &profile, 100.0);
+        assert!((full.filament_volume_cm3 - 8.0).abs() < 0.01, "got {}", full.filament_volume_cm3);
+
+        let sparse = estimate_print(&square_column(20.0, 100, 0.2), &profile, 20.0);
+        assert!(sparse.filament_volume_cm3 < full.filament_volume_cm3);
+        assert!(sparse.print_time_s < full.print_time_s);
+        assert!(sparse.perimeter_length_mm == full.perimeter_length_mm);
+    }
+
+    #[test]
+    fn test_thinner_layers_take_longer() {
+        let profile = MachineProfile::default();
+        let coarse = estimate_print(&square_column(20.0, 50, 0.3), &profile, 20.0);
+        let fine = estimate_print(&square_column(20.0, 150, 0.1), &profile, 20.0);
+        assert!(fine.print_time_s > 2.0 * coarse.print_time_s);
+        assert_eq!(coarse.layer_count, 50);
+    }
+}
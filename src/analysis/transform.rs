@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use super::vec3::{add, cross, dot, length, normalize, Vec3};
+use super::Mesh;
+
+/// Refuse transforms with more steps than this.
+pub const MAX_STEPS: usize = 32;
+
+/// One step of a transform. Steps apply in order; scaling, rotation and
+/// mirroring act about the origin, so finish with `CENTER` to put the part
+/// back on the bed.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransformStep {
+    /// Positive scale factor along X, Y and Z.
+    Scale { factors: Vec3 },
+    /// Right-handed rotation (degrees) about an axis through the origin.
+    Rotate { axis: Vec3, angle_deg: f64 },
+    /// Reflection across the plane through the origin with this normal.
+    Mirror { normal: Vec3 },
+    Translate { offset: Vec3 },
+    /// Moves the part so its bounding box is centred on X and Y and rests on Z = 0.
+    Center,
+}
+
+/// `v' = linear · v + translation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Affine {
+    linear: [[f64; 3]; 3],
+    translation: Vec3,
+}
+
+impl Affine {
+    const IDENTITY: Affine = Affine { linear: [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]], translation: [0.0; 3] };
+
+    fn linear(linear: [[f64; 3]; 3]) -> Self {
+        Affine { linear, translation: [0.0; 3] }
+    }
+
+    fn translation(translation: Vec3) -> Self {
+        Affine { translation, ..Affine::IDENTITY }
+    }
+
+    fn apply(&self, v: Vec3) -> Vec3 {
+        add(self.linear.map(|row| dot(row, v)), self.translation)
+    }
+
+    /// `self` applied after `first`.
+    fn after(&self, first: &Affine) -> Affine {
+        let mut linear = [[0.0; 3]; 3];
+        for (i, row) in linear.iter_mut().enumerate() {
+            for (j, entry) in row.iter_mut().enumerate() {
+                *entry = (0..3).map(|k| self.linear[i][k] * first.linear[k][j]).sum();
+            }
+        }
+        Affine { linear, translation: self.apply(first.translation) }
+    }
+
+    fn determinant(&self) -> f64 {
+        let [a, b, c] = self.linear;
+        dot(a, cross(b, c))
+    }
+
+    /// Row-major 4x4 homogeneous matrix.
+    fn matrix(&self) -> [[f64; 4]; 4] {
+        let row = |i: usize| [self.linear[i][0], self.linear[i][1], self.linear[i][2], self.translation[i]];
+        [row(0), row(1), row(2), [0.0, 0.0, 0.0, 1.0]]
+    }
+}
+
+/// Applies the steps to the mesh. Returns the transformed copy and the
+/// combined transform as a row-major 4x4 matrix, so the viewer can move its
+/// preview to match. Faces are rewound after an odd number of mirrors so
+/// normals keep pointing out.
+pub fn transform_mesh(mesh: &Mesh, steps: &[TransformStep]) -> Result<(Mesh, [[f64; 4]; 4]), String> {
+    if steps.is_empty() {
+        return Err("Transform needs at least one step".to_string());
+    }
+    if steps.len() > MAX_STEPS {
+        return Err(format!("Transform has {} steps (limit {})", steps.len(), MAX_STEPS));
+    }
+    let mut total = Affine::IDENTITY;
+    for step in steps {
+        let affine = match *step {
+            TransformStep::Scale { factors } => {
+                if !factors.iter().all(|f| f.is_finite() && *f > 0.0) {
+                    return Err("Scale factors must be positive; use MIRROR to flip".to_string());
+                }
+                Affine::linear([[factors[0], 0.0, 0.0], [0.0, factors[1], 0.0], [0.0, 0.0, factors[2]]])
+            }
+            TransformStep::Rotate { axis, angle_deg } => {
+                if !angle_deg.is_finite() {
+                    return Err("Rotation angle must be finite".to_string());
+                }
+                Affine::linear(rotation(unit(axis, "Rotation axis")?, angle_deg))
+            }
+            TransformStep::Mirror { normal } => {
+                let n = unit(normal, "Mirror normal")?;
+                let mut linear = [[0.0; 3]; 3];
+                for (i, row) in linear.iter_mut().enumerate() {
+                    for (j, entry) in row.iter_mut().enumerate() {
+                        *entry = if i == j { 1.0 } else { 0.0 } - 2.0 * n[i] * n[j];
+                    }
+                }
+                Affine::linear(linear)
+            }
+            TransformStep::Translate { offset } => {
+                if !offset.iter().all(|c| c.is_finite()) {
+                    return Err("Translation must be finite".to_string());
+                }
+                Affine::translation(offset)
+            }
+            TransformStep::Center => {
+                let moved = Mesh { vertices: mesh.vertices.iter().map(|&v| total.apply(v)).collect(), faces: mesh.faces.clone() };
+                let bbox = moved.bounding_box();
+                Affine::translation([-(bbox.min[0] + bbox.max[0]) / 2.0, -(bbox.min[1] + bbox.max[1]) / 2.0, -bbox.min[2]])
+            }
+        };
+        total = affine.after(&total);
+    }
+
+    let vertices = mesh.vertices.iter().map(|&v| total.apply(v)).collect();
+    let faces = if total.determinant() < 0.0 {
+        mesh.faces.iter().map(|&[a, b, c]| [a, c, b]).collect()
+    } else {
+        mesh.faces.clone()
+    };
+    Ok((Mesh { vertices, faces }, total.matrix()))
+}
+
+fn unit(v: Vec3, name: &str) -> Result<Vec3, String> {
+    if !v.iter().all(|c| c.is_finite()) || length(v) == 0.0 {
+        return Err(format!("{} must be a non-zero vector", name));
+    }
+    Ok(normalize(v))
+}
+
+/// Rodrigues' rotation matrix, exact for multiples of 90 degrees.
+fn rotation(axis: Vec3, angle_deg: f64) -> [[f64; 3]; 3] {
+    let (sin, cos) = match angle_deg.rem_euclid(360.0) {
+        0.0 => (0.0, 1.0),
+        90.0 => (1.0, 0.0),
+        180.0 => (0.0, -1.0),
+        270.0 => (-1.0, 0.0),
+        _ => angle_deg.to_radians().sin_cos(),
+    };
+    let [x, y, z] = axis;
+    let t = 1.0 - cos;
+    [
+        [t * x * x + cos, t * x * y - sin * z, t * x * z + sin * y],
+        [t * x * y + sin * z, t * y * y + cos, t * y * z - sin * x],
+        [t * x * z - sin * y, t * y * z + sin * x, t * z * z + cos],
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::face_range_totals;
+    use crate::analysis::test_util::cuboid;
+
+    #[test]
+    fn test_rotate_and_center() {
+        let plate = cuboid([0.0; 3], [20.0, 10.0, 2.0]);
+        let steps = [TransformStep::Rotate { axis: [0.0, 0.0, 1.0], angle_deg: 90.0 }, TransformStep::Center];
+        let (turned, matrix) = transform_mesh(&plate, &steps).unwrap();
+        let bbox = turned.bounding_box();
+        assert_eq!(bbox.min, [-5.0, -10.0, 0.0]);
+        assert_eq!(bbox.max, [5.0, 10.0, 2.0]);
+        assert_eq!(matrix[0], [0.0, -1.0, 0.0, 5.0]);
+        assert_eq!(matrix[3], [0.0, 0.0, 0.0, 1.0]);
+        assert!((face_range_totals(&turned, 0..turned.faces.len()).0 - 400.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_mirror_keeps_normals_outward() {
+        let cube = cuboid([0.0; 3], [10.0; 3]);
+        let (mirrored, _) = transform_mesh(&cube, &[TransformStep::Mirror { normal: [2.0, 0.0, 0.0] }]).unwrap();
+        assert_eq!(mirrored.bounding_box().min, [-10.0, 0.0, 0.0]);
+        assert!((face_range_totals(&mirrored, 0..mirrored.faces.len()).0 - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_scale_then_translate() {
+        let cube = cuboid([0.0; 3], [10.0; 3]);
+        let steps = [
+            TransformStep::Scale { factors: [2.0, 1.0, 0.5] },
+            TransformStep::Translate { offset: [1.0, 2.0, 3.0] },
+        ];
+        let (moved, matrix) = transform_mesh(&cube, &steps).unwrap();
+        assert_eq!(moved.bounding_box().max, [21.0, 12.0, 8.0]);
+        assert_eq!(matrix[0], [2.0, 0.0, 0.0, 1.0]);
+        assert!((face_range_totals(&moved, 0..moved.faces.len()).0 - 1000.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_rejects_bad_steps() {
+        let cube = cuboid([0.0; 3], [10.0; 3]);
+        assert!(transform_mesh(&cube, &[]).is_err());
+        assert!(transform_mesh(&cube, &[TransformStep::Scale { factors: [1.0, -1.0, 1.0] }]).is_err());
+        assert!(transform_mesh(&cube, &[TransformStep::Rotate { axis: [0.0; 3], angle_deg: 45.0 }]).is_err());
+        assert!(transform_mesh(&cube, &[TransformStep::Translate { offset: [f64::NAN, 0.0, 0.0] }]).is_err());
+        assert!(transform_mesh(&cube, &vec![TransformStep::Center; MAX_STEPS + 1]).is_err());
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use super::Mesh;
+
+/// Length unit a unitless model (STL, OBJ) was authored in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LengthUnit {
+    #[serde(rename = "mm")]
+    Millimeter,
+    #[serde(rename = "cm")]
+    Centimeter,
+    #[serde(rename = "m")]
+    Meter,
+    #[serde(rename = "in")]
+    Inch,
+}
+
+impl LengthUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LengthUnit::Millimeter => "mm",
+            LengthUnit::Centimeter => "cm",
+            LengthUnit::Meter => "m",
+            LengthUnit::Inch => "in",
+        }
+    }
+
+    /// Inverse of `as_str`, for units read back from the database or a form field.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "mm" => Some(LengthUnit::Millimeter),
+            "cm" => Some(LengthUnit::Centimeter),
+            "m" => Some(LengthUnit::Meter),
+            "in" => Some(LengthUnit::Inch),
+            _ => None,
+        }
+    }
+
+    pub fn mm_per_unit(&self) -> f64 {
+        match self {
+            LengthUnit::Millimeter => 1.0,
+            LengthUnit::Centimeter => 10.0,
+            LengthUnit::Meter => 1000.0,
+            LengthUnit::Inch => 25.4,
+        }
+    }
+}
+
+/// Largest extent (raw units) a part drawn on a 1/16 inch grid may have to be
+/// read as inches.
+const GRID_INCH_MAX_EXTENT: f64 = 12.0;
+
+/// How far (in sixteenths) a coordinate may sit off the 1/16 inch grid.
+const GRID_TOLERANCE: f64 = 1e-3;
+
+/// Guesses the units of a model from its largest extent, read as raw file
+/// units, and from where its vertices sit.
+///
+/// Anything under 2 units across would be too small to print in millimetres.
+/// Below 0.2 it is most plausibly metres (under 200 mm), otherwise inches
+/// (5-50 mm). Up to a foot across, a part whose every vertex lies on a 1/16
+/// inch grid, with some off whole units, is taken as drawn in inches too.
+/// Whole numbers alone don't count: millimetre parts (calibration cubes) are
+/// usually drawn on whole millimetres. Anything else is taken at face value.
+pub fn detect_units(mesh: &Mesh) -> LengthUnit {
+    let extent = mesh.bounding_box().size().into_iter().fold(0.0, f64::max);
+    if extent <= 0.0 {
+        LengthUnit::Millimeter
+    } else if extent < 0.2 {
+        LengthUnit::Meter
+    } else if extent < 2.0 || (extent < GRID_INCH_MAX_EXTENT && on_fractional_inch_grid(mesh)) {
+        LengthUnit::Inch
+    } else {
+        LengthUnit::Millimeter
+    }
+}
+
+/// Whether every coordinate is a multiple of 1/16 and at least one is not a whole number.
+fn on_fractional_inch_grid(mesh: &Mesh) -> bool {
+    let mut fractional = false;
+    for c in mesh.faces.iter().flatten().flat_map(|&v| mesh.vertices[v]) {
+        let sixteenths = (c * 16.0).round();
+        if (c * 16.0 - sixteenths).abs() >= GRID_TOLERANCE {
+            return false;
+        }
+        fractional |= sixteenths % 16.0 != 0.0;
+    }
+    fractional
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::{cube, cuboid};
+
+    /// Box offset by 1/32 so it is off the 1/16 inch grid and only its size counts.
+    fn extent(size: f64) -> Mesh {
+        let min = 1.0 / 32.0;
+        cuboid([min; 3], [min + size, min + size / 2.0, min + size / 4.0])
+    }
+
+    #[test]
+    fn test_detect_units() {
+        assert_eq!(detect_units(&extent(40.0)), LengthUnit::Millimeter);
+        assert_eq!(detect_units(&extent(2.0)), LengthUnit::Millimeter);
+        assert_eq!(detect_units(&extent(1.5)), LengthUnit::Inch);
+        assert_eq!(detect_units(&extent(0.05)), LengthUnit::Meter);
+        assert_eq!(detect_units(&extent(0.0)), LengthUnit::Millimeter);
+        assert_eq!(detect_units(&Mesh::default()), LengthUnit::Millimeter);
+    }
+
+    #[test]
+    fn test_detect_units_on_inch_grid() {
+        // A 4 x 2.5 x 3/4 inch bracket exported without converting
+        assert_eq!(detect_units(&cuboid([-2.0, 0.0, 0.0], [2.0, 2.5, 0.75])), LengthUnit::Inch);
+        assert_eq!(detect_units(&cuboid([0.0; 3], [4.0625, 2.5, 0.75])), LengthUnit::Inch);
+        // Same size, off the grid: a 4 mm part
+        assert_eq!(detect_units(&cuboid([0.0; 3], [4.1, 2.5, 0.75])), LengthUnit::Millimeter);
+        // On the grid, but more than a foot across
+        assert_eq!(detect_units(&cuboid([0.0; 3], [40.0, 25.0, 7.5])), LengthUnit::Millimeter);
+        // Whole numbers only: the 10 mm calibration cube
+        assert_eq!(detect_units(&cube(10.0)), LengthUnit::Millimeter);
+        assert_eq!(detect_units(&cuboid([0.0; 3], [4.0, 2.0, 1.0])), LengthUnit::Millimeter);
+    }
+
+    #[test]
+    fn test_units_round_trip_and_scale() {
+        for unit in [LengthUnit::Millimeter, LengthUnit::Centimeter, LengthUnit::Meter, LengthUnit::Inch] {
+            assert_eq!(LengthUnit::parse(unit.as_str()), Some(unit));
+        }
+        let mesh = Mesh { vertices: vec![[1.0, 2.0, 0.0]], faces: Vec::new() };
+        assert_eq!(mesh.scaled(LengthUnit::Inch.mm_per_unit()).vertices[0], [25.4, 50.8, 0.0]);
+    }
+}
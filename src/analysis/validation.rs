@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use super::intersections::{find_self_intersections, Intersection};
+use super::vec3::{face_normal, length, sub, Vec3};
+use super::Mesh;
+
+/// Topology report for a triangle mesh.
+///
+/// Edges are counted after welding vertices with identical coordinates, so
+/// formats that duplicate vertices along seams (OBJ, 3MF) are judged the same
+/// way as STL.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MeshValidation {
+    /// Edges used by exactly one face (holes in the surface).
+    pub boundary_edges: usize,
+    /// Edges shared by more than two faces.
+    pub non_manifold_edges: usize,
+    /// Manifold edges whose two faces traverse it in the same direction (flipped normals).
+    pub inconsistent_edges: usize,
+    /// Faces repeating the vertices of an earlier face.
+    pub duplicate_faces: usize,
+    /// Faces with zero area (repeated or collinear vertices).
+    pub degenerate_faces: usize,
+    /// Pairs of non-adjacent faces cutting through each other.
+    #[serde(default)]
+    pub self_intersections: usize,
+    /// Where they cross (first few pairs only).
+    #[serde(default)]
+    pub intersections: Vec<Intersection>,
+    pub is_watertight: bool,
+    pub status: String,
+}
+
+impl MeshValidation {
+    pub fn is_printable(&self) -> bool {
+        self.status == STATUS_ANALYZED
+    }
+}
+
+pub const STATUS_ANALYZED: &str = "ANALYZED";
+pub const STATUS_NEEDS_REPAIR: &str = "NEEDS_REPAIR";
+pub const STATUS_FAILED: &str = "FAILED";
+
+pub fn validate_mesh(mesh: &Mesh) -> MeshValidation {
+    let welded = weld_exact(mesh);
+
+    let mut report = MeshValidation::default();
+    let mut seen_faces: HashMap<[usize; 3], usize> = HashMap::new();
+    // Undirected edge -> (faces using it, faces using it as min->max)
+    let mut edges: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+
+    for (face_index, face) in welded.iter().enumerate() {
+        let [a, b, c] = mesh.triangle(face_index);
+        if face[0] == face[1] || face[1] == face[2] || face[0] == face[2] || is_degenerate_triangle(a, b, c) {
+            report.degenerate_faces += 1;
+            continue;
+        }
+
+        let mut key = *face;
+        key.sort_unstable();
+        let occurrences = seen_faces.entry(key).or_insert(0);
+        *occurrences += 1;
+        if *occurrences > 1 {
+            report.duplicate_faces += 1;
+            continue;
+        }
+
+        for i in 0..3 {
+            let (a, b) = (face[i], face[(i + 1) % 3]);
+            let entry = edges.entry((a.min(b), a.max(b))).or_insert((0, 0));
+            entry.0 += 1;
+            if a < b {
+                entry.1 += 1;
+            }
+        }
+    }
+
+    let mut manifold_edges = 0;
+    for &(count, forward) in edges.values() {
+        match count {
+            1 => report.boundary_edges += 1,
+            2 => {
+                manifold_edges += 1;
+                if forward != 1 {
+                    report.inconsistent_edges += 1;
+                }
+            }
+            _ => report.non_manifold_edges += 1,
+        }
+    }
+
+    report.is_watertight = !edges.is_empty() && report.boundary_edges == 0 && report.non_manifold_edges == 0;
+
+    let intersections = find_self_intersections(mesh);
+    report.self_intersections = intersections.pair_count;
+    report.intersections = intersections.intersections;
+
+    let valid_faces = welded.len() - report.degenerate_faces - report.duplicate_faces;
+    report.status = if valid_faces < 4 || manifold_edges == 0 {
+        // Nothing encloses a volume: a triangle soup or a flat sheet
+        STATUS_FAILED
+    } else if !report.is_watertight
+        || report.inconsistent_edges > 0
+        || report.duplicate_faces > 0
+        || report.self_intersections > 0
+    {
+        STATUS_NEEDS_REPAIR
+    } else {
+        STATUS_ANALYZED
+    }
+    .to_string();
+
+    report
+}
+
+/// Remaps face indices so vertices sharing exact coordinates share one index.
+pub(crate) fn weld_exact(mesh: &Mesh) -> Vec<[usize; 3]> {
+    let mut canonical: HashMap<[u64; 3], usize> = HashMap::with_capacity(mesh.vertices.len());
+    let remap: Vec<usize> = mesh
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(index, v)| {
+            // Normalise -0.0 so it welds with 0.0
+            let key = [(v[0] + 0.0).to_bits(), (v[1] + 0.0).to_bits(), (v[2] + 0.0).to_bits()];
+            *canonical.entry(key).or_insert(index)
+        })
+        .collect();
+
+    mesh.faces
+        .iter()
+        .map(|f| [remap[f[0]], remap[f[1]], remap[f[2]]])
+        .collect()
+}
+
+/// Whether a triangle has (numerically) zero area.
+pub(crate) fn is_degenerate_triangle(a: Vec3, b: Vec3, c: Vec3) -> bool {
+    // Relative test so the result does not depend on the model's scale
+    length(face_normal(a, b, c)) <= 1e-9 * length(sub(b, a)) * length(sub(c, a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::{add_box, cube};
+
+    #[test]
+    fn test_closed_cube_is_printable() {
+        let report = validate_mesh(&cube(10.0));
+        assert!(report.is_watertight);
+        assert!(report.is_printable());
+        assert_eq!(report, MeshValidation { is_watertight: true, status: STATUS_ANALYZED.to_string(), ..Default::default() });
+    }
+
+    #[test]
+    fn test_open_and_flipped_cube_needs_repair() {
+        let mut mesh = cube(10.0);
+        mesh.faces.pop();
+        mesh.faces[0] = [0, 3, 2];
+        let report = validate_mesh(&mesh);
+        assert_eq!(report.boundary_edges, 3);
+        assert_eq!(report.inconsistent_edges, 3);
+        assert!(!report.is_watertight);
+        assert_eq!(report.status, STATUS_NEEDS_REPAIR);
+    }
+
+    #[test]
+    fn test_self_intersecting_cubes_need_repair() {
+        let mut mesh = cube(10.0);
+        add_box(&mut mesh, [5.0; 3], [15.0; 3], false);
+
+        let report = validate_mesh(&mesh);
+        assert!(report.is_watertight);
+        assert!(report.self_intersections > 0);
+        assert!(!report.intersections.is_empty());
+        assert_eq!(report.status, STATUS_NEEDS_REPAIR);
+    }
+
+    #[test]
+    fn test_duplicate_vertices_are_welded() {
+        // Same cube with every face owning its own vertices, as some OBJ exporters write it
+        let source = cube(10.0);
+        let mut mesh = Mesh::default();
+        for face in &source.faces {
+            let base = mesh.vertices.len();
+            mesh.vertices.extend(face.iter().map(|&v| source.vertices[v]));
+            mesh.faces.push([base, base + 1, base + 2]);
+        }
+        assert!(validate_mesh(&mesh).is_printable());
+    }
+
+    #[test]
+    fn test_degenerate_duplicate_and_soup() {
+        let mut mesh = cube(10.0);
+        mesh.faces.push([0, 2, 3]);
+        mesh.vertices.push([5.0, 0.0, 0.0]);
+        mesh.faces.push([0, 8, 1]);
+        let report = validate_mesh(&mesh);
+        assert_eq!(report.duplicate_faces, 1);
+        assert_eq!(report.degenerate_faces, 1);
+        assert_eq!(report.status, STATUS_NEEDS_REPAIR);
+
+        let soup = Mesh {
+            vertices: vec![[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]],
+            faces: vec![[0, 1, 2]],
+        };
+        assert_eq!(validate_mesh(&soup).status, STATUS_FAILED);
+    }
+}
@@ -0,0 +1,46 @@
+//! Small helpers for `[f64; 3]` vector math used across the analysis modules.
+
+pub type Vec3 = [f64; 3];
+
+pub fn add(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+pub fn sub(a: Vec3, b: Vec3) -> Vec3 {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+pub fn scale(a: Vec3, s: f64) -> Vec3 {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+pub fn length(a: Vec3) -> f64 {
+    dot(a, a).sqrt()
+}
+
+/// Unit vector in the direction of `a`, or zero for a zero vector.
+pub fn normalize(a: Vec3) -> Vec3 {
+    let len = length(a);
+    if len > 0.0 { scale(a, 1.0 / len) } else { [0.0; 3] }
+}
+
+/// Non-normalized face normal (length is twice the triangle area).
+pub fn face_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    cross(sub(b, a), sub(c, a))
+}
+
+pub fn centroid(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    scale(add(add(a, b), c), 1.0 / 3.0)
+}
@@ -0,0 +1,301 @@
+use std::time::{Duration, Instant};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use super::Mesh;
+
+/// Cells along the longest side of the bounding box for the finest pass.
+pub const DEFAULT_RESOLUTION: usize = 128;
+/// Finest grid allowed; three 256³ vote grids are about 50 MB.
+pub const MAX_RESOLUTION: usize = 256;
+pub const DEFAULT_TIME_BUDGET: Duration = Duration::from_secs(2);
+/// Resolution of the first pass, which always runs regardless of the budget.
+const FIRST_PASS_RESOLUTION: usize = 32;
+
+/// How a model's volume was measured.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum VolumeMethod {
+    /// Sum of signed tetrahedron volumes; exact for closed meshes.
+    #[default]
+    SignedVolume,
+    /// Voxel count from `voxel_volume`, for meshes with holes or non-manifold edges.
+    Voxel,
+}
+
+impl VolumeMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VolumeMethod::SignedVolume => "SIGNED_VOLUME",
+            VolumeMethod::Voxel => "VOXEL",
+        }
+    }
+
+    /// Inverse of `as_str`, for methods read back from the database.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "SIGNED_VOLUME" => Some(VolumeMethod::SignedVolume),
+            "VOXEL" => Some(VolumeMethod::Voxel),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoxelSettings {
+    /// Cells along the longest side of the bounding box for the finest pass.
+    pub resolution: usize,
+    /// Refinement stops once the next pass isn't expected to finish within this.
+    pub time_budget: Duration,
+}
+
+impl Default for VoxelSettings {
+    fn default() -> Self {
+        Self { resolution: DEFAULT_RESOLUTION, time_budget: DEFAULT_TIME_BUDGET }
+    }
+}
+
+impl VoxelSettings {
+    /// Reads `VOXEL_RESOLUTION` and `VOXEL_TIME_BUDGET_MS`.
+    pub fn from_env() -> Self {
+        let setting = |name: &str| std::env::var(name).ok().and_then(|value| value.parse::<u64>().ok());
+        let defaults = Self::default();
+        Self {
+            resolution: setting("VOXEL_RESOLUTION").map_or(defaults.resolution, |r| r as usize),
+            time_budget: setting("VOXEL_TIME_BUDGET_MS").map_or(defaults.time_budget, Duration::from_millis),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoxelVolume {
+    pub volume_cm3: f64,
+    /// Estimated uncertainty: half the cells on the surface plus the cells rays disagree on.
+    pub error_cm3: f64,
+    /// Edge length (mm) of the cells of the pass that produced the estimate.
+    pub cell_mm: f64,
+}
+
+/// Estimates the volume enclosed by a mesh that may have holes or stray faces.
+///
+/// Each cell is classified by ray parity along X, Y and Z through its centre.
+/// A ray crossing the surface an odd number of times passed through a hole and
+/// abstains; the cell is inside when more rays say so than not. Passes start
+/// coarse and double in resolution up to `settings.resolution` while the next
+/// pass is expected to fit in the time budget.
+pub fn voxel_volume(mesh: &Mesh, settings: &VoxelSettings) -> VoxelVolume {
+    let started = Instant::now();
+    let max_resolution = settings.resolution.clamp(1, MAX_RESOLUTION);
+    let mut resolution = FIRST_PASS_RESOLUTION.min(max_resolution);
+    loop {
+        let pass_started = Instant::now();
+        let estimate = voxelize(mesh, resolution);
+        if resolution >= max_resolution {
+            return estimate;
+        }
+        // Work grows with the cube of the resolution
+        let next_pass = pass_started.elapsed() * 8;
+        if started.elapsed() + next_pass > settings.time_budget {
+            return estimate;
+        }
+        resolution = (resolution * 2).min(max_resolution);
+    }
+}
+
+/// Cube grid over the mesh's bounding box.
+pub(crate) struct Grid {
+    pub(crate) origin: [f64; 3],
+    /// Edge length (mm) of a cell.
+    pub(crate) cell: f64,
+    pub(crate) dims: [usize; 3],
+}
+
+impl Grid {
+    pub(crate) fn index(&self, cell: [usize; 3]) -> usize {
+        (cell[2] * self.dims[1] + cell[1]) * self.dims[0] + cell[0]
+    }
+
+    pub(crate) fn center(&self, axis: usize, i: usize) -> f64 {
+        self.origin[axis] + (i as f64 + 0.5) * self.cell
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.dims.iter().product()
+    }
+}
+
+/// Cells of a grid classified as inside or outside the mesh.
+pub(crate) struct Voxels {
+    pub(crate) grid: Grid,
+    pub(crate) inside: Vec<bool>,
+    /// Cells the three rays through them disagree on.
+    pub(crate) disputed: usize,
+}
+
+/// Classifies a grid with `resolution` cells along the longest side of the
+/// mesh's bounding box. `None` for a mesh with no extent.
+pub(crate) fn classify(mesh: &Mesh, resolution: usize) -> Option<Voxels> {
+    let bbox = mesh.bounding_box();
+    let size = bbox.size();
+    let longest = size[0].max(size[1]).max(size[2]);
+    if longest <= 0.0 {
+        return None;
+    }
+    let cell = longest / resolution as f64;
+    let dims = size.map(|s| ((s / cell).ceil() as usize).max(1));
+    let grid = Grid { origin: bbox.min, cell, dims };
+
+    // Per cell: +1 for each ray saying inside, -1 for each saying outside
+    let votes: Vec<Vec<i8>> = (0..3).into_par_iter().map(|axis| axis_votes(mesh, &grid, axis)).collect();
+    let inside: Vec<bool> = (0..grid.len())
+        .map(|i| votes[0][i] + votes[1][i] + votes[2][i] > 0)
+        .collect();
+    let disputed = (0..grid.len())
+        .filter(|&i| {
+            let cast: Vec<i8> = votes.iter().map(|v| v[i]).filter(|&v| v != 0).collect();
+            cast.iter().any(|&v| v != cast[0])
+        })
+        .count();
+    Some(Voxels { grid, inside, disputed })
+}
+
+fn voxelize(mesh: &Mesh, resolution: usize) -> VoxelVolume {
+    let Some(Voxels { grid, inside, disputed }) = classify(mesh, resolution) else {
+        return VoxelVolume { volume_cm3: 0.0, error_cm3: 0.0, cell_mm: 0.0 };
+    };
+
+    let [nx, ny, nz] = grid.dims;
+    let mut filled = 0usize;
+    let mut surface = 0usize;
+    for z in 0..nz {
+        for y in 0..ny {
+            for x in 0..nx {
+                if !inside[grid.index([x, y, z])] {
+                    continue;
+                }
+                filled += 1;
+                let on_surface = x == 0 || y == 0 || z == 0 || x + 1 == nx || y + 1 == ny || z + 1 == nz
+                    || !inside[grid.index([x - 1, y, z])] || !inside[grid.index([x + 1, y, z])]
+                    || !inside[grid.index([x, y - 1, z])] || !inside[grid.index([x, y + 1, z])]
+                    || !inside[grid.index([x, y, z - 1])] || !inside[grid.index([x, y, z + 1])];
+                if on_surface {
+                    surface += 1;
+                }
+            }
+        }
+    }
+
+    let cell_cm3 = grid.cell.powi(3) / 1000.0;
+    VoxelVolume {
+        volume_cm3: filled as f64 * cell_cm3,
+        error_cm3: (surface as f64 / 2.0 + disputed as f64) * cell_cm3,
+        cell_mm: grid.cell,
+    }
+}
+
+/// Casts a ray along `axis` through every column of cell centres and votes on
+/// each cell by crossing parity.
+fn axis_votes(mesh: &Mesh, grid: &Grid, axis: usize) -> Vec<i8> {
+    let (u, v) = ((axis + 1) % 3, (axis + 2) % 3);
+    let (nu, nv) = (grid.dims[u], grid.dims[v]);
+    // (depth along the ray, whether the face points along it) per column
+    let mut hits: Vec<Vec<(f64, bool)>> = vec![Vec::new(); nu * nv];
+
+    for face in 0..mesh.faces.len() {
+        let [a, b, c] = mesh.triangle(face);
+        let (pa, pb, pc) = ([a[u], a[v]], [b[u], b[v]], [c[u], c[v]]);
+        let area = cross(pa, pb, pc);
+        if area == 0.0 {
+            // Parallel to the ray
+            continue;
+        }
+        let column_range = |axis: usize, lo: f64, hi: f64, n: usize| {
+            let first = ((lo - grid.origin[axis]) / grid.cell - 0.5).ceil().max(0.0) as usize;
+            let last = ((hi - grid.origin[axis]) / grid.cell - 0.5).floor();
+            if last < 0.0 { first..first } else { first..(last as usize + 1).min(n) }
+        };
+        let us = column_range(u, pa[0].min(pb[0]).min(pc[0]), pa[0].max(pb[0]).max(pc[0]), nu);
+        let vs = column_range(v, pa[1].min(pb[1]).min(pc[1]), pa[1].max(pb[1]).max(pc[1]), nv);
+        for j in vs {
+            for i in us.clone() {
+                let p = [grid.center(u, i), grid.center(v, j)];
+                let (wa, wb, wc) = (cross(pb, pc, p) / area, cross(pc, pa, p) / area, cross(pa, pb, p) / area);
+                if wa < 0.0 || wb < 0.0 || wc < 0.0 {
+                    continue;
+                }
+                let depth = wa * a[axis] + wb * b[axis] + wc * c[axis];
+                hits[j * nu + i].push((depth, area > 0.0));
+            }
+        }
+    }
+
+    let mut votes = vec![0i8; grid.len()];
+    let epsilon = grid.cell * 1e-9;
+    for (column, mut crossings) in hits.into_iter().enumerate() {
+        crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+        // A ray through a shared edge or vertex meets each face there; facing the
+        // same way they are one crossing, facing opposite ways the ray grazes a fold.
+        crossings.dedup_by(|next, kept| next.1 == kept.1 && next.0 - kept.0 <= epsilon);
+        if crossings.len() % 2 == 1 {
+            continue;
+        }
+        let (i, j) = (column % nu, column / nu);
+        for k in 0..grid.dims[axis] {
+            let depth = grid.center(axis, k);
+            let crossed = crossings.partition_point(|&(d, _)| d < depth);
+            let mut cell = [0; 3];
+            cell[axis] = k;
+            cell[u] = i;
+            cell[v] = j;
+            votes[grid.index(cell)] = if crossed % 2 == 1 { 1 } else { -1 };
+        }
+    }
+    votes
+}
+
+/// Twice the signed area of triangle `abc`.
+fn cross(a: [f64; 2], b: [f64; 2], c: [f64; 2]) -> f64 {
+    (b[0] - a[0]) * (c[1] - a[1]) - (b[1] - a[1]) * (c[0] - a[0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::test_util::cube;
+
+    #[test]
+    fn test_closed_cube_volume() {
+        let estimate = voxel_volume(&cube(10.0), &VoxelSettings::default());
+        assert!((estimate.volume_cm3 - 1.0).abs() < 1e-9, "{:?}", estimate);
+        assert!(estimate.error_cm3 > 0.0 && estimate.error_cm3 < 0.05, "{:?}", estimate);
+    }
+
+    #[test]
+    fn test_cube_with_holes_and_stray_faces() {
+        // Top face missing entirely and one side half gone
+        let mut mesh = cube(10.0);
+        mesh.faces.retain(|&f| f != [4, 5, 7] && f != [4, 7, 6] && f != [1, 3, 7]);
+        // A loose sheet floating inside
+        mesh.vertices.extend([[2.0, 2.0, 5.0], [8.0, 2.0, 5.0], [2.0, 8.0, 5.0]]);
+        mesh.faces.push([8, 9, 10]);
+
+        let estimate = voxel_volume(&mesh, &VoxelSettings::default());
+        assert!((estimate.volume_cm3 - 1.0).abs() < estimate.error_cm3.max(0.02), "{:?}", estimate);
+    }
+
+    #[test]
+    fn test_resolution_and_budget_limit_passes() {
+        let coarse = voxel_volume(&cube(10.0), &VoxelSettings { resolution: 16, ..Default::default() });
+        assert_eq!(coarse.cell_mm, 10.0 / 16.0);
+        // The first pass always runs, even with no time to spare
+        let rushed = voxel_volume(&cube(10.0), &VoxelSettings { resolution: 256, time_budget: Duration::ZERO });
+        assert_eq!(rushed.cell_mm, 10.0 / FIRST_PASS_RESOLUTION as f64);
+    }
+
+    #[test]
+    fn test_method_round_trips() {
+        for method in [VolumeMethod::SignedVolume, VolumeMethod::Voxel] {
+            assert_eq!(VolumeMethod::parse(method.as_str()), Some(method));
+            assert_eq!(serde_json::to_value(method).unwrap(), method.as_str());
+        }
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use axum::http::StatusCode;
+use tokio::sync::Semaphore;
+
+/// Jobs allowed to wait for a slot before new ones are turned away.
+pub const DEFAULT_MAX_QUEUED: usize = 32;
+
+/// Runs CPU-heavy geometry work on Tokio's blocking threads, at most
+/// `concurrency` jobs at a time, so large models never tie up the async workers.
+/// Jobs beyond the limit wait in FIFO order; past `max_queued` they are rejected.
+#[derive(Clone)]
+pub struct ComputePool {
+    permits: Arc<Semaphore>,
+    waiting: Arc<AtomicUsize>,
+    max_queued: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ComputeError {
+    QueueFull,
+    /// The job panicked; the panic itself is logged by Tokio.
+    Failed,
+}
+
+impl From<ComputeError> for (StatusCode, String) {
+    fn from(error: ComputeError) -> Self {
+        match error {
+            ComputeError::QueueFull => (StatusCode::SERVICE_UNAVAILABLE, "Analysis queue is full, try again shortly".to_string()),
+            ComputeError::Failed => (StatusCode::INTERNAL_SERVER_ERROR, "Analysis failed".to_string()),
+        }
+    }
+}
+
+impl ComputePool {
+    pub fn new(concurrency: usize, max_queued: usize) -> Self {
+        Self {
+            permits: Arc::new(Semaphore::new(concurrency.max(1))),
+            waiting: Arc::new(AtomicUsize::new(0)),
+            max_queued,
+        }
+    }
+
+    /// Reads `ANALYSIS_CONCURRENCY` (default: one job per CPU) and `ANALYSIS_MAX_QUEUED`.
+    pub fn from_env() -> Self {
+        let setting = |name: &str| std::env::var(name).ok().and_then(|value| value.parse::<usize>().ok());
+        Self::new(
+            setting("ANALYSIS_CONCURRENCY").unwrap_or_else(default_concurrency),
+            setting("ANALYSIS_MAX_QUEUED").unwrap_or(DEFAULT_MAX_QUEUED),
+        )
+    }
+
+    /// Runs `job` once a slot is free and returns its result.
+    pub async fn run<T, F>(&self, job: F) -> Result<T, ComputeError>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let permit = match self.permits.clone().try_acquire_owned() {
+            Ok(permit) => permit,
+            Err(_) => {
+                if self.waiting.fetch_add(1, Ordering::SeqCst) >= self.max_queued {
+                    self.waiting.fetch_sub(1, Ordering::SeqCst);
+                    return Err(ComputeError::QueueFull);
+                }
+                let permit = self.permits.clone().acquire_owned().await;
+                self.waiting.fetch_sub(1, Ordering::SeqCst);
+                permit.map_err(|_| ComputeError::Failed)?
+            }
+        };
+        tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            job()
+        })
+        .await
+        .map_err(|_| ComputeError::Failed)
+    }
+}
+
+impl Default for ComputePool {
+    fn default() -> Self {
+        Self::new(default_concurrency(), DEFAULT_MAX_QUEUED)
+    }
+}
+
+fn default_concurrency() -> usize {
+    std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrency_is_limited() {
+        let pool = ComputePool::new(2, 8);
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let jobs: Vec<_> = (0..6)
+            .map(|i| {
+                let (pool, running, peak) = (pool.clone(), running.clone(), peak.clone());
+                tokio::spawn(async move {
+                    pool.run(move || {
+                        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                        peak.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(Duration::from_millis(20));
+                        running.fetch_sub(1, Ordering::SeqCst);
+                        i * 2
+                    })
+                    .await
+                })
+            })
+            .collect();
+        let mut results = Vec::new();
+        for job in jobs {
+            results.push(job.await.unwrap().unwrap());
+        }
+        assert_eq!(results, vec![0, 2, 4, 6, 8, 10]);
+        assert_eq!(peak.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_full_queue_is_rejected() {
+        let pool = ComputePool::new(1, 1);
+        let (release, blocked) = mpsc::channel::<()>();
+        let busy = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(move || blocked.recv().unwrap()).await }
+        });
+        while pool.permits.available_permits() > 0 {
+            tokio::task::yield_now().await;
+        }
+        let queued = tokio::spawn({
+            let pool = pool.clone();
+            async move { pool.run(|| 1).await }
+        });
+        while pool.waiting.load(Ordering::SeqCst) == 0 {
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(pool.run(|| 2).await, Err(ComputeError::QueueFull));
+        release.send(()).unwrap();
+        busy.await.unwrap().unwrap();
+        assert_eq!(queued.await.unwrap(), Ok(1));
+        assert_eq!(pool.run(|| 3).await, Ok(3));
+    }
+
+    #[tokio::test]
+    async fn test_panics_are_reported() {
+        let pool = ComputePool::new(1, 0);
+        assert_eq!(pool.run(|| panic!("bad mesh")).await, Err::<(), _>(ComputeError::Failed));
+        // The slot is released again
+        assert_eq!(pool.run(|| 4).await, Ok(4));
+    }
+}
@@ -6,13 +6,55 @@ pub mod admin;
 use axum::{
     extract::{State, Json},
     http::StatusCode,
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Extension,
 };
 use sqlx::PgPool;
+use crate::analysis::error::AnalysisError;
+use crate::compute::ComputeError;
 use crate::models::{CreateUserRequest, LoginRequest, AuthResponse, User};
 use crate::auth;
 
+/// Error of handlers that read stored models. A model that can't be read
+/// answers with its `AnalysisError` as JSON (`code`, details and `message`);
+/// everything else stays a plain-text `(StatusCode, String)`.
+#[derive(Debug)]
+pub enum ApiError {
+    Text(StatusCode, String),
+    Model(AnalysisError),
+}
+
+impl From<(StatusCode, String)> for ApiError {
+    fn from((status, message): (StatusCode, String)) -> Self {
+        ApiError::Text(status, message)
+    }
+}
+
+impl From<AnalysisError> for ApiError {
+    fn from(error: AnalysisError) -> Self {
+        ApiError::Model(error)
+    }
+}
+
+impl From<ComputeError> for ApiError {
+    fn from(error: ComputeError) -> Self {
+        <(StatusCode, String)>::from(error).into()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self {
+            ApiError::Text(status, message) => (status, message).into_response(),
+            ApiError::Model(error) => {
+                let mut body = serde_json::to_value(&error).unwrap_or_default();
+                body["message"] = error.to_string().into();
+                (error.status(), Json(body)).into_response()
+            }
+        }
+    }
+}
+
 pub async fn signup(
     State(pool): State<PgPool>,
     Json(payload): Json<CreateUserRequest>,
@@ -1,10 +1,22 @@
 use axum::{
-    extract::{Extension, Path},
-    http::StatusCode,
+    extract::{Extension, Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
     Json,
 };
+use bytes::Bytes;
 use sqlx::PgPool;
+use std::sync::Arc;
+use crate::analysis::gcode::{generate_gcode, GcodeFlavor, GcodeJob};
+use crate::analysis::orientation::optimize_orientation;
+use crate::analysis::shells::{analyze_shells, part_mesh};
+use crate::analysis::slicer::slice_mesh;
+use crate::compute::ComputePool;
+use crate::handlers::ApiError;
+use crate::handlers::files::{active_printers, fetch_stored_model};
 use crate::models::{User, Order};
+use crate::quoting::Material;
+use crate::storage::StorageService;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -21,7 +33,7 @@ pub async fn list_orders(
         return Err(StatusCode::FORBIDDEN);
     }
 
-    let orders = sqlx::query_as::<_, Order>("SELECT * FROM orders ORDER BY created_at DESC")
+    let orders = sqlx::query_as::<_, Order>("SELECT o.*, q.file_id FROM orders o JOIN quotes q ON q.id = o.quote_id ORDER BY o.created_at DESC")
         .fetch_all(&pool)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -50,3 +62,145 @@ pub async fn update_order_status(
 
     Ok(Json(order))
 }
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct GenerateGcodeRequest {
+    /// Printer to slice for; defaults to the first active FDM printer the part fits.
+    pub printer_id: Option<Uuid>,
+}
+
+#[derive(sqlx::FromRow)]
+struct OrderJob {
+    material: String,
+    layer_height: f64,
+    infill_percentage: i32,
+    shell_index: Option<i32>,
+    filename: String,
+    gcs_path: String,
+    file_format: String,
+    units: String,
+}
+
+async fn fetch_admin_order(pool: &PgPool, user: &User, id: Uuid) -> Result<Order, (StatusCode, String)> {
+    if user.role != "ADMIN" {
+        return Err((StatusCode::FORBIDDEN, "Admin access required".to_string()));
+    }
+    sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "Order not found".to_string()))
+}
+
+/// Slices the order's file with its quoted settings, in the support-free
+/// orientation, and stores Marlin/Klipper G-code for the chosen printer.
+pub async fn generate_order_gcode(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(id): Path<Uuid>,
+    payload: Option<Json<GenerateGcodeRequest>>,
+) -> Result<Json<Order>, ApiError> {
+    let order = fetch_admin_order(&pool, &user, id).await?;
+    if order.status != "PRINTING" {
+        return Err(ApiError::Text(StatusCode::CONFLICT, "G-code is generated once the order is PRINTING".to_string()));
+    }
+    let request = payload.map(|Json(request)| request).unwrap_or_default();
+
+    let job = sqlx::query_as::<_, OrderJob>(
+        "SELECT q.material, q.layer_height, q.infill_percentage, q.shell_index, f.filename, f.gcs_path, f.file_format, f.units \
+         FROM quotes q JOIN files f ON f.id = q.file_id WHERE q.id = $1"
+    )
+    .bind(order.quote_id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let material = Material::parse(&job.material)
+        .ok_or((StatusCode::UNPROCESSABLE_ENTITY, format!("Unknown material: {}", job.material)))?;
+    let (nozzle_temperature_c, bed_temperature_c) = material.print_temperatures_c()
+        .ok_or((StatusCode::UNPROCESSABLE_ENTITY, format!("{} orders are not printed from G-code", job.material)))?;
+
+    let stored = fetch_stored_model(storage.as_ref(), &job.gcs_path, &job.file_format, &job.units).await?;
+    let printers = active_printers(&pool, Some(material.technology())).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let (shell_index, layer_height, infill_percentage) = (job.shell_index, job.layer_height, job.infill_percentage);
+
+    let (gcode, printer) = compute.run(move || {
+        // No supports are generated, so print in the orientation that needs the least
+        let mut mesh = stored.mesh()?;
+        if let Some(index) = shell_index {
+            let shells = analyze_shells(&mesh);
+            if index < 0 || index as usize >= shells.len() {
+                return Err(ApiError::Text(StatusCode::UNPROCESSABLE_ENTITY, format!("Quoted shell {} no longer exists", index)));
+            }
+            mesh = part_mesh(&mesh, &shells, index as usize);
+        }
+        let best = optimize_orientation(&mesh, material.overhang_angle_deg()).best;
+        let mesh = mesh.rotated(&best.rotation);
+        let size = mesh.bounding_box().size();
+
+        let printer = match request.printer_id {
+            Some(printer_id) => printers.into_iter().find(|p| p.id == printer_id)
+                .ok_or((StatusCode::NOT_FOUND, "No active FDM printer with that id".to_string()))?,
+            None => printers.into_iter().find(|p| size.iter().zip(p.build_volume()).all(|(d, limit)| *d <= limit))
+                .ok_or((StatusCode::UNPROCESSABLE_ENTITY, "Model does not fit any FDM printer build volume".to_string()))?,
+        };
+        if size.iter().zip(printer.build_volume()).any(|(d, limit)| *d > limit) {
+            return Err(ApiError::Text(StatusCode::UNPROCESSABLE_ENTITY, format!("Model does not fit the {} build volume", printer.name)));
+        }
+        let flavor = GcodeFlavor::parse(&printer.gcode_flavor)
+            .ok_or((StatusCode::INTERNAL_SERVER_ERROR, format!("Unknown G-code flavor: {}", printer.gcode_flavor)))?;
+        let profile = printer.machine_profile();
+        if layer_height > profile.max_layer_height_mm() {
+            return Err(ApiError::Text(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Layer height {} mm is too thick for the {} nozzle", layer_height, printer.name),
+            ));
+        }
+
+        let sliced = slice_mesh(&mesh, layer_height).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+        let gcode = generate_gcode(&sliced, &profile, &GcodeJob {
+            flavor,
+            nozzle_temperature_c,
+            bed_temperature_c,
+            infill_percentage: infill_percentage as f64,
+            bed_center: [printer.build_x_mm / 2.0, printer.build_y_mm / 2.0],
+        });
+        Ok((gcode, printer))
+    }).await??;
+
+    let stem = std::path::Path::new(&job.filename).file_stem().and_then(|s| s.to_str()).unwrap_or("model");
+    let gcode_path = storage.upload_file(&format!("{}_{}.gcode", order.id, stem), Bytes::from(gcode), "text/x-gcode").await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+
+    let order = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET gcode_path = $1, gcode_printer_id = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(&gcode_path)
+    .bind(printer.id)
+    .bind(order.id)
+    .fetch_one(&pool)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Json(order))
+}
+
+pub async fn download_order_gcode(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    Extension(user): Extension<User>,
+    Path(id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let order = fetch_admin_order(&pool, &user, id).await?;
+    let gcode_path = order.gcode_path
+        .ok_or((StatusCode::NOT_FOUND, "No G-code has been generated for this order".to_string()))?;
+    let data = storage.download_file(&gcode_path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+
+    let disposition = format!("attachment; filename=\"order_{}.gcode\"", order.id);
+    Ok(([(header::CONTENT_TYPE, "text/x-gcode".to_string()), (header::CONTENT_DISPOSITION, disposition)], data))
+}
@@ -1,88 +1,900 @@
 use axum::{
-    extract::{State, Multipart, Path},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{State, Multipart, Path, Query},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json, Extension,
 };
-use sqlx::PgPool;
-use crate::models::User;
-use crate::analysis;
+use bytes::Bytes;
+use sqlx::{PgConnection, PgExecutor, PgPool};
+use crate::compute::ComputePool;
+use crate::handlers::ApiError;
+use crate::jobs::{AnalysisQueue, STATUS_PENDING};
+use crate::models::{Printer, User};
+use crate::analysis::{self, BoundingBox, GeometryAnalysis, Mesh, Model, ModelFormat, ObjectAnalysis};
+use crate::analysis::decimate::{decimate, PREVIEW_MAX_TRIANGLES};
+use crate::analysis::drainage::{analyze_drainage, ResinDrainage};
+use crate::analysis::error::AnalysisError;
+use crate::analysis::hollow::{hollow_mesh, HollowReport, HollowSettings};
+use crate::analysis::mass::MassProperties;
+use crate::analysis::measure::{measure, MeasureRequest, Measurement};
+use crate::analysis::orientation::{optimize_orientation, OrientationSearch};
+use crate::analysis::slicer::{slice_mesh, SlicedModel};
+use crate::analysis::thumbnail::{render_thumbnail, THUMBNAIL_SIZE};
+use crate::analysis::thickness::{analyze_wall_thickness, WallThickness};
+use crate::analysis::transform::{transform_mesh, TransformStep};
+use crate::analysis::repair::{self, RepairReport};
+use crate::analysis::section::{cross_section, section_svg};
+use crate::analysis::shells::Shell;
+use crate::analysis::units::LengthUnit;
+use crate::analysis::validation::MeshValidation;
+use crate::analysis::voxel::VolumeMethod;
+use crate::quoting::Material;
 use crate::storage::StorageService;
 use std::sync::Arc;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 use sqlx::FromRow;
+use sqlx::types::Json as DbJson;
 
 const MAX_FILE_SIZE: usize = 100 * 1024 * 1024; // 100 MB
 
-#[derive(Debug, Serialize, FromRow)]
+const FILE_RECORD_COLUMNS: &str = "id, user_id, filename, gcs_path, volume_cm3, volume_method, volume_error_cm3, surface_area_cm2, file_format, units, suggested_units, objects, shells, \
+    bbox_min_x, bbox_min_y, bbox_min_z, bbox_max_x, bbox_max_y, bbox_max_z, fits_build_volume, mesh_validation, mass_properties, \
+    parent_file_id, revision_kind, repair_report, thumbnail_path, preview_path, status, analysis_error, analysis_failure, created_at";
+
+pub const REVISION_REPAIR: &str = "REPAIR";
+pub const REVISION_RESCALE: &str = "RESCALE";
+pub const REVISION_HOLLOW: &str = "HOLLOW";
+pub const REVISION_TRANSFORM: &str = "TRANSFORM";
+
+#[derive(Debug, FromRow)]
 pub struct FileRecord {
     pub id: Uuid,
+    pub user_id: Uuid,
     pub filename: String,
+    pub gcs_path: String,
     pub volume_cm3: Option<f64>,
+    pub volume_method: Option<String>,
+    pub volume_error_cm3: Option<f64>,
     pub surface_area_cm2: Option<f64>,
+    pub file_format: String,
+    pub units: String,
+    pub suggested_units: Option<String>,
+    pub objects: Option<DbJson<Vec<ObjectAnalysis>>>,
+    pub shells: Option<DbJson<Vec<Shell>>>,
+    pub bbox_min_x: Option<f64>,
+    pub bbox_min_y: Option<f64>,
+    pub bbox_min_z: Option<f64>,
+    pub bbox_max_x: Option<f64>,
+    pub bbox_max_y: Option<f64>,
+    pub bbox_max_z: Option<f64>,
+    pub fits_build_volume: Option<bool>,
+    pub mesh_validation: Option<DbJson<MeshValidation>>,
+    pub mass_properties: Option<DbJson<MassProperties>>,
+    pub parent_file_id: Option<Uuid>,
+    pub revision_kind: Option<String>,
+    pub repair_report: Option<DbJson<RepairReport>>,
+    pub thumbnail_path: Option<String>,
+    pub preview_path: Option<String>,
     pub status: String,
+    pub analysis_error: Option<String>,
+    pub analysis_failure: Option<DbJson<AnalysisError>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl FileRecord {
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(BoundingBox {
+            min: [self.bbox_min_x?, self.bbox_min_y?, self.bbox_min_z?],
+            max: [self.bbox_max_x?, self.bbox_max_y?, self.bbox_max_z?],
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileAnalysisResponse {
+    pub id: Uuid,
+    pub filename: String,
+    pub file_format: String,
+    pub units: String,
+    pub suggested_units: Option<String>,
+    /// `PENDING` until background analysis finishes, see `analysis_error` when `FAILED`.
+    pub status: String,
+    pub analysis_error: Option<String>,
+    /// Why the model couldn't be read, with a stable `code` for clients to explain it.
+    pub analysis_failure: Option<AnalysisError>,
+    pub volume_cm3: Option<f64>,
+    /// `VOXEL` when the mesh has holes and `volume_cm3` is an estimate.
+    pub volume_method: Option<VolumeMethod>,
+    pub volume_error_cm3: Option<f64>,
+    pub surface_area_cm2: Option<f64>,
+    pub bounding_box: Option<BoundingBox>,
+    pub dimensions_mm: Option<[f64; 3]>,
+    pub fits_build_volume: Option<bool>,
+    pub mesh_validation: Option<MeshValidation>,
+    pub mass_properties: Option<MassProperties>,
+    pub objects: Vec<ObjectAnalysis>,
+    pub shells: Vec<Shell>,
+    pub parent_file_id: Option<Uuid>,
+    pub revision_kind: Option<String>,
+    pub repair_report: Option<RepairReport>,
     pub created_at: DateTime<Utc>,
 }
 
+impl From<FileRecord> for FileAnalysisResponse {
+    fn from(file: FileRecord) -> Self {
+        let bounding_box = file.bounding_box();
+        Self {
+            id: file.id,
+            filename: file.filename,
+            file_format: file.file_format,
+            units: file.units,
+            suggested_units: file.suggested_units,
+            status: file.status,
+            analysis_error: file.analysis_error,
+            analysis_failure: file.analysis_failure.map(|f| f.0),
+            volume_cm3: file.volume_cm3,
+            volume_method: file.volume_method.as_deref().and_then(VolumeMethod::parse),
+            volume_error_cm3: file.volume_error_cm3,
+            surface_area_cm2: file.surface_area_cm2,
+            bounding_box,
+            dimensions_mm: bounding_box.map(|b| b.size()),
+            fits_build_volume: file.fits_build_volume,
+            mesh_validation: file.mesh_validation.map(|v| v.0),
+            mass_properties: file.mass_properties.map(|m| m.0),
+            objects: file.objects.map(|o| o.0).unwrap_or_default(),
+            shells: file.shells.map(|s| s.0).unwrap_or_default(),
+            parent_file_id: file.parent_file_id,
+            revision_kind: file.revision_kind,
+            repair_report: file.repair_report.map(|r| r.0),
+            created_at: file.created_at,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadResponse {
     pub file_id: Uuid,
     pub filename: String,
+    pub file_format: String,
+    /// Units the uploaded coordinates were read in; all measurements below are millimetres.
+    pub units: String,
+    /// Likely units when the model looks mis-scaled, see `POST /api/files/:id/rescale`.
+    pub suggested_units: Option<String>,
+    pub status: String,
     pub volume_cm3: f64,
+    pub volume_method: VolumeMethod,
+    pub volume_error_cm3: f64,
     pub surface_area_cm2: f64,
+    pub bounding_box: BoundingBox,
+    pub dimensions_mm: [f64; 3],
+    pub fits_build_volume: bool,
+    pub mesh_validation: MeshValidation,
+    pub mass_properties: Option<MassProperties>,
+    pub objects: Vec<ObjectAnalysis>,
+    pub shells: Vec<Shell>,
+}
+
+impl UploadResponse {
+    fn new(file: FileRecord, analysis: GeometryAnalysis) -> Self {
+        Self {
+            file_id: file.id,
+            filename: file.filename,
+            file_format: file.file_format,
+            units: file.units,
+            suggested_units: file.suggested_units,
+            status: file.status,
+            volume_cm3: analysis.volume_cm3,
+            volume_method: analysis.volume_method,
+            volume_error_cm3: analysis.volume_error_cm3,
+            surface_area_cm2: analysis.surface_area_cm2,
+            bounding_box: analysis.bounding_box,
+            dimensions_mm: analysis.bounding_box.size(),
+            fits_build_volume: file.fits_build_volume.unwrap_or(false),
+            mesh_validation: analysis.validation,
+            mass_properties: analysis.mass_properties,
+            objects: analysis.objects,
+            shells: analysis.shells,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RepairResponse {
+    pub original_file_id: Uuid,
+    pub repair_report: RepairReport,
+    /// The repaired revision, or `None` when the model needed no fixes.
+    pub repaired_file: Option<UploadResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HollowResponse {
+    pub original_file_id: Uuid,
+    pub hollow_report: HollowReport,
+    pub hollowed_file: UploadResponse,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TransformResponse {
+    pub original_file_id: Uuid,
+    /// Combined transform as a row-major 4x4 matrix (millimetres).
+    pub matrix: [[f64; 4]; 4],
+    pub transformed_file: UploadResponse,
+}
+
+/// A model about to be stored and recorded in `files`.
+struct NewFile {
+    filename: String,
+    content_type: String,
+    format: ModelFormat,
+    data: Bytes,
+    units: LengthUnit,
+    parent_file_id: Option<Uuid>,
+    revision_kind: Option<&'static str>,
+    repair_report: Option<RepairReport>,
+}
+
+/// A model's analysis plus the renderings stored next to it.
+pub(crate) struct ProcessedModel {
+    pub(crate) analysis: GeometryAnalysis,
+    thumbnail: Bytes,
+    preview: Bytes,
+}
+
+impl ProcessedModel {
+    /// Renders the thumbnail and viewer preview of `mesh` (in millimetres). CPU-bound.
+    pub(crate) fn new(mesh: &Mesh, analysis: GeometryAnalysis) -> Self {
+        Self {
+            analysis,
+            thumbnail: Bytes::from(render_thumbnail(mesh, THUMBNAIL_SIZE)),
+            preview: Bytes::from(decimate(mesh, PREVIEW_MAX_TRIANGLES).to_stl_bytes()),
+        }
+    }
+
+    /// A derived mesh to be stored as an STL revision: its STL bytes and processing.
+    fn revision(mesh: &Mesh) -> (Bytes, Self) {
+        (Bytes::from(mesh.to_stl_bytes()), Self::new(mesh, analysis::analyze_mesh(mesh)))
+    }
+
+    /// Uploads the thumbnail and viewer preview of file `file_id` to storage.
+    pub(crate) async fn upload_renders(&self, storage: &dyn StorageService, file_id: Uuid) -> Result<RenderPaths, (StatusCode, String)> {
+        let thumbnail = storage.upload_file(&format!("{}_thumbnail.png", file_id), self.thumbnail.clone(), "image/png").await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+        let preview = storage.upload_file(&format!("{}_preview.stl", file_id), self.preview.clone(), ModelFormat::Stl.content_type()).await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+        Ok(RenderPaths { thumbnail, preview })
+    }
+}
+
+/// Storage paths of a file's thumbnail and viewer preview.
+pub(crate) struct RenderPaths {
+    thumbnail: String,
+    preview: String,
+}
+
+/// Active printers, optionally restricted to one technology (`FDM`, `RESIN`).
+pub(crate) async fn active_printers<'e>(executor: impl PgExecutor<'e>, technology: Option<&str>) -> Result<Vec<Printer>, sqlx::Error> {
+    sqlx::query_as::<_, Printer>(
+        "SELECT * FROM printers WHERE active AND ($1::VARCHAR IS NULL OR technology = $1) ORDER BY name"
+    )
+    .bind(technology)
+    .fetch_all(executor)
+    .await
 }
 
+/// Uploads a model to storage under a unique name and returns its path.
+async fn upload_model(storage: &dyn StorageService, file: &NewFile) -> Result<String, (StatusCode, String)> {
+    let unique_filename = format!("{}_{}", Uuid::new_v4(), file.filename);
+    storage.upload_file(&unique_filename, file.data.clone(), &file.content_type).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))
+}
+
+/// Inserts the `files` row of a model already uploaded to `gcs_path`, not yet analyzed.
+async fn insert_file<'e>(
+    executor: impl PgExecutor<'e>,
+    file_id: Uuid,
+    user_id: Uuid,
+    file: NewFile,
+    gcs_path: String,
+) -> Result<FileRecord, (StatusCode, String)> {
+    let file_size = file.data.len() as i64;
+    sqlx::query_as::<_, FileRecord>(&format!(
+        r#"
+        INSERT INTO files (id, user_id, filename, gcs_path, file_size_bytes, file_format, units, parent_file_id, revision_kind, repair_report, status)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        RETURNING {}
+        "#,
+        FILE_RECORD_COLUMNS
+    ))
+    .bind(file_id)
+    .bind(user_id)
+    .bind(&file.filename)
+    .bind(gcs_path)
+    .bind(file_size)
+    .bind(file.format.as_str())
+    .bind(file.units.as_str())
+    .bind(file.parent_file_id)
+    .bind(file.revision_kind)
+    .bind(file.repair_report.map(DbJson))
+    .bind(STATUS_PENDING)
+    .fetch_one(executor)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Checks the fit against our machines and records the analysis, with the
+/// already uploaded renderings, on the file's row.
+pub(crate) async fn record_analysis(
+    conn: &mut PgConnection,
+    file_id: Uuid,
+    suggested_units: Option<LengthUnit>,
+    processed: &ProcessedModel,
+    renders: RenderPaths,
+) -> Result<FileRecord, (StatusCode, String)> {
+    let analysis = &processed.analysis;
+    let printers = active_printers(&mut *conn, None).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let fits_build_volume = printers.iter().any(|p| analysis.bounding_box.fits_within(p.build_volume()));
+
+
+    let bbox = analysis.bounding_box;
+    sqlx::query_as::<_, FileRecord>(&format!(
+        r#"
+        UPDATE files SET volume_cm3 = $2, surface_area_cm2 = $3, suggested_units = $4, objects = $5, shells = $6,
+            bbox_min_x = $7, bbox_min_y = $8, bbox_min_z = $9, bbox_max_x = $10, bbox_max_y = $11, bbox_max_z = $12,
+            fits_build_volume = $13, mesh_validation = $14, thumbnail_path = $15, preview_path = $16, status = $17, analysis_error = NULL, analysis_failure = NULL,
+            volume_method = $18, volume_error_cm3 = $19, mass_properties = $20
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        FILE_RECORD_COLUMNS
+    ))
+    .bind(file_id)
+    .bind(analysis.volume_cm3)
+    .bind(analysis.surface_area_cm2)
+    .bind(suggested_units.map(|u| u.as_str()))
+    .bind(DbJson(&analysis.objects))
+    .bind(DbJson(&analysis.shells))
+    .bind(bbox.min[0])
+    .bind(bbox.min[1])
+    .bind(bbox.min[2])
+    .bind(bbox.max[0])
+    .bind(bbox.max[1])
+    .bind(bbox.max[2])
+    .bind(fits_build_volume)
+    .bind(DbJson(&analysis.validation))
+    .bind(renders.thumbnail)
+    .bind(renders.preview)
+    .bind(&analysis.validation.status)
+    .bind(analysis.volume_method.as_str())
+    .bind(analysis.volume_error_cm3)
+    .bind(analysis.mass_properties.as_ref().map(DbJson))
+    .fetch_one(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// `part.obj` -> `part_repaired.stl`; revisions are always stored as STL.
+fn revision_filename(filename: &str, suffix: &str) -> String {
+    let stem = std::path::Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("model");
+    format!("{}_{}.stl", stem, suffix)
+}
+
+/// Stores a revision derived on the spot, analysis included. Everything goes
+/// to storage first, then the row is inserted already analyzed, so a failure
+/// leaves no revision behind rather than one stuck in `PENDING`.
+async fn store_analyzed_file(
+    pool: &PgPool,
+    storage: &dyn StorageService,
+    user_id: Uuid,
+    file: NewFile,
+    processed: &ProcessedModel,
+) -> Result<FileRecord, (StatusCode, String)> {
+    let file_id = Uuid::new_v4();
+    let gcs_path = upload_model(storage, &file).await?;
+    let renders = processed.upload_renders(storage, file_id).await?;
+
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    insert_file(&mut *tx, file_id, user_id, file, gcs_path).await?;
+    let record = record_analysis(&mut tx, file_id, None, processed, renders).await?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(record)
+}
+
+/// Loads a file the user owns (admins may access any file).
+async fn fetch_owned_file(pool: &PgPool, user: &User, file_id: Uuid) -> Result<FileRecord, (StatusCode, String)> {
+    let file = sqlx::query_as::<_, FileRecord>(&format!("SELECT {} FROM files WHERE id = $1", FILE_RECORD_COLUMNS))
+        .bind(file_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((StatusCode::NOT_FOUND, "File not found".to_string()))?;
+
+    if file.user_id != user.id && user.role != "ADMIN" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+    Ok(file)
+}
+
+/// A stored model's bytes, downloaded but not yet parsed.
+pub(crate) struct StoredModel {
+    data: Bytes,
+    format: ModelFormat,
+    units: LengthUnit,
+}
+
+impl StoredModel {
+    /// Parses the model, with its 3MF objects, into millimetres. CPU-bound: call it on the compute pool.
+    pub(crate) fn model(&self) -> Result<Model, AnalysisError> {
+        analysis::load_model_in_units(&self.data, self.format, self.units)
+    }
+
+    /// Parses the model into a mesh in millimetres. CPU-bound: call it on the compute pool.
+    pub(crate) fn mesh(&self) -> Result<Mesh, AnalysisError> {
+        let mesh = analysis::load_mesh(&self.data, self.format)?;
+        Ok(match self.units {
+            LengthUnit::Millimeter => mesh,
+            units => mesh.scaled(units.mm_per_unit()),
+        })
+    }
+}
+
+/// Downloads a stored model for parsing on the compute pool.
+pub(crate) async fn fetch_stored_model(
+    storage: &dyn StorageService,
+    gcs_path: &str,
+    file_format: &str,
+    units: &str,
+) -> Result<StoredModel, (StatusCode, String)> {
+    let format = ModelFormat::parse(file_format)
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, format!("Unknown file format: {}", file_format)))?;
+    let units = LengthUnit::parse(units)
+        .ok_or((StatusCode::INTERNAL_SERVER_ERROR, format!("Unknown units: {}", units)))?;
+    let data = storage.download_file(gcs_path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+    Ok(StoredModel { data, format, units })
+}
+
+/// Overhang angle from the request, else the material default, else the global default.
+pub(crate) fn overhang_angle(requested: Option<f64>, material: Option<&Material>) -> Result<f64, (StatusCode, String)> {
+    let angle = requested
+        .or(material.map(|m| m.overhang_angle_deg()))
+        .unwrap_or(analysis::supports::DEFAULT_OVERHANG_ANGLE_DEG);
+    if !(0.0..=90.0).contains(&angle) {
+        return Err((StatusCode::BAD_REQUEST, "overhang_angle must be between 0 and 90 degrees".to_string()));
+    }
+    Ok(angle)
+}
+
+/// Stores an upload and queues it for analysis; poll `GET /api/files/:id/analysis` for the result.
 pub async fn upload_file(
     State(pool): State<PgPool>,
     State(storage): State<Arc<dyn StorageService>>,
+    State(jobs): State<AnalysisQueue>,
     Extension(user): Extension<User>,
     mut multipart: Multipart,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
+    let mut upload = None;
+    let mut declared_units = None;
     while let Some(field) = multipart.next_field().await.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))? {
+        if field.name() == Some("units") {
+            let value = field.text().await.map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+            declared_units = Some(LengthUnit::parse(value.trim())
+                .ok_or((StatusCode::BAD_REQUEST, "units must be one of mm, cm, m, in".to_string()))?);
+            continue;
+        }
         let filename = field.file_name().unwrap_or("unknown.stl").to_string();
         let content_type = field.content_type().unwrap_or("application/octet-stream").to_string();
         let data = field.bytes().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        upload = Some((filename, content_type, data));
+    }
+    let Some((filename, content_type, data)) = upload else {
+        return Err(ApiError::Text(StatusCode::BAD_REQUEST, "No file provided".to_string()));
+    };
 
-        if data.len() > MAX_FILE_SIZE {
-            return Err((StatusCode::PAYLOAD_TOO_LARGE, "File size exceeds 100MB limit".to_string()));
-        }
-
-        // 1. Analyze Geometry
-        let analysis = analysis::analyze_stl(&data).map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid STL: {}", e)))?;
+    if data.len() > MAX_FILE_SIZE {
+        return Err(ApiError::Text(StatusCode::PAYLOAD_TOO_LARGE, "File size exceeds 100MB limit".to_string()));
+    }
 
-        // 2. Upload to Storage
-        let unique_filename = format!("{}_{}", Uuid::new_v4(), filename);
-        let gcs_path = storage.upload_file(&unique_filename, data.clone(), &content_type).await
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+    let format = ModelFormat::detect(&filename, &data).ok_or(AnalysisError::UnsupportedFormat)?;
+    // 3MF declares its own unit and is already converted to millimetres on import
+    if format == ModelFormat::ThreeMf && declared_units.is_some_and(|u| u != LengthUnit::Millimeter) {
+        return Err(ApiError::Text(StatusCode::BAD_REQUEST, "3MF files declare their own units".to_string()));
+    }
+    // Only second-guess the scale when the customer didn't state it
+    let detect_units = declared_units.is_none() && matches!(format, ModelFormat::Stl | ModelFormat::Obj);
 
-        let file_record = sqlx::query_as::<_, FileRecord>(
-            r#"
-            INSERT INTO files (user_id, filename, gcs_path, file_size_bytes, volume_cm3, surface_area_cm2, status)
-            VALUES ($1, $2, $3, $4, $5, $6, 'ANALYZED')
-            RETURNING id, filename, volume_cm3, surface_area_cm2, status, created_at
-            "#
-        )
-        .bind(user.id)
-        .bind(&filename)
-        .bind(gcs_path)
-        .bind(data.len() as i64)
-        .bind(analysis.volume_cm3)
-        .bind(analysis.surface_area_cm2)
-        .fetch_one(&pool)
-        .await
+    let file = NewFile {
+        filename,
+        content_type,
+        format,
+        data,
+        units: declared_units.unwrap_or(LengthUnit::Millimeter),
+        parent_file_id: None,
+        revision_kind: None,
+        repair_report: None,
+    };
+    let gcs_path = upload_model(storage.as_ref(), &file).await?;
+    let mut tx = pool.begin().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let file_record = insert_file(&mut *tx, Uuid::new_v4(), user.id, file, gcs_path).await?;
+    jobs.enqueue(&mut *tx, file_record.id, detect_units).await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    tx.commit().await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    jobs.wake();
+
+    Ok((StatusCode::ACCEPTED, Json(FileAnalysisResponse::from(file_record))))
+}
+
+/// Runs automatic repair on a file and stores the result as a new STL revision.
+pub async fn repair_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+) -> Result<impl IntoResponse, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
 
-        return Ok((StatusCode::CREATED, Json(UploadResponse {
-            file_id: file_record.id,
-            filename: file_record.filename,
-            volume_cm3: file_record.volume_cm3.unwrap_or(0.0),
-            surface_area_cm2: file_record.surface_area_cm2.unwrap_or(0.0),
+    let (report, repaired) = compute
+        .run(move || {
+            let (mesh, report) = repair::repair_mesh(&stored.mesh()?);
+            if !report.has_changes() {
+                return Ok((report, None));
+            }
+            if mesh.faces.is_empty() {
+                return Err(ApiError::Text(StatusCode::UNPROCESSABLE_ENTITY, "Model cannot be repaired".to_string()));
+            }
+            Ok((report, Some(ProcessedModel::revision(&mesh))))
+        })
+        .await??;
+    let Some((data, processed)) = repaired else {
+        return Ok((StatusCode::OK, Json(RepairResponse {
+            original_file_id: file.id,
+            repair_report: report,
+            repaired_file: None,
         })));
+    };
+
+    let repaired = store_analyzed_file(&pool, storage.as_ref(), file.user_id, NewFile {
+        filename: revision_filename(&file.filename, "repaired"),
+        content_type: ModelFormat::Stl.content_type().to_string(),
+        format: ModelFormat::Stl,
+        data,
+        units: LengthUnit::Millimeter,
+        parent_file_id: Some(file.id),
+        revision_kind: Some(REVISION_REPAIR),
+        repair_report: Some(report.clone()),
+    }, &processed).await?;
+
+    Ok((StatusCode::CREATED, Json(RepairResponse {
+        original_file_id: file.id,
+        repair_report: report,
+        repaired_file: Some(UploadResponse::new(repaired, processed.analysis)),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RescaleRequest {
+    /// Units the model was really authored in.
+    pub units: Option<LengthUnit>,
+    /// Or an explicit scale factor.
+    pub scale: Option<f64>,
+}
+
+/// Stores a rescaled copy of a file, in millimetres, as a new STL revision.
+pub async fn rescale_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Json(req): Json<RescaleRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let factor = match (req.units, req.scale) {
+        (Some(units), None) => {
+            let current = LengthUnit::parse(&file.units)
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, format!("Unknown units: {}", file.units)))?;
+            units.mm_per_unit() / current.mm_per_unit()
+        }
+        (None, Some(scale)) if scale.is_finite() && scale > 0.0 => scale,
+        (None, Some(_)) => return Err(ApiError::Text(StatusCode::BAD_REQUEST, "scale must be a positive number".to_string())),
+        _ => return Err(ApiError::Text(StatusCode::BAD_REQUEST, "Provide either units or scale".to_string())),
+    };
+    if factor == 1.0 {
+        return Err(ApiError::Text(StatusCode::BAD_REQUEST, "Model is already at that scale".to_string()));
     }
 
-    Err((StatusCode::BAD_REQUEST, "No file provided".to_string()))
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    let (data, processed) = compute
+        .run(move || Ok::<_, ApiError>(ProcessedModel::revision(&stored.mesh()?.scaled(factor))))
+        .await??;
+    let rescaled = store_analyzed_file(&pool, storage.as_ref(), file.user_id, NewFile {
+        filename: revision_filename(&file.filename, "rescaled"),
+        content_type: ModelFormat::Stl.content_type().to_string(),
+        format: ModelFormat::Stl,
+        data,
+        units: LengthUnit::Millimeter,
+        parent_file_id: Some(file.id),
+        revision_kind: Some(REVISION_RESCALE),
+        repair_report: None,
+    }, &processed).await?;
+
+    Ok((StatusCode::CREATED, Json(UploadResponse::new(rescaled, processed.analysis))))
+}
+
+/// Stores a hollowed copy of a file (for resin printing) as a new STL revision.
+pub async fn hollow_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    payload: Option<Json<HollowSettings>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let settings = payload.map(|Json(settings)| settings).unwrap_or_default();
+    settings.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+
+    let (report, (data, processed)) = compute
+        .run(move || {
+            let (mesh, report) = hollow_mesh(&stored.mesh()?, &settings).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+            Ok::<_, ApiError>((report, ProcessedModel::revision(&mesh)))
+        })
+        .await??;
+    let hollowed = store_analyzed_file(&pool, storage.as_ref(), file.user_id, NewFile {
+        filename: revision_filename(&file.filename, "hollowed"),
+        content_type: ModelFormat::Stl.content_type().to_string(),
+        format: ModelFormat::Stl,
+        data,
+        units: LengthUnit::Millimeter,
+        parent_file_id: Some(file.id),
+        revision_kind: Some(REVISION_HOLLOW),
+        repair_report: None,
+    }, &processed).await?;
+
+    Ok((StatusCode::CREATED, Json(HollowResponse {
+        original_file_id: file.id,
+        hollow_report: report,
+        hollowed_file: UploadResponse::new(hollowed, processed.analysis),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TransformRequest {
+    pub steps: Vec<TransformStep>,
+}
+
+/// Stores a scaled, rotated, mirrored or moved copy of a file as a new STL revision.
+pub async fn transform_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Json(req): Json<TransformRequest>,
+) -> Result<impl IntoResponse, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+
+    let (matrix, (data, processed)) = compute
+        .run(move || {
+            let (mesh, matrix) = transform_mesh(&stored.mesh()?, &req.steps).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            Ok::<_, ApiError>((matrix, ProcessedModel::revision(&mesh)))
+        })
+        .await??;
+    let transformed = store_analyzed_file(&pool, storage.as_ref(), file.user_id, NewFile {
+        filename: revision_filename(&file.filename, "transformed"),
+        content_type: ModelFormat::Stl.content_type().to_string(),
+        format: ModelFormat::Stl,
+        data,
+        units: LengthUnit::Millimeter,
+        parent_file_id: Some(file.id),
+        revision_kind: Some(REVISION_TRANSFORM),
+        repair_report: None,
+    }, &processed).await?;
+
+    Ok((StatusCode::CREATED, Json(TransformResponse {
+        original_file_id: file.id,
+        matrix,
+        transformed_file: UploadResponse::new(transformed, processed.analysis),
+    })))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OrientationQuery {
+    pub material: Option<Material>,
+    pub overhang_angle: Option<f64>,
+}
+
+/// Searches for the print orientation needing the least support.
+pub async fn get_file_orientation(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Query(query): Query<OrientationQuery>,
+) -> Result<Json<OrientationSearch>, ApiError> {
+    let angle = overhang_angle(query.overhang_angle, query.material.as_ref())?;
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    compute
+        .run(move || Ok(Json(optimize_orientation(&stored.mesh()?, angle))))
+        .await?
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThicknessQuery {
+    pub material: Material,
+}
+
+/// Measures wall thickness against the minimum for a material.
+pub async fn get_file_thickness(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Query(query): Query<ThicknessQuery>,
+) -> Result<Json<WallThickness>, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    let min_thickness = query.material.min_wall_thickness_mm();
+    compute
+        .run(move || Ok(Json(analyze_wall_thickness(&stored.mesh()?, min_thickness))))
+        .await?
+}
+
+/// Sealed cavities that would trap resin, with drain holes to drill.
+pub async fn get_file_drainage(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+) -> Result<Json<ResinDrainage>, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    compute
+        .run(move || Ok(Json(analyze_drainage(&stored.mesh()?))))
+        .await?
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SliceQuery {
+    pub layer_height: Option<f64>, // mm, default 0.2
+}
+
+/// Per-layer contours and cross-section areas.
+pub async fn get_file_slices(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Query(query): Query<SliceQuery>,
+) -> Result<Json<SlicedModel>, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    let layer_height = query.layer_height.unwrap_or(0.2);
+    compute
+        .run(move || slice_mesh(&stored.mesh()?, layer_height).map(Json).map_err(|e| ApiError::Text(StatusCode::BAD_REQUEST, e)))
+        .await?
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SectionQuery {
+    pub normal: Option<String>, // "x,y,z", default 0,0,1
+    pub offset: Option<f64>, // mm along the normal, default through the middle of the model
+    pub format: Option<String>, // json (default) or svg
+}
+
+/// Cross-section of the file at an arbitrary plane, as JSON contours or an SVG drawing.
+pub async fn get_file_section(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Query(query): Query<SectionQuery>,
+) -> Result<Response, ApiError> {
+    let normal = match &query.normal {
+        Some(normal) => parse_vector(normal).ok_or((StatusCode::BAD_REQUEST, "normal must be three comma-separated numbers".to_string()))?,
+        None => [0.0, 0.0, 1.0],
+    };
+    let svg = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("svg") => true,
+        Some(other) => return Err(ApiError::Text(StatusCode::BAD_REQUEST, format!("Unsupported format: {}", other))),
+    };
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    let section = compute
+        .run(move || cross_section(&stored.mesh()?, normal, query.offset).map_err(|e| ApiError::Text(StatusCode::BAD_REQUEST, e)))
+        .await??;
+
+    Ok(if svg {
+        ([(header::CONTENT_TYPE, "image/svg+xml")], section_svg(&section)).into_response()
+    } else {
+        Json(section).into_response()
+    })
+}
+
+fn parse_vector(value: &str) -> Option<[f64; 3]> {
+    let parts: Vec<f64> = value.split(',').map(|part| part.trim().parse().ok()).collect::<Option<_>>()?;
+    parts.try_into().ok()
+}
+
+/// Point-to-point, wall thickness or overall extent measured on the full mesh.
+pub async fn measure_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+    Json(request): Json<MeasureRequest>,
+) -> Result<Json<Measurement>, ApiError> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
+    compute
+        .run(move || measure(&stored.mesh()?, &request).map(Json).map_err(|e| ApiError::Text(StatusCode::UNPROCESSABLE_ENTITY, e)))
+        .await?
+}
+
+pub async fn download_file(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let data = storage.download_file(&file.gcs_path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+
+    let content_type = ModelFormat::parse(&file.file_format)
+        .map(|f| f.content_type())
+        .unwrap_or("application/octet-stream");
+    let disposition = format!("attachment; filename=\"{}\"", file.filename.replace('"', ""));
+
+    Ok(([(header::CONTENT_TYPE, content_type.to_string()), (header::CONTENT_DISPOSITION, disposition)], data))
+}
+
+/// Rendered PNG preview of the file.
+pub async fn get_file_thumbnail(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let path = file.thumbnail_path.ok_or((StatusCode::NOT_FOUND, "No thumbnail for this file".to_string()))?;
+    let data = storage.download_file(&path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+
+    // Files are never modified in place, so neither is their thumbnail
+    Ok(([(header::CONTENT_TYPE, "image/png"), (header::CACHE_CONTROL, "private, max-age=86400")], data))
+}
+
+/// Decimated binary STL of the file for the web viewer.
+pub async fn get_file_preview(
+    State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    Extension(user): Extension<User>,
+    Path(file_id): Path<Uuid>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let file = fetch_owned_file(&pool, &user, file_id).await?;
+    let path = file.preview_path.ok_or((StatusCode::NOT_FOUND, "No preview for this file".to_string()))?;
+    let data = storage.download_file(&path).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", e)))?;
+
+    Ok(([(header::CONTENT_TYPE, ModelFormat::Stl.content_type()), (header::CACHE_CONTROL, "private, max-age=86400")], data))
 }
 
 pub async fn get_file_analysis(
@@ -90,16 +902,14 @@ pub async fn get_file_analysis(
     Extension(_user): Extension<User>,
     Path(file_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let file = sqlx::query_as::<_, FileRecord>(
-        "SELECT id, filename, volume_cm3, surface_area_cm2, status, created_at FROM files WHERE id = $1"
-    )
+    let file = sqlx::query_as::<_, FileRecord>(&format!("SELECT {} FROM files WHERE id = $1", FILE_RECORD_COLUMNS))
     .bind(file_id)
     .fetch_optional(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     match file {
-        Some(f) => Ok(Json(f)),
+        Some(f) => Ok(Json(FileAnalysisResponse::from(f))),
         None => Err((StatusCode::NOT_FOUND, "File not found".to_string())),
     }
 }
@@ -48,7 +48,8 @@ pub async fn list_orders(
     Extension(user): Extension<User>,
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
     let orders = sqlx::query_as::<_, OrderResponse>(
-        "SELECT id, status, created_at FROM orders WHERE user_id = $1 ORDER BY created_at DESC"
+        "SELECT o.id, o.status, o.created_at, q.file_id FROM orders o JOIN quotes q ON q.id = o.quote_id \
+         WHERE o.user_id = $1 ORDER BY o.created_at DESC"
     )
     .bind(user.id)
     .fetch_all(&pool)
@@ -4,55 +4,217 @@ use axum::{
     response::IntoResponse,
 };
 use sqlx::PgPool;
+use sqlx::types::Json as DbJson;
+use std::sync::Arc;
 use uuid::Uuid;
-use crate::quoting::{calculate_quote, QuoteRequest};
+use crate::analysis::{BoundingBox, Mesh};
+use crate::analysis::drainage::analyze_drainage;
+use crate::analysis::hollow::hollow_mesh;
+use crate::analysis::orientation::optimize_orientation;
+use crate::analysis::shells::{analyze_shells, part_mesh};
+use crate::analysis::supports::estimate_supports;
+use crate::analysis::slicer::slice_mesh;
+use crate::analysis::thickness::analyze_wall_thickness;
+use crate::analysis::toolpath::{estimate_print, MIN_LAYER_HEIGHT_MM};
+use crate::analysis::validation::STATUS_FAILED;
+use crate::jobs::STATUS_PENDING;
+use crate::compute::ComputePool;
+use crate::handlers::ApiError;
+use crate::handlers::files::{active_printers, fetch_stored_model, overhang_angle};
+use crate::models::Printer;
+use crate::quoting::{calculate_quote, Material, QuoteRequest, QuoteResponse};
+use crate::storage::StorageService;
 
 #[derive(sqlx::FromRow)]
 struct FileVolume {
     volume_cm3: Option<f64>,
+    gcs_path: String,
+    file_format: String,
+    units: String,
+    status: String,
+    bbox_min_x: Option<f64>,
+    bbox_min_y: Option<f64>,
+    bbox_min_z: Option<f64>,
+    bbox_max_x: Option<f64>,
+    bbox_max_y: Option<f64>,
+    bbox_max_z: Option<f64>,
+}
+
+impl FileVolume {
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        Some(BoundingBox {
+            min: [self.bbox_min_x?, self.bbox_min_y?, self.bbox_min_z?],
+            max: [self.bbox_max_x?, self.bbox_max_y?, self.bbox_max_z?],
+        })
+    }
 }
 
 pub async fn calculate_quote_handler(
     State(pool): State<PgPool>,
+    State(storage): State<Arc<dyn StorageService>>,
+    State(compute): State<ComputePool>,
     Json(payload): Json<QuoteRequest>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
+) -> Result<impl IntoResponse, ApiError> {
+    let overhang_angle = overhang_angle(payload.overhang_angle, Some(&payload.material))?;
+    let layer_height = payload.layer_height.unwrap_or(0.2);
+    let infill_percentage = payload.infill_percentage.unwrap_or(20);
+    if !(0..=100).contains(&infill_percentage) {
+        return Err(ApiError::Text(StatusCode::BAD_REQUEST, "infill_percentage must be between 0 and 100".to_string()));
+    }
+    if let Some(hollow) = &payload.hollow {
+        if payload.material != Material::Resin {
+            return Err(ApiError::Text(StatusCode::BAD_REQUEST, "Only resin parts can be hollowed; FDM prints use infill_percentage".to_string()));
+        }
+        hollow.validate().map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    }
+
     // 1. Fetch file volume
     let file = sqlx::query_as::<_, FileVolume>(
-        "SELECT volume_cm3 FROM files WHERE id = $1"
+        "SELECT volume_cm3, gcs_path, file_format, units, status, bbox_min_x, bbox_min_y, bbox_min_z, bbox_max_x, bbox_max_y, bbox_max_z FROM files WHERE id = $1"
     )
     .bind(payload.file_id)
     .fetch_optional(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
-    let volume = match file {
-        Some(f) => f.volume_cm3.ok_or((StatusCode::BAD_REQUEST, "File analysis not complete (volume missing)".to_string()))?,
-        None => return Err((StatusCode::NOT_FOUND, "File not found".to_string())),
-    };
+    let file = file.ok_or((StatusCode::NOT_FOUND, "File not found".to_string()))?;
+    if file.status == STATUS_PENDING {
+        return Err(ApiError::Text(StatusCode::CONFLICT, "File is still being analyzed".to_string()));
+    }
+    let volume = file.volume_cm3.ok_or((StatusCode::BAD_REQUEST, "File analysis not complete (volume missing)".to_string()))?;
+
+    if file.status == STATUS_FAILED {
+        return Err(ApiError::Text(StatusCode::UNPROCESSABLE_ENTITY, "Model does not enclose a printable volume".to_string()));
+    }
 
-    // 2. Calculate quote
-    let mut response = calculate_quote(volume, &payload.material);
+    // 2. Machines that could print this material, and the stored model
+    let printers = active_printers(&pool, Some(payload.material.technology())).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let stored = fetch_stored_model(storage.as_ref(), &file.gcs_path, &file.file_format, &file.units).await?;
 
-    // 3. Save quote to DB
+    // 3-5. Geometry and pricing run on the compute pool
+    let settings = PrintSettings { volume, bbox: file.bounding_box(), overhang_angle, layer_height, infill_percentage };
+    let request = payload.clone();
+    let mut response = compute
+        .run(move || Ok::<_, ApiError>(price_model(stored.mesh()?, &request, printers, settings)?))
+        .await??;
+
+    // 6. Save quote to DB
     let quote_id = sqlx::query_scalar::<_, Uuid>(
         r#"
-        INSERT INTO quotes (file_id, material, color, layer_height, infill_percentage, estimated_cost)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO quotes (file_id, material, color, layer_height, infill_percentage, estimated_cost, shell_index, hollow)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         RETURNING id
         "#
     )
     .bind(payload.file_id)
     .bind(format!("{:?}", payload.material).to_uppercase()) // Enum to string
     .bind(&payload.color)
-    .bind(payload.layer_height.unwrap_or(0.2))
-    .bind(payload.infill_percentage.unwrap_or(20))
+    .bind(layer_height)
+    .bind(infill_percentage)
     .bind(response.estimated_cost)
+    .bind(payload.shell.map(|index| index as i32))
+    .bind(payload.hollow.as_ref().map(DbJson))
     .fetch_one(&pool)
     .await
     .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
 
     response.id = quote_id;
 
-    // 4. Return response
+    // 7. Return response
     Ok(Json(response))
 }
+
+/// Quote inputs resolved from the request and the stored analysis.
+struct PrintSettings {
+    volume: f64,
+    bbox: Option<BoundingBox>,
+    overhang_angle: f64,
+    layer_height: f64,
+    infill_percentage: i32,
+}
+
+/// Narrows the mesh to the requested part, orients it if asked, checks it fits
+/// one of `printers`, then estimates supports, toolpaths and the price.
+fn price_model(
+    mut mesh: Mesh,
+    payload: &QuoteRequest,
+    printers: Vec<Printer>,
+    settings: PrintSettings,
+) -> Result<QuoteResponse, (StatusCode, String)> {
+    let PrintSettings { mut volume, mut bbox, overhang_angle, layer_height, infill_percentage } = settings;
+    if let Some(index) = payload.shell {
+        let shells = analyze_shells(&mesh);
+        let shell = shells.get(index).filter(|shell| !shell.is_void)
+            .ok_or((StatusCode::BAD_REQUEST, format!("Shell {} is not a part of this model", index)))?;
+        volume = shell.net_volume_cm3;
+        bbox = Some(shell.bounding_box);
+        mesh = part_mesh(&mesh, &shells, index);
+    }
+
+    // 3. Re-orient first when asked, so the fit check sees the part as printed
+    let orientation = payload
+        .optimize_orientation
+        .unwrap_or(false)
+        .then(|| optimize_orientation(&mesh, overhang_angle).best);
+
+    // Refuse to quote parts no machine for this material can print. A re-oriented
+    // part must fit as it will be printed; otherwise it may be turned onto any side.
+    let mut printer = None;
+    let oriented_bbox = orientation.as_ref().map(|candidate| mesh.rotated(&candidate.rotation).bounding_box());
+    if let Some(bbox) = oriented_bbox.or(bbox) {
+        let fits = |p: &Printer| match oriented_bbox {
+            Some(_) => bbox.size().iter().zip(p.build_volume()).all(|(d, limit)| *d <= limit),
+            None => bbox.fits_within(p.build_volume()),
+        };
+        printer = printers.into_iter().find(fits);
+        if printer.is_none() {
+            let [x, y, z] = bbox.size();
+            return Err((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Model ({:.1} x {:.1} x {:.1} mm) does not fit any {} printer build volume", x, y, z, payload.material.technology()),
+            ));
+        }
+    }
+
+    // Estimate supports in the print orientation
+    let supports = match &orientation {
+        Some(candidate) => candidate.supports.clone(),
+        None => estimate_supports(&mesh, overhang_angle),
+    };
+
+    // Hollow resin parts once supports are placed; none go inside the cavity
+    let mut hollow = None;
+    if let Some(settings) = &payload.hollow {
+        let (hollowed, report) = hollow_mesh(&mesh, settings).map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, e))?;
+        volume = (volume - report.cavity_volume_cm3).max(0.0);
+        mesh = hollowed;
+        hollow = Some(report);
+    }
+
+    // 4. Slice and estimate toolpaths on the machine that will print it (FDM only)
+    let rotated = orientation.as_ref().map(|candidate| mesh.rotated(&candidate.rotation));
+    let print_mesh = rotated.as_ref().unwrap_or(&mesh);
+    let print = match printer.filter(|p| p.technology == "FDM") {
+        Some(printer) => {
+            let profile = printer.machine_profile();
+            if !(MIN_LAYER_HEIGHT_MM..=profile.max_layer_height_mm()).contains(&layer_height) {
+                return Err((
+                    StatusCode::BAD_REQUEST,
+                    format!("layer_height must be between {} and {:.2} mm", MIN_LAYER_HEIGHT_MM, profile.max_layer_height_mm()),
+                ));
+            }
+            let sliced = slice_mesh(print_mesh, layer_height).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+            Some(estimate_print(&sliced, &profile, infill_percentage as f64))
+        }
+        None => None,
+    };
+
+    // 5. Calculate quote
+    let mut response = calculate_quote(volume, supports, print.as_ref(), &payload.material);
+    response.orientation = orientation;
+    response.hollow = hollow;
+    response.wall_thickness = Some(analyze_wall_thickness(&mesh, payload.material.min_wall_thickness_mm()));
+    response.resin_drainage = (payload.material == Material::Resin).then(|| analyze_drainage(&mesh));
+    Ok(response)
+}
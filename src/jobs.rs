@@ -0,0 +1,256 @@
+use std::sync::Arc;
+use std::time::Duration;
+use sqlx::{FromRow, PgConnection, PgExecutor, PgPool};
+use sqlx::types::Json as DbJson;
+use tokio::sync::Notify;
+use uuid::Uuid;
+use crate::analysis::error::AnalysisError;
+use crate::analysis::units::{detect_units, LengthUnit};
+use crate::analysis::validation::STATUS_FAILED;
+use crate::analysis::analyze_model;
+use crate::compute::{ComputeError, ComputePool};
+use crate::handlers::files::{fetch_stored_model, record_analysis, ProcessedModel};
+use crate::storage::StorageService;
+
+/// File status while its analysis job is queued or running.
+pub const STATUS_PENDING: &str = "PENDING";
+/// Background workers started when `ANALYSIS_WORKERS` isn't set.
+pub const DEFAULT_WORKERS: usize = 2;
+/// How often idle workers look for jobs nobody woke them for (e.g. queued by another instance).
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a claimed job stays with its worker before another worker may take it over.
+const LEASE: Duration = Duration::from_secs(600);
+/// Claims of one job before it is failed for good.
+const MAX_ATTEMPTS: i32 = 5;
+/// Wait before retrying a job after a storage failure, times the attempts so far.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Queue of uploads waiting for analysis, kept in the `analysis_jobs` table.
+///
+/// A worker claims a job by marking it `RUNNING` with a lease (`locked_until`)
+/// in its own short statement, analyzes the model without holding a
+/// transaction, then writes the results and the job's status together. A job
+/// whose worker dies (or whose server shuts down) is claimed again once its
+/// lease runs out; storage failures put it back in the queue with a delay.
+/// Either way it gives up after `MAX_ATTEMPTS` claims.
+#[derive(Clone)]
+pub struct AnalysisQueue {
+    wake: Arc<Notify>,
+}
+
+#[derive(Debug, FromRow)]
+struct QueuedJob {
+    id: Uuid,
+    file_id: Uuid,
+    detect_units: bool,
+    /// Claims so far, this one included.
+    attempts: i32,
+    gcs_path: String,
+    file_format: String,
+    units: String,
+}
+
+enum JobError {
+    /// The compute pool is saturated; leave the job queued and try later.
+    Busy,
+    /// Storage or the database failed; try the job again later.
+    Retry(String),
+    /// The model can't be read.
+    Invalid(AnalysisError),
+    Failed(String),
+}
+
+impl AnalysisQueue {
+    /// Spawns `ANALYSIS_WORKERS` background workers on the current runtime.
+    pub fn start(pool: PgPool, storage: Arc<dyn StorageService>, compute: ComputePool) -> Self {
+        let workers = std::env::var("ANALYSIS_WORKERS")
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .unwrap_or(DEFAULT_WORKERS);
+        let queue = Self { wake: Arc::new(Notify::new()) };
+        for _ in 0..workers.max(1) {
+            tokio::spawn(work(queue.clone(), pool.clone(), storage.clone(), compute.clone()));
+        }
+        queue
+    }
+
+    /// Queues analysis of a stored file. Call `wake` once the transaction commits.
+    pub async fn enqueue<'e>(&self, executor: impl PgExecutor<'e>, file_id: Uuid, detect_units: bool) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO analysis_jobs (file_id, detect_units) VALUES ($1, $2)")
+            .bind(file_id)
+            .bind(detect_units)
+            .execute(executor)
+            .await
+            .map(|_| ())
+    }
+
+    /// Tells an idle worker there is a new job.
+    pub fn wake(&self) {
+        self.wake.notify_one();
+    }
+}
+
+async fn work(queue: AnalysisQueue, pool: PgPool, storage: Arc<dyn StorageService>, compute: ComputePool) {
+    loop {
+        match run_next(&pool, storage.as_ref(), &compute).await {
+            Ok(true) => continue,
+            Ok(false) => {}
+            Err(e) => tracing::error!("Analysis worker: {}", e),
+        }
+        let _ = tokio::time::timeout(POLL_INTERVAL, queue.wake.notified()).await;
+    }
+}
+
+/// Claims and processes the oldest queued job. `Ok(false)` when there was nothing to do.
+async fn run_next(pool: &PgPool, storage: &dyn StorageService, compute: &ComputePool) -> Result<bool, sqlx::Error> {
+    let Some(job) = claim_next(pool).await? else {
+        return Ok(false);
+    };
+    if job.attempts > MAX_ATTEMPTS {
+        let reason = format!("Analysis did not finish after {} attempts", MAX_ATTEMPTS);
+        fail(pool, &job, &reason, None).await?;
+        return Ok(true);
+    }
+
+    let result = async {
+        let (processed, suggested_units) = analyze_file(storage, compute, &job).await?;
+        record(pool, storage, &job, suggested_units, &processed).await
+    };
+    match result.await {
+        Ok(()) => {}
+        Err(JobError::Busy) => {
+            release(pool, &job).await?;
+            return Ok(false);
+        }
+        Err(JobError::Retry(reason)) if job.attempts < MAX_ATTEMPTS => retry_later(pool, &job, &reason).await?,
+        Err(JobError::Retry(reason)) | Err(JobError::Failed(reason)) => fail(pool, &job, &reason, None).await?,
+        Err(JobError::Invalid(failure)) => fail(pool, &job, &failure.to_string(), Some(failure)).await?,
+    }
+    Ok(true)
+}
+
+/// Marks the oldest waiting job (or one whose lease ran out) `RUNNING` and
+/// returns it; the claim commits on its own.
+async fn claim_next(pool: &PgPool) -> Result<Option<QueuedJob>, sqlx::Error> {
+    sqlx::query_as::<_, QueuedJob>(
+        "WITH claimed AS ( \
+             UPDATE analysis_jobs SET status = 'RUNNING', attempts = attempts + 1, \
+                 locked_until = NOW() + make_interval(secs => $1) \
+             WHERE id = ( \
+                 SELECT id FROM analysis_jobs \
+                 WHERE status IN ('QUEUED', 'RUNNING') AND (locked_until IS NULL OR locked_until < NOW()) \
+                 ORDER BY created_at LIMIT 1 FOR UPDATE SKIP LOCKED \
+             ) \
+             RETURNING id, file_id, detect_units, attempts \
+         ) \
+         SELECT c.id, c.file_id, c.detect_units, c.attempts, f.gcs_path, f.file_format, f.units \
+         FROM claimed c JOIN files f ON f.id = c.file_id"
+    )
+    .bind(LEASE.as_secs_f64())
+    .fetch_optional(pool)
+    .await
+}
+
+/// Sets the final status of a job this worker still holds. `Ok(false)` when its
+/// lease ran out and another worker claimed it in the meantime.
+async fn finish(conn: &mut PgConnection, job: &QueuedJob, status: &str, error: Option<&str>) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE analysis_jobs SET status = $3, error = $4, locked_until = NULL, finished_at = NOW() \
+         WHERE id = $1 AND attempts = $2 AND status = 'RUNNING'"
+    )
+    .bind(job.id)
+    .bind(job.attempts)
+    .bind(status)
+    .bind(error)
+    .execute(conn)
+    .await?;
+    Ok(result.rows_affected() == 1)
+}
+
+/// Uploads the renderings, then writes the analysis and marks the job done in one transaction.
+async fn record(
+    pool: &PgPool,
+    storage: &dyn StorageService,
+    job: &QueuedJob,
+    suggested_units: Option<LengthUnit>,
+    processed: &ProcessedModel,
+) -> Result<(), JobError> {
+    let renders = processed.upload_renders(storage, job.file_id).await
+        .map_err(|(_, e)| JobError::Retry(e))?;
+    let retry = |e: sqlx::Error| JobError::Retry(e.to_string());
+    let mut tx = pool.begin().await.map_err(retry)?;
+    record_analysis(&mut tx, job.file_id, suggested_units, processed, renders).await
+        .map_err(|(_, e)| JobError::Retry(e))?;
+    // Dropping the transaction discards the results of a job taken over by another worker
+    if finish(&mut tx, job, "DONE", None).await.map_err(retry)? {
+        tx.commit().await.map_err(retry)?;
+    }
+    Ok(())
+}
+
+/// Puts a job back in the queue without counting the claim.
+async fn release(pool: &PgPool, job: &QueuedJob) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE analysis_jobs SET status = 'QUEUED', attempts = attempts - 1, locked_until = NULL WHERE id = $1 AND attempts = $2 AND status = 'RUNNING'")
+        .bind(job.id)
+        .bind(job.attempts)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+/// Puts a job back in the queue, to be claimed again after a growing delay.
+async fn retry_later(pool: &PgPool, job: &QueuedJob, reason: &str) -> Result<(), sqlx::Error> {
+    tracing::warn!("Analysis of file {} failed (attempt {} of {}), retrying: {}", job.file_id, job.attempts, MAX_ATTEMPTS, reason);
+    sqlx::query(
+        "UPDATE analysis_jobs SET status = 'QUEUED', error = $3, locked_until = NOW() + make_interval(secs => $4) \
+         WHERE id = $1 AND attempts = $2 AND status = 'RUNNING'"
+    )
+    .bind(job.id)
+    .bind(job.attempts)
+    .bind(reason)
+    .bind(RETRY_DELAY.as_secs_f64() * job.attempts as f64)
+    .execute(pool)
+    .await
+    .map(|_| ())
+}
+
+/// Fails the file and its job in one transaction.
+async fn fail(pool: &PgPool, job: &QueuedJob, reason: &str, failure: Option<AnalysisError>) -> Result<(), sqlx::Error> {
+    tracing::warn!("Analysis of file {} failed: {}", job.file_id, reason);
+    let mut tx = pool.begin().await?;
+    if !finish(&mut tx, job, "FAILED", Some(reason)).await? {
+        return Ok(());
+    }
+    sqlx::query("UPDATE files SET status = $2, analysis_error = $3, analysis_failure = $4 WHERE id = $1")
+        .bind(job.file_id)
+        .bind(STATUS_FAILED)
+        .bind(reason)
+        .bind(failure.map(DbJson))
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Parses and analyzes the stored model. Returns the results to record and
+/// the units to suggest, if any.
+async fn analyze_file(storage: &dyn StorageService, compute: &ComputePool, job: &QueuedJob) -> Result<(ProcessedModel, Option<LengthUnit>), JobError> {
+    let stored = fetch_stored_model(storage, &job.gcs_path, &job.file_format, &job.units).await
+        .map_err(|(_, e)| JobError::Retry(e))?;
+    let detect = job.detect_units;
+    let (processed, detected_units) = compute
+        .run(move || {
+            stored.model().map(|model| {
+                let detected_units = detect.then(|| detect_units(&model.mesh));
+                (ProcessedModel::new(&model.mesh, analyze_model(&model)), detected_units)
+            })
+        })
+        .await
+        .map_err(|e| match e {
+            ComputeError::QueueFull => JobError::Busy,
+            ComputeError::Failed => JobError::Failed("Analysis failed".to_string()),
+        })?
+        .map_err(JobError::Invalid)?;
+
+    let suggested_units = detected_units.filter(|&u| LengthUnit::parse(&job.units) != Some(u));
+    Ok((processed, suggested_units))
+}
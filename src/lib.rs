@@ -3,6 +3,8 @@ pub mod handlers;
 pub mod models;
 pub mod middleware;
 pub mod analysis;
+pub mod compute;
+pub mod jobs;
 pub mod storage;
 pub mod quoting;
 
@@ -19,11 +21,23 @@ use utoipa_swagger_ui::SwaggerUi;
 use sqlx::PgPool;
 use std::sync::Arc;
 use storage::StorageService;
+use compute::ComputePool;
+use jobs::AnalysisQueue;
 
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
     pub storage: Arc<dyn StorageService>,
+    pub compute: ComputePool,
+    pub jobs: AnalysisQueue,
+}
+
+impl AppState {
+    /// Builds the state and starts the background analysis workers.
+    pub fn new(pool: PgPool, storage: Arc<dyn StorageService>, compute: ComputePool) -> Self {
+        let jobs = AnalysisQueue::start(pool.clone(), storage.clone(), compute.clone());
+        Self { pool, storage, compute, jobs }
+    }
 }
 
 impl FromRef<AppState> for PgPool {
@@ -38,6 +52,18 @@ impl FromRef<AppState> for Arc<dyn StorageService> {
     }
 }
 
+impl FromRef<AppState> for ComputePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.compute.clone()
+    }
+}
+
+impl FromRef<AppState> for AnalysisQueue {
+    fn from_ref(state: &AppState) -> Self {
+        state.jobs.clone()
+    }
+}
+
 #[derive(Debug, Serialize, ToSchema)]
 pub struct GreetingResponse {
     pub message: String,
@@ -94,10 +120,24 @@ pub fn create_app(state: AppState) -> Router {
         .route("/api/files/upload", post(handlers::files::upload_file).layer(from_fn(middleware::auth_middleware)))
         .route("/api/files/:id/analysis", get(handlers::files::get_file_analysis).layer(from_fn(middleware::auth_middleware)))
         .route("/api/files/:id/quoting", get(handlers::files::get_file_quoting).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/repair", post(handlers::files::repair_file).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/hollow", post(handlers::files::hollow_file).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/transform", post(handlers::files::transform_file).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/rescale", post(handlers::files::rescale_file).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/download", get(handlers::files::download_file).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/thumbnail", get(handlers::files::get_file_thumbnail).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/preview", get(handlers::files::get_file_preview).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/orientation", get(handlers::files::get_file_orientation).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/thickness", get(handlers::files::get_file_thickness).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/drainage", get(handlers::files::get_file_drainage).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/slices", get(handlers::files::get_file_slices).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/section", get(handlers::files::get_file_section).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/files/:id/measure", post(handlers::files::measure_file).layer(from_fn(middleware::auth_middleware)))
         .route("/api/quotes/calculate", post(handlers::quoting::calculate_quote_handler).layer(from_fn(middleware::auth_middleware)))
         .route("/api/orders", post(handlers::orders::create_order).get(handlers::orders::list_orders).layer(from_fn(middleware::auth_middleware)))
         .route("/api/admin/orders", get(handlers::admin::list_orders).layer(from_fn(middleware::auth_middleware)))
         .route("/api/admin/orders/:id/status", axum::routing::patch(handlers::admin::update_order_status).layer(from_fn(middleware::auth_middleware)))
+        .route("/api/admin/orders/:id/gcode", post(handlers::admin::generate_order_gcode).get(handlers::admin::download_order_gcode).layer(from_fn(middleware::auth_middleware)))
         .layer(DefaultBodyLimit::max(102 * 1024 * 1024))
         .layer(Extension(state.pool.clone()))
         .with_state(state)
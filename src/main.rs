@@ -3,7 +3,7 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use sqlx::postgres::PgPoolOptions;
 use dotenvy::dotenv;
 use std::env;
-use alpha3d::{create_app, AppState, storage::{LocalStorage, GcsStorage, StorageService}};
+use alpha3d::{create_app, AppState, compute::ComputePool, storage::{LocalStorage, GcsStorage, StorageService}};
 use std::sync::Arc;
 
 #[tokio::main]
@@ -40,10 +40,9 @@ async fn main() {
         Arc::new(LocalStorage::new(&path))
     };
 
-    let app_state = AppState {
-        pool,
-        storage,
-    };
+    let compute = ComputePool::from_env();
+
+    let app_state = AppState::new(pool, storage, compute);
 
     let app = create_app(app_state);
 
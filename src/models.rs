@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
+use crate::analysis::toolpath::MachineProfile;
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct User {
@@ -51,7 +52,13 @@ pub struct Order {
     pub status: String,
     pub shipping_address: sqlx::types::Json<serde_json::Value>,
     pub tracking_number: Option<String>,
+    /// Generated G-code, once an operator has sliced the order.
+    pub gcode_path: Option<String>,
+    pub gcode_printer_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
+    /// The quoted file, filled in by queries that join `quotes`.
+    #[sqlx(default)]
+    pub file_id: Option<Uuid>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -65,4 +72,47 @@ pub struct OrderResponse {
     pub id: Uuid,
     pub status: String,
     pub created_at: DateTime<Utc>,
+    #[sqlx(default)]
+    pub file_id: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+pub struct Printer {
+    pub id: Uuid,
+    pub name: String,
+    pub technology: String,
+    pub build_x_mm: f64,
+    pub build_y_mm: f64,
+    pub build_z_mm: f64,
+    pub nozzle_diameter_mm: f64,
+    pub filament_diameter_mm: f64,
+    pub perimeter_speed_mm_s: f64,
+    pub infill_speed_mm_s: f64,
+    pub travel_speed_mm_s: f64,
+    pub acceleration_mm_s2: f64,
+    pub perimeters: i32,
+    pub solid_layers: i32,
+    /// Firmware flavor (`MARLIN`, `KLIPPER`) for generated G-code.
+    pub gcode_flavor: String,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Printer {
+    pub fn build_volume(&self) -> [f64; 3] {
+        [self.build_x_mm, self.build_y_mm, self.build_z_mm]
+    }
+
+    pub fn machine_profile(&self) -> MachineProfile {
+        MachineProfile {
+            nozzle_diameter_mm: self.nozzle_diameter_mm,
+            filament_diameter_mm: self.filament_diameter_mm,
+            perimeter_speed_mm_s: self.perimeter_speed_mm_s,
+            infill_speed_mm_s: self.infill_speed_mm_s,
+            travel_speed_mm_s: self.travel_speed_mm_s,
+            acceleration_mm_s2: self.acceleration_mm_s2,
+            perimeters: self.perimeters.max(0) as u32,
+            solid_layers: self.solid_layers.max(0) as u32,
+        }
+    }
 }
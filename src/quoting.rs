@@ -1,5 +1,11 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
+use crate::analysis::drainage::ResinDrainage;
+use crate::analysis::hollow::{HollowReport, HollowSettings};
+use crate::analysis::orientation::OrientationCandidate;
+use crate::analysis::supports::{SupportEstimate, DEFAULT_OVERHANG_ANGLE_DEG};
+use crate::analysis::thickness::WallThickness;
+use crate::analysis::toolpath::PrintEstimate;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "UPPERCASE")]
@@ -9,6 +15,61 @@ pub enum Material {
     Resin,
 }
 
+impl Material {
+    /// Parses the stored `quotes.material` value.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "PLA" => Some(Material::Pla),
+            "ABS" => Some(Material::Abs),
+            "RESIN" => Some(Material::Resin),
+            _ => None,
+        }
+    }
+
+    /// Printer technology (`printers.technology`) used for this material.
+    pub fn technology(&self) -> &'static str {
+        match self {
+            Material::Pla | Material::Abs => "FDM",
+            Material::Resin => "RESIN",
+        }
+    }
+
+    /// Default overhang angle (degrees from vertical) printed without support.
+    pub fn overhang_angle_deg(&self) -> f64 {
+        match self {
+            Material::Pla | Material::Abs => DEFAULT_OVERHANG_ANGLE_DEG,
+            // Peel forces on resin printers make unsupported overhangs fail sooner
+            Material::Resin => 30.0,
+        }
+    }
+
+    /// Thinnest wall (mm) we can reliably print.
+    pub fn min_wall_thickness_mm(&self) -> f64 {
+        match self {
+            // Two 0.4 mm perimeters
+            Material::Pla | Material::Abs => 0.8,
+            Material::Resin => 0.4,
+        }
+    }
+
+    /// Nozzle and bed temperatures (°C) for FDM materials.
+    pub fn print_temperatures_c(&self) -> Option<(u32, u32)> {
+        match self {
+            Material::Pla => Some((210, 60)),
+            Material::Abs => Some((245, 100)),
+            Material::Resin => None,
+        }
+    }
+
+    /// Fraction of the support region actually filled with material.
+    pub fn support_fill(&self) -> f64 {
+        match self {
+            Material::Pla | Material::Abs => 0.15,
+            Material::Resin => 0.05,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuoteRequest {
     pub file_id: Uuid,
@@ -16,6 +77,10 @@ pub struct QuoteRequest {
     pub color: String,
     pub layer_height: Option<f64>,     // mm, default 0.2
     pub infill_percentage: Option<i32>, // %, default 20
+    pub overhang_angle: Option<f64>,    // degrees from vertical, default per material
+    pub optimize_orientation: Option<bool>, // price in the best orientation, default false
+    pub shell: Option<usize>,               // quote one part of a multi-part upload, default all
+    pub hollow: Option<HollowSettings>,     // price a hollowed resin part, default solid
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,12 +89,26 @@ pub struct QuoteResponse {
     pub estimated_cost: f64,
     pub currency: String,
     pub breakdown: CostBreakdown,
+    pub supports: SupportEstimate,
+    /// Orientation the quote was priced in, when optimization was requested.
+    pub orientation: Option<OrientationCandidate>,
+    /// Wall thickness against the material minimum; thin walls are a warning, not an error.
+    pub wall_thickness: Option<WallThickness>,
+    /// Sealed cavities that would trap uncured resin, with suggested drain holes (resin only).
+    pub resin_drainage: Option<ResinDrainage>,
+    /// Cavity the price assumes, when hollowing was requested.
+    pub hollow: Option<HollowReport>,
+    /// Toolpath estimate the price is based on (FDM only).
+    pub print_estimate: Option<PrintEstimate>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostBreakdown {
+    /// Model and support material.
     pub material_cost: f64,
+    pub support_mass_g: f64,
     pub machine_cost: f64,
+    pub print_time_hours: f64,
     pub labor_cost: f64,
 }
 
@@ -37,22 +116,27 @@ pub struct CostBreakdown {
 /// 
 /// # Arguments
 /// * `volume_cm3` - The volume of the model in cubic centimeters.
+/// * `supports` - Support estimate for the print orientation.
+/// * `print` - Toolpath estimate; replaces the volume heuristics for time and material when present.
 /// * `material` - The selected material.
 /// 
 /// # Returns
 /// * `QuoteResponse` containing the calculated cost.
-pub fn calculate_quote(volume_cm3: f64, material: &Material) -> QuoteResponse {
+pub fn calculate_quote(volume_cm3: f64, supports: SupportEstimate, print: Option<&PrintEstimate>, material: &Material) -> QuoteResponse {
     let (density, cost_per_gram) = match material {
         Material::Pla => (1.24, 30.0),
         Material::Abs => (1.04, 40.0),
         Material::Resin => (1.1, 100.0),
     };
 
-    let weight_g = volume_cm3 * density;
+    // Extruded filament accounts for infill and shells; otherwise assume a solid part
+    let model_volume_cm3 = print.map_or(volume_cm3, |p| p.filament_volume_cm3);
+    let support_mass_g = supports.support_volume_cm3 * material.support_fill() * density;
+    let weight_g = model_volume_cm3 * density + support_mass_g;
     let material_cost = weight_g * cost_per_gram;
 
-    // Simple heuristic: 10 cm3 takes 1 hour
-    let print_time_hours = volume_cm3 / 10.0;
+    // Without a toolpath estimate: 10 cm3 takes 1 hour
+    let print_time_hours = print.map_or(volume_cm3 / 10.0, |p| p.print_time_hours());
     let machine_hourly_rate = 2000.0;
     let machine_cost = print_time_hours * machine_hourly_rate;
 
@@ -69,8 +153,16 @@ pub fn calculate_quote(volume_cm3: f64, material: &Material) -> QuoteResponse {
         breakdown: CostBreakdown {
             material_cost: (material_cost * 100.0).round() / 100.0,
             machine_cost: (machine_cost * 100.0).round() / 100.0,
+            print_time_hours: (print_time_hours * 100.0).round() / 100.0,
             labor_cost,
+            support_mass_g: (support_mass_g * 100.0).round() / 100.0,
         },
+        supports,
+        orientation: None,
+        wall_thickness: None,
+        resin_drainage: None,
+        hollow: None,
+        print_estimate: print.cloned(),
     }
 }
 
@@ -92,7 +184,7 @@ mod tests {
         // Total Base = 23720
         // Markup 1.5 = 35580
         
-        let quote = calculate_quote(volume, &material);
+        let quote = calculate_quote(volume, SupportEstimate::default(), None, &material);
         
         assert_eq!(quote.currency, "KRW");
         assert!((quote.estimated_cost - 35580.0).abs() < 1.0, "Cost should be around 35580, got {}", quote.estimated_cost);
@@ -112,9 +204,33 @@ mod tests {
         // Total Base = 15500
         // Markup 1.5 = 23250
         
-        let quote = calculate_quote(volume, &material);
+        let quote = calculate_quote(volume, SupportEstimate::default(), None, &material);
         
         assert_eq!(quote.currency, "KRW");
         assert!((quote.estimated_cost - 23250.0).abs() < 1.0, "Cost should be around 23250, got {}", quote.estimated_cost);
     }
+
+    #[test]
+    fn test_support_mass_is_charged_as_material() {
+        let supports = SupportEstimate { support_volume_cm3: 20.0, ..Default::default() };
+        let without = calculate_quote(100.0, SupportEstimate::default(), None, &Material::Pla);
+        let with = calculate_quote(100.0, supports, None, &Material::Pla);
+
+        // 20 cm3 of support region at 15% fill = 3 cm3 of PLA = 3.72 g
+        assert!((with.breakdown.support_mass_g - 3.72).abs() < 1e-9);
+        assert!((with.breakdown.material_cost - without.breakdown.material_cost - 3.72 * 30.0).abs() < 0.01);
+        assert_eq!(with.breakdown.machine_cost, without.breakdown.machine_cost);
+    }
+
+    #[test]
+    fn test_print_estimate_replaces_heuristics() {
+        let print = PrintEstimate { filament_volume_cm3: 40.0, print_time_s: 3.0 * 3600.0, ..Default::default() };
+        let quote = calculate_quote(100.0, SupportEstimate::default(), Some(&print), &Material::Pla);
+
+        // 40 cm3 * 1.24 g/cm3 * 30 KRW/g, 3 h * 2000 KRW/h
+        assert!((quote.breakdown.material_cost - 1488.0).abs() < 0.01);
+        assert!((quote.breakdown.machine_cost - 6000.0).abs() < 0.01);
+        assert_eq!(quote.breakdown.print_time_hours, 3.0);
+        assert_eq!(quote.print_estimate, Some(print));
+    }
 }
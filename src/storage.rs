@@ -7,6 +7,8 @@ use tokio::io::AsyncWriteExt;
 #[async_trait]
 pub trait StorageService: Send + Sync {
     async fn upload_file(&self, file_name: &str, content: Bytes, content_type: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>>;
+    /// Reads back an object by the path `upload_file` returned.
+    async fn download_file(&self, path: &str) -> Result<Bytes, Box<dyn std::error::Error + Send + Sync>>;
 }
 
 pub struct LocalStorage {
@@ -32,6 +34,14 @@ impl StorageService for LocalStorage {
         file.write_all(&content).await?;
         Ok(format!("file://{}", file_path.to_string_lossy()))
     }
+
+    async fn download_file(&self, path: &str) -> Result<Bytes, Box<dyn std::error::Error + Send + Sync>> {
+        let file_path = match path.strip_prefix("file://") {
+            Some(absolute) => PathBuf::from(absolute),
+            None => self.base_path.join(path),
+        };
+        Ok(Bytes::from(fs::read(file_path).await?))
+    }
 }
 
 // GCS Storage - Simulated for testing without GCP credentials
@@ -62,4 +72,10 @@ impl StorageService for GcsStorage {
         self.local_fallback.upload_file(file_name, content, content_type).await?;
         Ok(format!("gs://{}/{}", self.bucket_name, file_name))
     }
+
+    async fn download_file(&self, path: &str) -> Result<Bytes, Box<dyn std::error::Error + Send + Sync>> {
+        let prefix = format!("gs://{}/", self.bucket_name);
+        let file_name = path.strip_prefix(&prefix).ok_or_else(|| format!("Path is not in bucket {}: {}", self.bucket_name, path))?;
+        self.local_fallback.download_file(file_name).await
+    }
 }
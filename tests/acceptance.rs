@@ -34,11 +34,13 @@ struct AuthResponse {
 
 #[derive(Debug, Deserialize)]
 #[allow(dead_code)]
-struct UploadResponse {
-    file_id: uuid::Uuid,
+struct FileAnalysisResponse {
+    id: uuid::Uuid,
     filename: String,
-    volume_cm3: f64,
-    surface_area_cm2: f64,
+    status: String,
+    analysis_error: Option<String>,
+    volume_cm3: Option<f64>,
+    surface_area_cm2: Option<f64>,
 }
 
 async fn get_auth_token(base_url: &str) -> Result<String> {
@@ -147,15 +149,31 @@ async fn test_upload_all_stl_files_sorted() -> Result<()> {
             assert_eq!(resp.status(), 413, "Expected 413 for file {} of size {}", filename, size);
         } else {
             let status = resp.status();
-            if status != 201 {
+            if status != 202 {
                 let error_text = resp.text().await.unwrap_or_else(|_| "Could not read error".to_string());
-                panic!("Expected 201 for file {} but got {}. Error: {}", filename, status, error_text);
+                panic!("Expected 202 for file {} but got {}. Error: {}", filename, status, error_text);
             }
-            let body: UploadResponse = resp.json().await?;
-            println!("Analyzed {}: Volume={:.2} cm3, Surface Area={:.2} cm2", filename, body.volume_cm3, body.surface_area_cm2);
-            
-            assert!(body.volume_cm3 >= 0.0, "Volume should be non-negative");
-            assert!(body.surface_area_cm2 >= 0.0, "Surface area should be non-negative");
+            let mut body: FileAnalysisResponse = resp.json().await?;
+            assert_eq!(body.status, "PENDING");
+            // Analysis runs in the background; poll until it finishes
+            for _ in 0..600 {
+                if body.status != "PENDING" {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+                body = client.get(format!("{}/api/files/{}/analysis", base_url, body.id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .send()
+                    .await?
+                    .json()
+                    .await?;
+            }
+            assert_ne!(body.status, "FAILED", "Analysis of {} failed: {:?}", filename, body.analysis_error);
+            let (volume, area) = (body.volume_cm3.unwrap(), body.surface_area_cm2.unwrap());
+            println!("Analyzed {}: Volume={:.2} cm3, Surface Area={:.2} cm2", filename, volume, area);
+
+            assert!(volume >= 0.0, "Volume should be non-negative");
+            assert!(area >= 0.0, "Surface area should be non-negative");
         }
     }
 
@@ -1,6 +1,7 @@
 use alpha3d::{create_app, AppState};
+use alpha3d::compute::ComputePool;
 use alpha3d::models::AuthResponse;
-use alpha3d::handlers::files::UploadResponse;
+use alpha3d::handlers::files::FileAnalysisResponse;
 use alpha3d::storage::LocalStorage;
 use axum::{
     body::Body,
@@ -29,21 +30,10 @@ async fn get_test_pool() -> PgPool {
 #[derive(Debug, Deserialize)]
 struct QuoteResponse {
     id: Uuid,
-    estimated_cost: f64,
-    currency: String,
-    breakdown: CostBreakdown,
-}
-
-#[derive(Debug, Deserialize)]
-struct CostBreakdown {
-    material_cost: f64,
-    machine_cost: f64,
-    labor_cost: f64,
 }
 
 #[derive(Debug, Deserialize)]
 struct OrderResponse {
-    id: Uuid,
     status: String,
 }
 
@@ -51,7 +41,7 @@ struct OrderResponse {
 async fn test_create_order_flow() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
     // 1. Signup & Login
@@ -87,7 +77,8 @@ async fn test_create_order_flow() {
     let token = auth_response.token;
 
     // 2. Upload File
-    let stl_content = "solid cube\nfacet normal 0 0 0\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid cube";
+    // Closed tetrahedron, so the file is printable and can be quoted
+    let stl_content = "solid cube\nfacet normal 0 0 -1\nouter loop\nvertex 0 0 0\nvertex 0 1 0\nvertex 1 0 0\nendloop\nendfacet\nfacet normal 0 -1 0\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 0 1\nendloop\nendfacet\nfacet normal -1 0 0\nouter loop\nvertex 0 0 0\nvertex 0 0 1\nvertex 0 1 0\nendloop\nendfacet\nfacet normal 1 1 1\nouter loop\nvertex 1 0 0\nvertex 0 1 0\nvertex 0 0 1\nendloop\nendfacet\nendsolid cube";
     let boundary = "------------------------boundary123";
     let body_data = format!(
         "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"cube.stl\"\r\n\r\n{}\r\n--{}--\r\n",
@@ -106,10 +97,12 @@ async fn test_create_order_flow() {
         )
         .await
         .unwrap();
-    
+
+    assert_eq!(upload_res.status(), StatusCode::ACCEPTED);
     let upload_body = upload_res.into_body().collect().await.unwrap().to_bytes();
-    let upload_response: UploadResponse = serde_json::from_slice(&upload_body).unwrap();
-    let file_id = upload_response.file_id;
+    let upload_response: FileAnalysisResponse = serde_json::from_slice(&upload_body).unwrap();
+    let upload_response = wait_for_analysis(&app, &token, upload_response.id).await;
+    let file_id = upload_response.id;
 
     // 3. Calculate Quote
     let quote_payload = json!({
@@ -168,3 +161,26 @@ async fn test_create_order_flow() {
     
     assert_eq!(order_response.status, "PAID");
 }
+
+/// Waits for the background analysis of an uploaded file to finish.
+async fn wait_for_analysis(app: &axum::Router, token: &str, file_id: Uuid) -> FileAnalysisResponse {
+    for _ in 0..600 {
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/{}/analysis", file_id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let file: FileAnalysisResponse = serde_json::from_slice(&body).unwrap();
+        if file.status != "PENDING" {
+            return file;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("Analysis of {} did not finish", file_id);
+}
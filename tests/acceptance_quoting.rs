@@ -1,6 +1,7 @@
 use alpha3d::{create_app, AppState};
+use alpha3d::compute::ComputePool;
 use alpha3d::models::AuthResponse;
-use alpha3d::handlers::files::UploadResponse;
+use alpha3d::handlers::files::FileAnalysisResponse;
 use alpha3d::storage::LocalStorage;
 use axum::{
     body::Body,
@@ -28,7 +29,6 @@ async fn get_test_pool() -> PgPool {
 
 #[derive(Debug, Deserialize)]
 struct QuoteResponse {
-    id: Uuid,
     estimated_cost: f64,
     currency: String,
     breakdown: CostBreakdown,
@@ -45,7 +45,7 @@ struct CostBreakdown {
 async fn test_create_quote() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
     // 1. Signup & Login to get token
@@ -188,10 +188,11 @@ endsolid cube";
         .await
         .unwrap();
 
-    assert_eq!(upload_res.status(), StatusCode::CREATED);
+    assert_eq!(upload_res.status(), StatusCode::ACCEPTED);
     let upload_body = upload_res.into_body().collect().await.unwrap().to_bytes();
-    let upload_response: UploadResponse = serde_json::from_slice(&upload_body).unwrap();
-    let file_id = upload_response.file_id;
+    let upload_response: FileAnalysisResponse = serde_json::from_slice(&upload_body).unwrap();
+    let upload_response = wait_for_analysis(&app, &token, upload_response.id).await;
+    let file_id = upload_response.id;
 
     // 3. Calculate Quote
     let quote_payload = json!({
@@ -220,6 +221,10 @@ endsolid cube";
 
     assert_eq!(quote_response.currency, "KRW");
     assert!(quote_response.estimated_cost > 0.0);
+    // 50% markup on the itemized costs, each rounded to 0.01
+    let breakdown = &quote_response.breakdown;
+    let base_cost = breakdown.material_cost + breakdown.machine_cost + breakdown.labor_cost;
+    assert!((quote_response.estimated_cost - base_cost * 1.5).abs() < 0.05);
     
     // Volume of 10x10x10 cube is 1000 mm3 = 1 cm3.
     // Wait, 10 units. If units are mm, then 10mm x 10mm x 10mm = 1000 mm3 = 1 cm3.
@@ -227,5 +232,28 @@ endsolid cube";
     // STL usually doesn't have units, but we assume mm usually.
     // My analysis code likely assumes units are consistent.
     // Let's check what volume I get.
-    println!("Volume: {}", upload_response.volume_cm3);
+    println!("Volume: {:?}", upload_response.volume_cm3);
+}
+
+/// Waits for the background analysis of an uploaded file to finish.
+async fn wait_for_analysis(app: &axum::Router, token: &str, file_id: Uuid) -> FileAnalysisResponse {
+    for _ in 0..600 {
+        let response = app.clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/{}/analysis", file_id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let file: FileAnalysisResponse = serde_json::from_slice(&body).unwrap();
+        if file.status != "PENDING" {
+            return file;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("Analysis of {} did not finish", file_id);
 }
@@ -1,4 +1,5 @@
 use alpha3d::{create_app, AppState};
+use alpha3d::compute::ComputePool;
 use alpha3d::storage::LocalStorage;
 use axum::{
     body::Body,
@@ -66,7 +67,7 @@ async fn create_user(app: &axum::Router, email: &str, password: &str) -> String
 async fn test_admin_access_denied_for_user() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
     let email = format!("user_{}@example.com", Uuid::new_v4());
@@ -97,7 +98,7 @@ async fn test_admin_access_denied_for_user() {
 async fn test_admin_list_orders() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
     let email = format!("admin_{}@example.com", Uuid::new_v4());
@@ -124,3 +125,103 @@ async fn test_admin_list_orders() {
 
     assert_eq!(response.status(), StatusCode::OK);
 }
+
+/// Sends a request and returns the status and raw body.
+async fn send(app: &axum::Router, method: &str, uri: &str, token: &str, body: Body, content_type: &str) -> (StatusCode, Vec<u8>) {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", content_type)
+                .body(body)
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let status = response.status();
+    (status, axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec())
+}
+
+/// Uploads a 20 mm cube, quotes it in `material` and orders it; returns the order id.
+async fn order_cube(app: &axum::Router, token: &str, material: &str) -> String {
+    let boundary = "------------------------boundary123";
+    let cube = "v 0 0 0\nv 20 0 0\nv 20 20 0\nv 0 20 0\nv 0 0 20\nv 20 0 20\nv 20 20 20\nv 0 20 20\n\
+                f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8\n";
+    let body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"cube.obj\"\r\n\r\n{}\r\n--{}--\r\n",
+        boundary, cube, boundary
+    );
+    let (status, body) = send(app, "POST", "/api/files/upload", token, Body::from(body), &format!("multipart/form-data; boundary={}", boundary)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let file: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let analysis_uri = format!("/api/files/{}/analysis", file["id"].as_str().unwrap());
+    for _ in 0..600 {
+        let (_, body) = send(app, "GET", &analysis_uri, token, Body::empty(), "application/json").await;
+        let analysis: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        if analysis["status"] != "PENDING" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+
+    let quote = json!({ "file_id": file["id"], "material": material, "color": "Red" });
+    let (status, body) = send(app, "POST", "/api/quotes/calculate", token, Body::from(quote.to_string()), "application/json").await;
+    assert_eq!(status, StatusCode::OK);
+    let quote: serde_json::Value = serde_json::from_slice(&body).unwrap();
+
+    let order = json!({ "quote_id": quote["id"], "shipping_address": { "city": "Seoul" } });
+    let (status, body) = send(app, "POST", "/api/orders", token, Body::from(order.to_string()), "application/json").await;
+    assert_eq!(status, StatusCode::CREATED);
+    let order: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    order["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_admin_generates_gcode_for_printing_orders() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+
+    let email = format!("admin_{}@example.com", Uuid::new_v4());
+    let token = create_user(&app, &email, "password").await;
+    sqlx::query("UPDATE users SET role = 'ADMIN' WHERE email = $1")
+        .bind(&email)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let order_id = order_cube(&app, &token, "PLA").await;
+    let gcode_uri = format!("/api/admin/orders/{}/gcode", order_id);
+
+    // Only once the order is being printed
+    let (status, _) = send(&app, "POST", &gcode_uri, &token, Body::empty(), "application/json").await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let printing = json!({ "status": "PRINTING" }).to_string();
+    let (status, _) = send(&app, "PATCH", &format!("/api/admin/orders/{}/status", order_id), &token, Body::from(printing.clone()), "application/json").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "GET", &gcode_uri, &token, Body::empty(), "application/json").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = send(&app, "POST", &gcode_uri, &token, Body::empty(), "application/json").await;
+    assert_eq!(status, StatusCode::OK);
+    let order: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert!(order["gcode_path"].as_str().unwrap().ends_with("_cube.gcode"));
+    assert!(order["gcode_printer_id"].is_string());
+
+    let (status, body) = send(&app, "GET", &gcode_uri, &token, Body::empty(), "application/json").await;
+    assert_eq!(status, StatusCode::OK);
+    let gcode = String::from_utf8(body).unwrap();
+    assert!(gcode.starts_with(";FLAVOR:"));
+    assert_eq!(gcode.matches(";LAYER:").count(), 100);
+    assert!(gcode.contains("M109 S210") || gcode.contains("EXTRUDER=210"));
+
+    // Resin orders are printed from slices, not G-code
+    let resin_id = order_cube(&app, &token, "RESIN").await;
+    let (status, _) = send(&app, "PATCH", &format!("/api/admin/orders/{}/status", resin_id), &token, Body::from(printing), "application/json").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&app, "POST", &format!("/api/admin/orders/{}/gcode", resin_id), &token, Body::empty(), "application/json").await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
@@ -1,4 +1,5 @@
 use alpha3d::{create_app, AppState};
+use alpha3d::compute::ComputePool;
 use alpha3d::models::{AuthResponse, User};
 use alpha3d::storage::LocalStorage;
 use axum::{
@@ -28,7 +29,7 @@ async fn get_test_pool() -> PgPool {
 async fn test_signup_and_login() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
     let email = format!("test_{}@example.com", Uuid::new_v4());
@@ -1,7 +1,17 @@
 use alpha3d::{create_app, AppState};
+use alpha3d::compute::ComputePool;
 use alpha3d::models::AuthResponse;
-use alpha3d::handlers::files::UploadResponse;
-use alpha3d::storage::LocalStorage;
+use alpha3d::handlers::files::{FileAnalysisResponse, HollowResponse, RepairResponse, TransformResponse, UploadResponse};
+use alpha3d::analysis::drainage::ResinDrainage;
+use alpha3d::analysis::error::AnalysisError;
+use alpha3d::analysis::measure::Measurement;
+use alpha3d::analysis::orientation::OrientationSearch;
+use alpha3d::analysis::section::CrossSection;
+use alpha3d::analysis::slicer::SlicedModel;
+use alpha3d::analysis::thickness::WallThickness;
+use alpha3d::analysis::voxel::VolumeMethod;
+use alpha3d::quoting::QuoteResponse;
+use alpha3d::storage::{LocalStorage, StorageService};
 use axum::{
     body::Body,
     http::{Request, StatusCode},
@@ -11,7 +21,8 @@ use sqlx::PgPool;
 use serde_json::json;
 use uuid::Uuid;
 use http_body_util::BodyExt;
-use std::sync::Arc;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 
 // Helper to create a test pool
 async fn get_test_pool() -> PgPool {
@@ -30,7 +41,7 @@ async fn get_test_pool() -> PgPool {
 async fn test_file_upload_and_analysis() {
     let pool = get_test_pool().await;
     let storage = Arc::new(LocalStorage::new("./test_uploads"));
-    let state = AppState { pool: pool.clone(), storage };
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
     let app = create_app(state);
 
 
@@ -98,13 +109,8 @@ endsolid test";
         .await
         .unwrap();
 
-    // If stl_io fails to parse ASCII from the multipart stream, we might get 400.
-    // Let's check status.
-    assert_eq!(response.status(), StatusCode::CREATED);
-    
-    let body = response.into_body().collect().await.unwrap().to_bytes();
-    let upload_res: UploadResponse = serde_json::from_slice(&body).unwrap();
-    
+    let upload_res = analyzed(&app, &token, response).await;
+
     assert_eq!(upload_res.filename, "test.stl");
     // Volume check: 
     // V = 1/6 * det(v1, v2, v3)
@@ -113,5 +119,1048 @@ endsolid test";
     // V = 1000 / 6 = 166.666 mm3.
     // In cm3: 0.1666...
     // Let's just assert it's > 0.
-    assert!(upload_res.volume_cm3 > 0.0);
+    assert!(upload_res.volume_cm3.unwrap() > 0.0);
+}
+
+/// OBJ cube of the given edge length (mm), made of quads.
+fn cube_obj(size: f64) -> String {
+    let mut obj = String::from("o cube\n");
+    for [x, y, z] in [[0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0], [0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]] {
+        obj.push_str(&format!("v {} {} {}\n", x as f64 * size, y as f64 * size, z as f64 * size));
+    }
+    obj.push_str("f 1 4 3 2\nf 5 6 7 8\nf 1 2 6 5\nf 2 3 7 6\nf 3 4 8 7\nf 4 1 5 8\n");
+    obj
+}
+
+/// OBJ with one axis-aligned box per `(min, max)` pair.
+fn boxes_obj(boxes: &[([f64; 3], [f64; 3])]) -> String {
+    let mut obj = String::new();
+    for (i, (min, max)) in boxes.iter().enumerate() {
+        for [x, y, z] in [[0, 0, 0], [1, 0, 0], [1, 1, 0], [0, 1, 0], [0, 0, 1], [1, 0, 1], [1, 1, 1], [0, 1, 1]] {
+            let pick = |axis: usize, hi: i32| if hi == 1 { max[axis] } else { min[axis] };
+            obj.push_str(&format!("v {} {} {}\n", pick(0, x), pick(1, y), pick(2, z)));
+        }
+        let b = i * 8;
+        for face in [[1, 4, 3, 2], [5, 6, 7, 8], [1, 2, 6, 5], [2, 3, 7, 6], [3, 4, 8, 7], [4, 1, 5, 8]] {
+            obj.push_str(&format!("f {} {} {} {}\n", face[0] + b, face[1] + b, face[2] + b, face[3] + b));
+        }
+    }
+    obj
+}
+
+async fn quote(app: &axum::Router, token: &str, payload: serde_json::Value) -> QuoteResponse {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/quotes/calculate")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+async fn upload(app: &axum::Router, token: &str, filename: &str, content: String) -> axum::response::Response {
+    let boundary = "------------------------boundary123";
+    let body_data = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n{}\r\n--{}--\r\n",
+        boundary, filename, content, boundary
+    );
+
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/files/upload")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", format!("multipart/form-data; boundary={}", boundary))
+                .body(Body::from(body_data))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+async fn get_analysis(app: &axum::Router, token: &str, file_id: Uuid) -> FileAnalysisResponse {
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/analysis", file_id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+/// Checks an upload was accepted and waits for its background analysis to finish.
+async fn analyzed(app: &axum::Router, token: &str, response: axum::response::Response) -> FileAnalysisResponse {
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let accepted: FileAnalysisResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(accepted.status, "PENDING");
+    assert_eq!(accepted.volume_cm3, None);
+
+    for _ in 0..600 {
+        let file = get_analysis(app, token, accepted.id).await;
+        if file.status != "PENDING" {
+            return file;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+    panic!("Analysis of {} did not finish", accepted.id);
+}
+
+async fn get_auth_token(app: &axum::Router) -> String {
+    let email = format!("test_file_{}@example.com", Uuid::new_v4());
+    let password = "password123";
+
+    let _ = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/auth/signup")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "email": email, "password": password }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let login_res = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/auth/login")
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "email": email, "password": password }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    let body = login_res.into_body().collect().await.unwrap().to_bytes();
+    let auth_response: AuthResponse = serde_json::from_slice(&body).unwrap();
+    auth_response.token
+}
+
+#[tokio::test]
+async fn test_obj_upload_detects_format() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+
+    assert_eq!(upload_res.file_format, "OBJ");
+    assert_eq!(upload_res.status, "ANALYZED");
+    assert!(upload_res.mesh_validation.as_ref().unwrap().is_watertight);
+    assert!((upload_res.volume_cm3.unwrap() - 1.0).abs() < 1e-6);
+    assert_eq!(upload_res.volume_method, Some(VolumeMethod::SignedVolume));
+    assert_eq!(upload_res.volume_error_cm3, Some(0.0));
+    assert!((upload_res.surface_area_cm2.unwrap() - 6.0).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_mass_properties_and_stability() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let cube = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+    let mass = cube.mass_properties.expect("cube has mass properties");
+    assert!(mass.center_of_mass.iter().all(|c| (c - 5.0).abs() < 1e-6));
+    assert!((mass.inertia_tensor[0][0] - 200.0 / 12.0).abs() < 1e-6);
+    assert!(mass.stability.is_stable);
+    assert!((mass.stability.tip_angle_deg - 45.0).abs() < 1e-6);
+
+    // A long block resting on one end of a small pedestal falls over
+    let cantilever = boxes_obj(&[
+        ([0.0, 0.0, 0.0], [10.0, 10.0, 2.0]),
+        ([0.0, 0.0, 2.0], [40.0, 10.0, 12.0]),
+    ]);
+    let cantilever = analyzed(&app, &token, upload(&app, &token, "cantilever.obj", cantilever).await).await;
+    let stability = cantilever.mass_properties.expect("cantilever has mass properties").stability;
+    assert!(!stability.is_stable);
+    assert!(stability.com_margin_mm < 0.0);
+    assert_eq!(stability.tip_angle_deg, 0.0);
+}
+
+/// Local storage that fails the first download of every object, like a brief outage.
+struct FlakyStorage {
+    inner: LocalStorage,
+    failed: Mutex<HashSet<String>>,
+}
+
+#[async_trait::async_trait]
+impl StorageService for FlakyStorage {
+    async fn upload_file(&self, file_name: &str, content: bytes::Bytes, content_type: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+        self.inner.upload_file(file_name, content, content_type).await
+    }
+
+    async fn download_file(&self, path: &str) -> Result<bytes::Bytes, Box<dyn std::error::Error + Send + Sync>> {
+        if self.failed.lock().unwrap().insert(path.to_string()) {
+            return Err("storage unavailable".into());
+        }
+        self.inner.download_file(path).await
+    }
+}
+
+#[tokio::test]
+async fn test_storage_failure_is_retried() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(FlakyStorage { inner: LocalStorage::new("./test_uploads"), failed: Mutex::new(HashSet::new()) });
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let file = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+    assert_eq!(file.status, "ANALYZED");
+    assert!((file.volume_cm3.unwrap() - 1.0).abs() < 1e-6);
+
+    let (status, error): (String, Option<String>) = sqlx::query_as("SELECT status, error FROM analysis_jobs WHERE file_id = $1")
+        .bind(file.id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "DONE");
+    assert_eq!(error, None);
+}
+
+#[tokio::test]
+async fn test_unreadable_upload_fails_analysis() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // Recognised as OBJ, but there is nothing to analyze
+    let failed = analyzed(&app, &token, upload(&app, &token, "empty.obj", "o empty\nv 0 0 0\n".to_string()).await).await;
+    assert_eq!(failed.status, "FAILED");
+    assert_eq!(failed.analysis_error.as_deref(), Some("Model contains no faces"));
+    assert_eq!(failed.analysis_failure, Some(AnalysisError::NoFaces));
+    assert_eq!(failed.volume_cm3, None);
+
+    let broken = analyzed(&app, &token, upload(&app, &token, "broken.obj", "v 0 0 0\nv 1 0 0\nf 1 2 3\n".to_string()).await).await;
+    assert_eq!(broken.status, "FAILED");
+    let failure = serde_json::to_value(broken.analysis_failure.unwrap()).unwrap();
+    assert_eq!(failure["code"], "INVALID_SYNTAX");
+    assert_eq!(failure["format"], "OBJ");
+    assert!(failure["detail"].as_str().unwrap().starts_with("line 3:"));
+
+    let response = upload(&app, &token, "notes.txt", "hello".to_string()).await;
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let error = json_error(response).await;
+    assert_eq!(error["code"], "UNSUPPORTED_FORMAT");
+    assert_eq!(error["message"], "Unsupported file format (expected STL, OBJ or 3MF)");
+
+    // Binary STL header promising two triangles, with none following
+    let header = format!("{}\u{2}\0\0\0", "x".repeat(80));
+    let truncated = analyzed(&app, &token, upload(&app, &token, "truncated.stl", header).await).await;
+    assert_eq!(truncated.status, "FAILED");
+    let response = rescale(&app, &token, truncated.id, json!({ "scale": 2.0 })).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let error = json_error(response).await;
+    assert_eq!(error["code"], "TRUNCATED_BINARY");
+    assert_eq!(error["expected_bytes"], 184);
+    assert_eq!(error["actual_bytes"], 84);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/quotes/calculate")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "file_id": failed.id, "material": "PLA", "color": "Red" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+/// Body of a failed request on an unreadable model: its `AnalysisError` plus `message`.
+async fn json_error(response: axum::response::Response) -> serde_json::Value {
+    assert_eq!(response.headers()["content-type"], "application/json");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&body).unwrap()
+}
+
+#[tokio::test]
+async fn test_oversized_model_is_flagged() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // 300mm cube is larger than every seeded printer
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "big.obj", cube_obj(300.0)).await).await;
+    assert_eq!(upload_res.dimensions_mm.unwrap(), [300.0, 300.0, 300.0]);
+    assert_eq!(upload_res.bounding_box.unwrap().max, [300.0, 300.0, 300.0]);
+    assert!(!upload_res.fits_build_volume.unwrap());
+
+    let quote_res = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/quotes/calculate")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red" }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(quote_res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_repair_creates_downloadable_revision() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // Cube with the top face missing
+    let open_cube = cube_obj(10.0).replace("f 5 6 7 8\n", "");
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "open.obj", open_cube).await).await;
+    assert_eq!(upload_res.status, "NEEDS_REPAIR");
+    // Still priced, from a voxel estimate of the volume the walls enclose
+    assert_eq!(upload_res.volume_method, Some(VolumeMethod::Voxel));
+    let error = upload_res.volume_error_cm3.unwrap();
+    assert!(error > 0.0 && error < 0.1);
+    assert!((upload_res.volume_cm3.unwrap() - 1.0).abs() <= error);
+
+    let repair = |token: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/files/{}/repair", upload_res.id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    // Other customers cannot touch the file
+    let other_token = get_auth_token(&app).await;
+    assert_eq!(repair(other_token).await.status(), StatusCode::FORBIDDEN);
+
+    let response = repair(token.clone()).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let repair_res: RepairResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(repair_res.repair_report.filled_holes, 1);
+
+    let repaired = repair_res.repaired_file.expect("repaired revision");
+    assert_eq!(repaired.filename, "open_repaired.stl");
+    assert_eq!(repaired.file_format, "STL");
+    assert_eq!(repaired.status, "ANALYZED");
+    assert!((repaired.volume_cm3 - 1.0).abs() < 1e-6);
+    assert_eq!(repaired.volume_method, VolumeMethod::SignedVolume);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/download", repaired.file_id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let mesh = stl_io::read_stl(&mut std::io::Cursor::new(body.to_vec())).unwrap();
+    // 10 original triangles plus a 4-triangle fan over the square hole
+    assert_eq!(mesh.faces.len(), 14);
+}
+
+#[tokio::test]
+async fn test_quote_includes_support_material() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // 20x20 slab floating 10mm above a 10x10 pillar
+    let model = boxes_obj(&[([5.0, 5.0, 0.0], [15.0, 15.0, 10.0]), ([0.0, 0.0, 20.0], [20.0, 20.0, 30.0])]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "table.obj", model).await).await;
+    assert_eq!(upload_res.status, "ANALYZED");
+
+    let supported = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red" })).await;
+    assert_eq!(supported.supports.overhang_angle_deg, 45.0);
+    assert_eq!(supported.supports.overhang_faces, 2);
+    assert!((supported.supports.support_volume_cm3 - 7.0).abs() < 0.35);
+    assert!(supported.breakdown.support_mass_g > 0.0);
+
+    // Nothing is steeper than 90 degrees, so no supports are charged
+    let unsupported = quote(&app, &token, json!({
+        "file_id": upload_res.id, "material": "PLA", "color": "Red", "overhang_angle": 90.0
+    })).await;
+    assert_eq!(unsupported.supports.overhang_faces, 0);
+    assert_eq!(unsupported.breakdown.support_mass_g, 0.0);
+    assert!(supported.breakdown.material_cost > unsupported.breakdown.material_cost);
+}
+
+#[tokio::test]
+async fn test_quote_in_optimized_orientation() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // Square pyramid standing on its apex
+    let pyramid = "v 0 0 10\nv 40 0 10\nv 40 40 10\nv 0 40 10\nv 20 20 0\nf 1 2 3 4\nf 1 5 2\nf 2 5 3\nf 3 5 4\nf 4 5 1\n";
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "pyramid.obj", pyramid.to_string()).await).await;
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/orientation?material=PLA", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let search: OrientationSearch = serde_json::from_slice(&body).unwrap();
+    assert_eq!(search.best.down, [0.0, 0.0, 1.0]);
+    assert!(!search.alternatives.is_empty());
+
+    let as_uploaded = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red" })).await;
+    assert!(as_uploaded.supports.support_volume_cm3 > 0.0);
+    assert!(as_uploaded.orientation.is_none());
+
+    let optimized = quote(&app, &token, json!({
+        "file_id": upload_res.id, "material": "PLA", "color": "Red", "optimize_orientation": true
+    })).await;
+    assert_eq!(optimized.supports.support_volume_cm3, 0.0);
+    assert_eq!(optimized.orientation.unwrap().down, [0.0, 0.0, 1.0]);
+    assert!(optimized.estimated_cost < as_uploaded.estimated_cost);
+}
+
+#[tokio::test]
+async fn test_thin_walls_warn_per_material() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // 0.6 mm plate: too thin for FDM, fine for resin
+    let plate = boxes_obj(&[([0.0, 0.0, 0.0], [20.0, 20.0, 0.6])]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "plate.obj", plate).await).await;
+
+    let fdm = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red" })).await;
+    let thickness = fdm.wall_thickness.unwrap();
+    assert_eq!(thickness.required_mm, 0.8);
+    assert!(!thickness.is_thick_enough);
+    assert!((thickness.min_mm.unwrap() - 0.6).abs() < 1e-9);
+    assert!(!thickness.thin_regions.is_empty());
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/thickness?material=RESIN", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let resin: WallThickness = serde_json::from_slice(&body).unwrap();
+    assert!(resin.is_thick_enough);
+}
+
+#[tokio::test]
+async fn test_slices_follow_layer_height() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+
+    let slices = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/{}/slices{}", upload_res.id, query))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = slices("?layer_height=0.5").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let sliced: SlicedModel = serde_json::from_slice(&body).unwrap();
+    assert_eq!(sliced.layers.len(), 20);
+    assert!(sliced.layers.iter().all(|layer| (layer.area_mm2 - 100.0).abs() < 1e-9));
+
+    let body = slices("").await.into_body().collect().await.unwrap().to_bytes();
+    let sliced: SlicedModel = serde_json::from_slice(&body).unwrap();
+    assert_eq!(sliced.layers.len(), 50);
+
+    assert_eq!(slices("?layer_height=0").await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_cross_section_and_measurements() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+
+    let section = |query: &'static str| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/{}/section{}", upload_res.id, query))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    // Diagonal cut through the middle: a 10 mm by 10√2 mm rectangle
+    let response = section("?normal=1,0,1").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let cut: CrossSection = serde_json::from_slice(&body).unwrap();
+    assert_eq!(cut.contours.len(), 1);
+    assert!((cut.area_mm2 - 100.0 * 2f64.sqrt()).abs() < 1e-6);
+
+    let response = section("?offset=5&format=svg").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/svg+xml");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(String::from_utf8(body.to_vec()).unwrap().contains("<path"));
+
+    assert_eq!(section("?normal=0,0").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(section("?normal=0,0,0").await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(section("?format=dxf").await.status(), StatusCode::BAD_REQUEST);
+
+    let measure = |payload: serde_json::Value| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/files/{}/measure", upload_res.id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(payload.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let response = measure(json!({ "type": "POINT_TO_POINT", "from": [-1.0, 5.0, 5.0], "to": [10.5, 5.0, 5.0] })).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let width: Measurement = serde_json::from_slice(&body).unwrap();
+    assert!((width.distance_mm - 10.0).abs() < 1e-6);
+
+    let body = measure(json!({ "type": "THICKNESS", "point": [5.0, 5.0, 10.0] })).await.into_body().collect().await.unwrap().to_bytes();
+    let thickness: Measurement = serde_json::from_slice(&body).unwrap();
+    assert!((thickness.distance_mm - 10.0).abs() < 1e-6);
+
+    assert_eq!(measure(json!({ "type": "EXTENT", "direction": [0.0, 0.0, 0.0] })).await.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_quote_follows_infill_and_layer_height() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(30.0)).await).await;
+    let settings = |layer_height: f64, infill: i32| json!({
+        "file_id": upload_res.id, "material": "PLA", "color": "Red",
+        "layer_height": layer_height, "infill_percentage": infill
+    });
+
+    let sparse = quote(&app, &token, settings(0.2, 20)).await;
+    let solid = quote(&app, &token, settings(0.2, 100)).await;
+    let estimate = solid.print_estimate.as_ref().unwrap();
+    assert_eq!(estimate.layer_count, 150);
+    assert!((estimate.filament_volume_cm3 - 27.0).abs() < 0.5);
+    assert!(sparse.print_estimate.unwrap().filament_volume_cm3 < estimate.filament_volume_cm3);
+    assert!(sparse.estimated_cost < solid.estimated_cost);
+
+    let fine = quote(&app, &token, settings(0.1, 20)).await;
+    let coarse = quote(&app, &token, settings(0.3, 20)).await;
+    assert!(fine.breakdown.print_time_hours > coarse.breakdown.print_time_hours);
+    assert!(fine.estimated_cost > coarse.estimated_cost);
+
+    // Layers thicker than the nozzle allows are refused
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/quotes/calculate")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(settings(1.0, 20).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_shells_split_parts_and_voids() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // Two separate parts; the first has a 6 mm cavity modelled as its own box
+    let kit = boxes_obj(&[
+        ([0.0, 0.0, 0.0], [10.0, 10.0, 10.0]),
+        ([20.0, 0.0, 0.0], [40.0, 10.0, 10.0]),
+        ([2.0, 2.0, 2.0], [8.0, 8.0, 8.0]),
+    ]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "kit.obj", kit).await).await;
+
+    assert_eq!(upload_res.shells.len(), 3);
+    assert!(upload_res.shells[2].is_void);
+    assert_eq!(upload_res.shells[2].enclosed_by, Some(0));
+    assert!((upload_res.shells[0].net_volume_cm3 - 0.784).abs() < 1e-9);
+    assert!((upload_res.volume_cm3.unwrap() - 2.784).abs() < 1e-9);
+
+    let whole = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "RESIN", "color": "Grey" })).await;
+    let part = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "RESIN", "color": "Grey", "shell": 1 })).await;
+    assert!(part.estimated_cost < whole.estimated_cost);
+    assert!(part.supports.overhang_faces <= whole.supports.overhang_faces);
+
+    // The cavity would trap resin: 2 mm walls all round, so the drain goes straight down
+    let drainage = whole.resin_drainage.unwrap();
+    assert!(!drainage.is_drainable);
+    assert_eq!(drainage.cavities.len(), 1);
+    assert_eq!(drainage.cavities[0].shell_index, 2);
+    assert!((drainage.trapped_volume_cm3 - 0.216).abs() < 1e-9);
+    let drain = &drainage.cavities[0].drain_holes[0];
+    assert_eq!(drain.direction, [0.0, 0.0, -1.0]);
+    assert!((drain.length_mm - 2.0).abs() < 1e-9);
+    assert!(part.resin_drainage.unwrap().is_drainable);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/drainage", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(serde_json::from_slice::<ResinDrainage>(&body).unwrap(), drainage);
+
+    let fdm = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red" })).await;
+    assert!(fdm.resin_drainage.is_none());
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/quotes/calculate")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "file_id": upload_res.id, "material": "PLA", "color": "Red", "shell": 2 }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_hollowing_resin_parts() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let block = boxes_obj(&[([0.0, 0.0, 0.0], [40.0, 40.0, 40.0])]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "block.obj", block).await).await;
+
+    // 2 mm walls on 1 mm voxels leave a 36 mm cavity
+    let solid = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "RESIN", "color": "Grey" })).await;
+    let hollow = quote(&app, &token, json!({ "file_id": upload_res.id, "material": "RESIN", "color": "Grey", "hollow": {} })).await;
+    assert!(solid.hollow.is_none());
+    let report = hollow.hollow.unwrap();
+    assert_eq!(report.wall_thickness_mm, 2.0);
+    assert!((report.cavity_volume_cm3 - 36.0f64.powi(3) / 1000.0).abs() < 1e-6);
+    assert!(hollow.estimated_cost < solid.estimated_cost);
+    assert!(!hollow.resin_drainage.unwrap().is_drainable);
+
+    let hollow_quote = |material: &str, hollow: serde_json::Value| {
+        let body = json!({ "file_id": upload_res.id, "material": material, "color": "Grey", "hollow": hollow });
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/quotes/calculate")
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body.to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+            .status()
+        }
+    };
+    assert_eq!(hollow_quote("PLA", json!({})).await, StatusCode::BAD_REQUEST);
+    assert_eq!(hollow_quote("RESIN", json!({ "wall_thickness_mm": 0.1 })).await, StatusCode::BAD_REQUEST);
+    assert_eq!(hollow_quote("RESIN", json!({ "wall_thickness_mm": 25.0 })).await, StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/files/{}/hollow", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(json!({ "wall_thickness_mm": 3.0, "lattice_spacing_mm": 10.0 }).to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let hollowed: HollowResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(hollowed.original_file_id, upload_res.id);
+    assert_eq!(hollowed.hollowed_file.filename, "block_hollowed.stl");
+    assert_eq!(hollowed.hollowed_file.status, "ANALYZED");
+    assert!(hollowed.hollowed_file.shells[1].is_void);
+    let expected = 64.0 - hollowed.hollow_report.cavity_volume_cm3;
+    assert!((hollowed.hollowed_file.volume_cm3 - expected).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_self_intersections_are_reported() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // Two boxes overlapping without a boolean union
+    let model = boxes_obj(&[([0.0, 0.0, 0.0], [10.0, 10.0, 10.0]), ([5.0, 2.0, 2.0], [15.0, 8.0, 8.0])]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "overlap.obj", model).await).await;
+    assert_eq!(upload_res.status, "NEEDS_REPAIR");
+    assert!(upload_res.mesh_validation.as_ref().unwrap().is_watertight);
+    assert!(upload_res.mesh_validation.as_ref().unwrap().self_intersections > 0);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/analysis", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let analysis: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    let validation = &analysis["mesh_validation"];
+    assert_eq!(validation["self_intersections"], upload_res.mesh_validation.as_ref().unwrap().self_intersections);
+    let location = validation["intersections"][0]["location"].as_array().unwrap();
+    assert!((location[0].as_f64().unwrap() - 10.0).abs() < 1e-9);
+}
+
+async fn upload_in_units(app: &axum::Router, token: &str, filename: &str, content: String, units: &str) -> FileAnalysisResponse {
+    let boundary = "------------------------boundary123";
+    let body_data = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"units\"\r\n\r\n{}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\r\n{}\r\n--{b}--\r\n",
+        units, filename, content, b = boundary
+    );
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri("/api/files/upload")
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", format!("multipart/form-data; boundary={}", boundary))
+                .body(Body::from(body_data))
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    analyzed(app, token, response).await
+}
+
+async fn rescale(app: &axum::Router, token: &str, file_id: Uuid, payload: serde_json::Value) -> axum::response::Response {
+    app.clone()
+        .oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/files/{}/rescale", file_id))
+                .header("Authorization", format!("Bearer {}", token))
+                .header("Content-Type", "application/json")
+                .body(Body::from(payload.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_inch_model_is_detected_and_rescaled() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    // A one inch cube exported without converting to millimetres
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(1.0)).await).await;
+    assert_eq!(upload_res.units, "mm");
+    assert_eq!(upload_res.suggested_units.as_deref(), Some("in"));
+
+    // A 4 x 2.5 x 3/4 inch bracket: too big for the size rule alone, but on the 1/16 inch grid
+    let bracket = boxes_obj(&[([0.0, 0.0, 0.0], [4.0, 2.5, 0.75])]);
+    let bracket_res = analyzed(&app, &token, upload(&app, &token, "bracket.obj", bracket).await).await;
+    assert_eq!(bracket_res.suggested_units.as_deref(), Some("in"));
+    // Whole millimetres are not read as inches: the 10 mm calibration cube stays put
+    let calibration = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+    assert_eq!(calibration.suggested_units, None);
+
+    let response = rescale(&app, &token, upload_res.id, json!({ "units": "parsec" })).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = rescale(&app, &token, upload_res.id, json!({})).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = rescale(&app, &token, upload_res.id, json!({ "units": "in" })).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let rescaled: UploadResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(rescaled.filename, "cube_rescaled.stl");
+    assert_eq!(rescaled.units, "mm");
+    assert_eq!(rescaled.suggested_units, None);
+    assert!((rescaled.dimensions_mm[0] - 25.4).abs() < 1e-4);
+    assert!((rescaled.volume_cm3 - 2.54f64.powi(3)).abs() < 1e-3);
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/analysis", rescaled.file_id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let analysis: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(analysis["parent_file_id"], json!(upload_res.id));
+    assert_eq!(analysis["revision_kind"], "RESCALE");
+}
+
+#[tokio::test]
+async fn test_transform_creates_revision() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let plate = boxes_obj(&[([0.0, 0.0, 0.0], [20.0, 10.0, 2.0])]);
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "plate.obj", plate).await).await;
+
+    let transform = |steps: serde_json::Value| {
+        let app = app.clone();
+        let token = token.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/files/{}/transform", upload_res.id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(json!({ "steps": steps }).to_string()))
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    // Stand the plate on its long edge, mirror it, double it and put it back on the bed
+    let response = transform(json!([
+        { "type": "ROTATE", "axis": [1.0, 0.0, 0.0], "angle_deg": 90.0 },
+        { "type": "MIRROR", "normal": [1.0, 0.0, 0.0] },
+        { "type": "SCALE", "factors": [2.0, 2.0, 2.0] },
+        { "type": "CENTER" },
+    ])).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let transformed: TransformResponse = serde_json::from_slice(&body).unwrap();
+    assert_eq!(transformed.original_file_id, upload_res.id);
+    let revision = &transformed.transformed_file;
+    assert_eq!(revision.filename, "plate_transformed.stl");
+    assert_eq!(revision.status, "ANALYZED");
+    assert_eq!(revision.dimensions_mm, [40.0, 4.0, 20.0]);
+    assert_eq!(revision.bounding_box.min, [-20.0, -2.0, 0.0]);
+    // Mirroring rewinds the faces, so the volume stays positive
+    assert!((revision.volume_cm3 - 3.2).abs() < 1e-9);
+    assert_eq!(transformed.matrix[3], [0.0, 0.0, 0.0, 1.0]);
+
+    let analysis = get_analysis(&app, &token, revision.file_id).await;
+    assert_eq!(analysis.parent_file_id, Some(upload_res.id));
+    assert_eq!(analysis.revision_kind.as_deref(), Some("TRANSFORM"));
+
+    assert_eq!(transform(json!([])).await.status(), StatusCode::BAD_REQUEST);
+    assert_eq!(transform(json!([{ "type": "SCALE", "factors": [0.0, 1.0, 1.0] }])).await.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_declared_units_scale_analysis() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = upload_in_units(&app, &token, "cube.obj", cube_obj(10.0), "cm").await;
+    assert_eq!(upload_res.units, "cm");
+    assert_eq!(upload_res.suggested_units, None);
+    assert_eq!(upload_res.dimensions_mm.unwrap(), [100.0, 100.0, 100.0]);
+    assert!((upload_res.volume_cm3.unwrap() - 1000.0).abs() < 1e-6);
+
+    // The stored file stays in centimetres; rescaling starts from its millimetre size
+    let response = rescale(&app, &token, upload_res.id, json!({ "scale": 0.5 })).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    let rescaled: UploadResponse = serde_json::from_slice(&body).unwrap();
+    assert!((rescaled.dimensions_mm[0] - 50.0).abs() < 1e-4);
+    assert!((rescaled.volume_cm3 - 125.0).abs() < 1e-3);
+}
+
+#[tokio::test]
+async fn test_thumbnail_is_rendered_on_upload() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+
+    let thumbnail = |token: String| {
+        let app = app.clone();
+        async move {
+            app.oneshot(
+                Request::builder()
+                    .uri(format!("/api/files/{}/thumbnail", upload_res.id))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap()
+        }
+    };
+
+    let other_token = get_auth_token(&app).await;
+    assert_eq!(thumbnail(other_token).await.status(), StatusCode::FORBIDDEN);
+
+    let response = thumbnail(token).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "image/png");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.starts_with(b"\x89PNG\r\n\x1a\n"));
+    // 256x256 from the IHDR chunk
+    assert_eq!(&body[16..24], &[0, 0, 1, 0, 0, 0, 1, 0]);
+}
+
+#[tokio::test]
+async fn test_preview_is_served_as_stl() {
+    let pool = get_test_pool().await;
+    let storage = Arc::new(LocalStorage::new("./test_uploads"));
+    let state = AppState::new(pool.clone(), storage, ComputePool::default());
+    let app = create_app(state);
+    let token = get_auth_token(&app).await;
+
+    let upload_res = analyzed(&app, &token, upload(&app, &token, "cube.obj", cube_obj(10.0)).await).await;
+
+    let response = app.clone()
+        .oneshot(
+            Request::builder()
+                .uri(format!("/api/files/{}/preview", upload_res.id))
+                .header("Authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "model/stl");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    // Small models are only converted, not simplified: the six quads as twelve triangles
+    let mesh = stl_io::read_stl(&mut std::io::Cursor::new(body.to_vec())).unwrap();
+    assert_eq!(mesh.faces.len(), 12);
 }